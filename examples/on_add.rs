@@ -0,0 +1,19 @@
+//! An `on-add` hook filing tasks without a project under `Inbox`, and rejecting tasks without a
+//! description.
+//!
+//! Install by copying the built binary to `~/.task/hooks/on-add-inbox`.
+use std::process::ExitCode;
+
+use tasklib::prelude::*;
+
+fn main() -> ExitCode {
+    run_on_add(|mut task| {
+        if task.description().trim().is_empty() {
+            return Err("A description is required.".to_string());
+        }
+        if task.project().is_empty() {
+            *task.project_mut() = "Inbox".to_string();
+        }
+        Ok(task)
+    })
+}
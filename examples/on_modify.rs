@@ -0,0 +1,20 @@
+//! An `on-modify` hook tagging tasks `deadline` when they're given a due date, and untagging them
+//! when it's removed.
+//!
+//! Install by copying the built binary to `~/.task/hooks/on-modify-deadline`.
+use std::process::ExitCode;
+
+use tasklib::prelude::*;
+
+const TAG: &str = "deadline";
+
+fn main() -> ExitCode {
+    run_on_modify(|original, mut task| {
+        match (original.due(), task.due()) {
+            (None, Some(_)) => task.add_tag(TAG).map_err(|e| e.to_string())?,
+            (Some(_), None) => task.tags_mut().retain(|tag| tag != TAG),
+            _ => {}
+        }
+        Ok(task)
+    })
+}
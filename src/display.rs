@@ -0,0 +1,286 @@
+//! Render every field of a task as a string, for reports and templates.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{offset::Utc, DateTime, TimeZone};
+
+use crate::duration::Duration;
+use crate::udas::UdaValue;
+use crate::{Task, BUILT_IN_FIELDS};
+
+/// Format for dates shown to a user, in their time zone.
+const DISPLAY_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+/// Format for annotation dates, in the user's time zone.
+const ANNOTATION_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Display
+impl Task {
+    /// Every field rendered as a string, keyed by its name as serialized.
+    ///
+    /// * Dates are in `tz`, e.g. `2022-01-31 03:30`
+    /// * Durations are humanized, e.g. `weekly` -> `1 week`
+    /// * Tags are joined by spaces, dependencies by commas, and annotations by newlines
+    /// * UDAs are included by name
+    ///
+    /// Built-in fields that aren't set are empty strings, so every key is present. Also includes
+    /// `age`, how long ago the task was entered as of `now`, e.g. `3d`, and `virtual_tags`,
+    /// joined by spaces. See `Task::virtual_tags`. These take precedence over UDAs of the same
+    /// name.
+    pub fn display_fields<Tz>(&self, tz: &Tz, now: DateTime<Utc>) -> BTreeMap<String, String>
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        let date =
+            |dt: &DateTime<Utc>| dt.with_timezone(tz).format(DISPLAY_DATE_FORMAT).to_string();
+        let date_opt = |dt: Option<&DateTime<Utc>>| dt.map(date).unwrap_or_default();
+
+        let mut fields: BTreeMap<String, String> = BUILT_IN_FIELDS
+            .iter()
+            .map(|field| (field.to_string(), String::new()))
+            .collect();
+        let mut set = |field: &str, value: String| {
+            fields.insert(field.to_string(), value);
+        };
+
+        // UDAs first, so built-in and computed fields take precedence over UDAs of the same name
+        for (name, value) in &self.udas {
+            let value = match value {
+                UdaValue::Date(dt) => date(dt),
+                UdaValue::Duration(d) => d.to_human_string(),
+                UdaValue::String(_) | UdaValue::Numeric(_) => value.to_string(),
+            };
+            set(name, value);
+        }
+        set("id", self.id.map(|id| id.to_string()).unwrap_or_default());
+        set("uuid", self.uuid.to_string());
+        set("description", self.description.clone());
+        set("status", self.status.as_str().to_string());
+        set("project", self.project.clone());
+        set("tags", self.tags.join(" "));
+        set("entry", date(&self.entry));
+        set("modified", date(&self.modified));
+        set("start", date_opt(self.start.as_ref()));
+        set("end", date_opt(self.end.as_ref()));
+        set("scheduled", date_opt(self.scheduled.as_ref()));
+        set("until", date_opt(self.until.as_ref()));
+        set("wait", date_opt(self.wait.as_ref()));
+        set("due", date_opt(self.due.as_ref()));
+        set(
+            "recur",
+            self.recur
+                .as_ref()
+                .map(Duration::to_human_string)
+                .unwrap_or_default(),
+        );
+        set(
+            "depends",
+            self.depends
+                .iter()
+                .map(|uuid| uuid.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        set(
+            "parent",
+            self.parent.map(|uuid| uuid.to_string()).unwrap_or_default(),
+        );
+        set("mask", self.mask.clone().unwrap_or_default());
+        set(
+            "imask",
+            self.imask
+                .map(|imask| imask.to_string())
+                .unwrap_or_default(),
+        );
+        set(
+            "urgency",
+            self.urgency
+                .filter(|urgency| urgency.is_finite())
+                .map(|urgency| format!("{urgency:.2}"))
+                .unwrap_or_default(),
+        );
+        set(
+            "annotations",
+            self.annotations
+                .iter()
+                .map(|annotation| {
+                    format!("{} {}", date(annotation.entry()), annotation.description())
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        set("age", format_age(now - self.entry));
+        set("virtual_tags", self.virtual_tags(now).join(" "));
+        fields
+    }
+    /// Annotations rendered for display, oldest first.
+    ///
+    /// Each is `<date> <description>`, with the date in `tz`, e.g. `2022-01-31 Use rainwater`.
+    /// Given `relative_to`, dates are instead ages as of then, as in `display_fields`, e.g.
+    /// `2d Use rainwater`.
+    pub fn annotations_display<Tz>(
+        &self,
+        tz: &Tz,
+        relative_to: Option<DateTime<Utc>>,
+    ) -> Vec<String>
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        let mut annotations: Vec<_> = self.annotations.iter().collect();
+        annotations.sort_by_key(|annotation| annotation.entry());
+        annotations
+            .into_iter()
+            .map(|annotation| {
+                let date = match relative_to {
+                    Some(now) => format_age(now - *annotation.entry()),
+                    None => annotation
+                        .entry()
+                        .with_timezone(tz)
+                        .format(ANNOTATION_DATE_FORMAT)
+                        .to_string(),
+                };
+                format!("{date} {}", annotation.description())
+            })
+            .collect()
+    }
+}
+
+/// Format an age in its largest whole unit, as Taskwarrior's reports do.
+///
+/// e.g. `45s`, `3min`, `5h`, `3d`, `2w`, `4mo`, `1.5y`
+fn format_age(age: chrono::Duration) -> String {
+    let sign = if age < chrono::Duration::zero() {
+        "-"
+    } else {
+        ""
+    };
+    let seconds = age.num_seconds().unsigned_abs();
+    let days = seconds / (24 * 60 * 60);
+    let age = if days >= 365 {
+        format!("{:.1}y", days as f64 / 365.0)
+    } else if days >= 84 {
+        format!("{}mo", days / 30)
+    } else if days >= 13 {
+        format!("{}w", days / 7)
+    } else if days >= 1 {
+        format!("{days}d")
+    } else if seconds >= 60 * 60 {
+        format!("{}h", seconds / (60 * 60))
+    } else if seconds >= 60 {
+        format!("{}min", seconds / 60)
+    } else {
+        format!("{seconds}s")
+    };
+    format!("{sign}{age}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn display_fields() {
+        let task_str = r#"
+        {
+            "id": 3,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Water the plants",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220202T160000Z",
+            "recur": "P7D",
+            "tags": ["home", "garden"],
+            "annotations": [{"entry": "20220131T090000Z", "description": "Use rainwater"}],
+            "estimate": "PT90M"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        // UDAs are parsed as strings, without their type
+        task.udas_mut().insert(
+            "remaining".to_string(),
+            UdaValue::Duration("PT90M".parse().unwrap()),
+        );
+        let now = Utc.with_ymd_and_hms(2022, 2, 3, 8, 30, 0).unwrap();
+        let tz = FixedOffset::west_opt(5 * 60 * 60).unwrap();
+        let fields = task.display_fields(&tz, now);
+
+        assert_eq!(fields["id"], "3");
+        assert_eq!(fields["status"], "pending");
+        assert_eq!(fields["entry"], "2022-01-31 03:30");
+        assert_eq!(fields["due"], "2022-02-02 11:00");
+        assert_eq!(fields["wait"], "");
+        assert_eq!(fields["recur"], "1 week");
+        assert_eq!(fields["tags"], "home garden");
+        assert_eq!(fields["annotations"], "2022-01-31 04:00 Use rainwater");
+        assert_eq!(fields["estimate"], "PT90M");
+        assert_eq!(fields["remaining"], "90 minutes");
+        assert_eq!(fields["age"], "3d");
+        assert_eq!(
+            fields["virtual_tags"],
+            "ANNOTATED OVERDUE PENDING READY RECURRING TAGGED UDA"
+        );
+        // Every built-in field is present
+        assert!(BUILT_IN_FIELDS
+            .iter()
+            .all(|field| fields.contains_key(*field)));
+
+        // Computed fields take precedence over UDAs
+        task.udas_mut()
+            .insert("age".to_string(), UdaValue::String("old".to_string()));
+        task.udas_mut().insert(
+            "virtual_tags".to_string(),
+            UdaValue::String("X".to_string()),
+        );
+        let fields = task.display_fields(&tz, now);
+        assert_eq!(fields["age"], "3d");
+        assert!(fields["virtual_tags"].starts_with("ANNOTATED"));
+    }
+    #[test]
+    fn annotations_display() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Water the plants",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "annotations": [
+                {"entry": "20220203T090000Z", "description": "Done the roses"},
+                {"entry": "20220201T030000Z", "description": "Use rainwater"}
+            ]
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let tz = FixedOffset::west_opt(5 * 60 * 60).unwrap();
+
+        // Oldest first, in `tz`
+        assert_eq!(
+            task.annotations_display(&tz, None),
+            ["2022-01-31 Use rainwater", "2022-02-03 Done the roses"]
+        );
+        assert_eq!(
+            task.annotations_display(&Utc, None),
+            ["2022-02-01 Use rainwater", "2022-02-03 Done the roses"]
+        );
+
+        let now = Utc.with_ymd_and_hms(2022, 2, 3, 12, 0, 0).unwrap();
+        assert_eq!(
+            task.annotations_display(&tz, Some(now)),
+            ["2d Use rainwater", "3h Done the roses"]
+        );
+    }
+    #[test]
+    fn format_age() {
+        let age = |seconds| super::format_age(chrono::Duration::seconds(seconds));
+        assert_eq!(age(45), "45s");
+        assert_eq!(age(3 * 60), "3min");
+        assert_eq!(age(5 * 60 * 60), "5h");
+        assert_eq!(age(20 * 24 * 60 * 60), "2w");
+        assert_eq!(age(100 * 24 * 60 * 60), "3mo");
+        assert_eq!(age(548 * 24 * 60 * 60), "1.5y");
+        assert_eq!(age(-90), "-1min");
+    }
+}
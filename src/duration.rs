@@ -1,19 +1,23 @@
 //! FIXME: 1 month + 1 month should really be 2 months and not converted to 60 days.
 //!   * Should this really be fixed? `task calc` will calculate `1m + 1m` as 60 days.
-use crate::UdaValue;
+use crate::{UdaConversionError, UdaValue};
 use std::convert::TryFrom;
 use std::fmt;
+use std::iter;
 use std::ops;
 use std::str::FromStr;
 use std::time;
 
+use chrono::{offset::Utc, DateTime, Datelike, Months, Weekday};
+
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
 use nom::character::complete::space0;
+use nom::combinator::eof;
 use nom::combinator::map_res;
 use nom::combinator::opt;
-use nom::error::context;
+use nom::error::{context, Error, ErrorKind};
 use nom::sequence::tuple;
 use nom::IResult;
 use serde::{Deserialize, Serialize};
@@ -21,7 +25,7 @@ use serde::{Deserialize, Serialize};
 /// Special duration types.
 ///
 /// * weekdays
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 enum Special {
     /// Represented by "weekdays" in Taskwarrior's `recur` attribute.
     Weekdays,
@@ -97,6 +101,9 @@ impl Duration {
 }
 
 /// Conversion Methods
+///
+/// Echoes the source if the duration was parsed, so it serializes as it was read, e.g. `5 days`
+/// stays `5 days` and `P1M` isn't turned into days. Otherwise, as `Duration::to_iso_string`.
 impl fmt::Display for Duration {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Source
@@ -105,11 +112,23 @@ impl fmt::Display for Duration {
         if let Some(ref source) = self.source {
             return write!(f, "{}", *source);
         }
+        write!(f, "{}", self.to_iso_string())
+    }
+}
+
+impl Duration {
+    /// ISO-8601 form, ignoring how the duration was written if it was parsed.
+    ///
+    /// Units are kept as they are, without smoothing. Weekdays have no ISO-8601 form and are
+    /// `weekdays`, and a zero duration is `PT0S`. Use `to_string` to keep the source.
+    ///
+    /// e.g. `5 days` -> `P5D`
+    pub fn to_iso_string(&self) -> String {
         // Special Circumstances
         //
         // e.g. "weekdays"
         if let Special::Weekdays = self.special {
-            return write!(f, "weekdays");
+            return "weekdays".to_string();
         }
 
         let mut buffer = String::new();
@@ -135,24 +154,214 @@ impl fmt::Display for Duration {
         if self.seconds > 0 {
             buffer.push_str(&format!("{}S", self.seconds))
         }
-        write!(f, "{buffer}")
+        if buffer == "P" {
+            buffer.push_str("T0S");
+        }
+        buffer
     }
 }
 
 impl Duration {
-    pub fn num_seconds(&self) -> u32 {
-        let seconds_per_minute = 60;
+    /// Total seconds, with 30-day months and 365-day years.
+    ///
+    /// Can't overflow, as each unit is at most `u32::MAX`.
+    pub fn num_seconds(&self) -> u64 {
+        let seconds_per_minute: u64 = 60;
         let seconds_per_hour = 60 * seconds_per_minute;
         let seconds_per_day = 24 * seconds_per_hour;
         let seconds_per_month = 30 * seconds_per_day;
         let seconds_per_year = 365 * seconds_per_day;
 
-        self.seconds
-            + self.minutes * seconds_per_minute
-            + self.hours * seconds_per_hour
-            + self.days * seconds_per_day
-            + self.months * seconds_per_month
-            + self.years * seconds_per_year
+        self.seconds as u64
+            + self.minutes as u64 * seconds_per_minute
+            + self.hours as u64 * seconds_per_hour
+            + self.days as u64 * seconds_per_day
+            + self.months as u64 * seconds_per_month
+            + self.years as u64 * seconds_per_year
+    }
+    /// Smoothed duration of `seconds`.
+    ///
+    /// Returns `None` if there are more days than can be represented.
+    fn from_num_seconds(seconds: u64) -> Option<Self> {
+        Some(Duration {
+            days: u32::try_from(seconds / (24 * 60 * 60)).ok()?,
+            hours: (seconds / (60 * 60) % 24) as u32,
+            minutes: (seconds / 60 % 60) as u32,
+            seconds: (seconds % 60) as u32,
+            ..Default::default()
+        })
+    }
+    /// Smoothed duration of `seconds`, clamped to the longest that can be represented in days.
+    fn from_num_seconds_saturating(seconds: u64) -> Self {
+        Duration::from_num_seconds(seconds).unwrap_or(Duration {
+            days: u32::MAX,
+            hours: 23,
+            minutes: 59,
+            seconds: 59,
+            ..Default::default()
+        })
+    }
+}
+
+impl Duration {
+    /// Human-readable form, e.g. `2 weeks`, as accepted by `Duration::from_human`.
+    ///
+    /// Uses a single unit, the largest that divides the duration evenly:
+    ///
+    /// * Only years, or only months, are kept as `years` or `months`
+    /// * Otherwise, the first of `weeks`, `days`, `hours`, `minutes`, `seconds` that divides the
+    ///   duration, with 30-day months and 365-day years
+    /// * Weekdays are `weekdays`, and a zero duration is `0 seconds`
+    ///
+    /// e.g. P14D -> `2 weeks`, PT90M -> `90 minutes`
+    pub fn to_human_string(&self) -> String {
+        if let Special::Weekdays = self.special {
+            return "weekdays".to_string();
+        }
+        let rest = self.days + self.hours + self.minutes + self.seconds;
+        let (count, unit) = if rest == 0 && self.months == 0 && self.years > 0 {
+            (self.years as u64, "year")
+        } else if rest == 0 && self.years == 0 && self.months > 0 {
+            (self.months as u64, "month")
+        } else {
+            let seconds = self.num_seconds();
+            [
+                (7 * 24 * 60 * 60, "week"),
+                (24 * 60 * 60, "day"),
+                (60 * 60, "hour"),
+                (60, "minute"),
+                (1, "second"),
+            ]
+            .into_iter()
+            .find(|(unit, _)| seconds > 0 && seconds.is_multiple_of(*unit))
+            .map_or((0, "second"), |(unit, name)| (seconds / unit, name))
+        };
+        if count == 1 {
+            format!("{count} {unit}")
+        } else {
+            format!("{count} {unit}s")
+        }
+    }
+}
+
+/// Length of calendar units, for converting a duration to seconds.
+///
+/// The default matches `Duration::num_seconds`: 30-day months and 365-day years.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationUnits {
+    /// Days in a month, e.g. `30.44` for an average Gregorian month.
+    pub days_per_month: f64,
+    /// Days in a year, e.g. `365.25` for an average Julian year.
+    pub days_per_year: f64,
+}
+
+impl Default for DurationUnits {
+    fn default() -> Self {
+        DurationUnits {
+            days_per_month: 30.0,
+            days_per_year: 365.0,
+        }
+    }
+}
+
+/// A unit to round a duration to. See `Duration::round_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+}
+
+impl DurationUnit {
+    /// Length of the unit in seconds.
+    pub fn num_seconds(&self) -> u64 {
+        match self {
+            DurationUnit::Second => 1,
+            DurationUnit::Minute => 60,
+            DurationUnit::Hour => 60 * 60,
+            DurationUnit::Day => 24 * 60 * 60,
+        }
+    }
+}
+
+impl Duration {
+    /// Round to the nearest whole `unit`, e.g. for bucketing in reports.
+    ///
+    /// Operates on `num_seconds()`, so months and years are collapsed into days. Exactly half a
+    /// unit rounds up, e.g. PT30M -> PT1H, so a bucket covers `[n - 0.5, n + 0.5)` units. The
+    /// result is smoothed.
+    ///
+    /// e.g. PT90M -> PT2H
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result has more days than can be represented.
+    pub fn round_to(&self, unit: DurationUnit) -> Duration {
+        let unit = unit.num_seconds();
+        let units = (self.num_seconds() + unit / 2) / unit;
+        Duration::from_num_seconds(units * unit).expect("duration overflow in rounding")
+    }
+}
+
+/// A duration broken down into units, largest first. See `Duration::components`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DurationComponents {
+    pub years: u64,
+    pub months: u64,
+    pub days: u64,
+    pub hours: u64,
+    pub minutes: u64,
+    pub seconds: u64,
+}
+
+impl Duration {
+    /// Break the duration down into units, however it was built.
+    ///
+    /// Rolls `num_seconds()` up into minutes, hours, and days, then 30-day months and 365-day
+    /// years.
+    ///
+    /// e.g. PT9010S -> 2 hours, 30 minutes, 10 seconds
+    pub fn components(&self) -> DurationComponents {
+        self.components_with(&DurationUnits::default())
+    }
+    /// Break the duration down into units, using the given lengths for months and years.
+    ///
+    /// See `Duration::components`.
+    pub fn components_with(&self, units: &DurationUnits) -> DurationComponents {
+        let seconds_per_day = 24 * 60 * 60;
+        // At least a second, so an odd unit can't divide by zero
+        let seconds_per_month =
+            ((units.days_per_month * seconds_per_day as f64).round() as u64).max(1);
+        let seconds_per_year =
+            ((units.days_per_year * seconds_per_day as f64).round() as u64).max(1);
+
+        let seconds = self.num_seconds_with(units);
+        let (years, seconds) = (seconds / seconds_per_year, seconds % seconds_per_year);
+        let (months, seconds) = (seconds / seconds_per_month, seconds % seconds_per_month);
+        DurationComponents {
+            years,
+            months,
+            days: seconds / seconds_per_day,
+            hours: seconds / (60 * 60) % 24,
+            minutes: seconds / 60 % 60,
+            seconds: seconds % 60,
+        }
+    }
+    /// Total seconds, using the given lengths for months and years.
+    ///
+    /// Months and years are each rounded to the nearest second.
+    pub fn num_seconds_with(&self, units: &DurationUnits) -> u64 {
+        let seconds_per_day = 24 * 60 * 60;
+        let seconds_per_month = (units.days_per_month * seconds_per_day as f64).round() as u64;
+        let seconds_per_year = (units.days_per_year * seconds_per_day as f64).round() as u64;
+
+        self.seconds as u64
+            + self.minutes as u64 * 60
+            + self.hours as u64 * 60 * 60
+            + self.days as u64 * seconds_per_day
+            + self.months as u64 * seconds_per_month
+            + self.years as u64 * seconds_per_year
     }
 }
 
@@ -170,55 +379,341 @@ impl Duration {
         self.minutes %= 60;
 
         self.days += self.hours / 24;
-        self.days %= 24;
+        self.hours %= 24;
 
         self.years += self.months / 12;
         self.months %= 12;
     }
 }
 
+/// Dates
+impl Duration {
+    /// Add the duration to a date, keeping to the calendar.
+    ///
+    /// Months and years are added as calendar months, clamped to the end of a shorter month, and
+    /// weekdays skip Saturday and Sunday.
+    ///
+    /// e.g. 2022-01-31 + P1M -> 2022-02-28
+    ///
+    /// Returns `None` if the result is out of range.
+    pub fn add_to(&self, dt: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let months = self.years.checked_mul(12)?.checked_add(self.months)?;
+        let time = chrono::Duration::days(self.days.into())
+            + chrono::Duration::hours(self.hours.into())
+            + chrono::Duration::minutes(self.minutes.into())
+            + chrono::Duration::seconds(self.seconds.into());
+        let mut dt = dt
+            .checked_add_months(Months::new(months))?
+            .checked_add_signed(time)?;
+        if let Special::Weekdays = self.special {
+            while matches!(dt.weekday(), Weekday::Sat | Weekday::Sun) {
+                dt = dt.checked_add_signed(chrono::Duration::days(1))?;
+            }
+        }
+        Some(dt)
+    }
+    /// Add the duration to a date `n` times, keeping to the calendar.
+    ///
+    /// Unlike adding repeatedly, months are counted from `dt`, so the day of the month doesn't
+    /// drift after a shorter month, e.g. 2022-01-31 + 2 * P1M -> 2022-03-31.
+    pub(crate) fn add_times_to(&self, dt: DateTime<Utc>, n: u32) -> Option<DateTime<Utc>> {
+        if self.is_weekdays() {
+            let mut dt = dt;
+            for _ in 0..n {
+                dt = self.add_to(dt)?;
+            }
+            return Some(dt);
+        }
+        let times = Duration {
+            years: self.years.checked_mul(n)?,
+            months: self.months.checked_mul(n)?,
+            ..Default::default()
+        };
+        let dt = times.add_to(dt)?;
+        let seconds = self.days as i64 * 24 * 60 * 60
+            + self.hours as i64 * 60 * 60
+            + self.minutes as i64 * 60
+            + self.seconds as i64;
+        let time = chrono::Duration::try_seconds(seconds.checked_mul(n.into())?)?;
+        dt.checked_add_signed(time)
+    }
+    /// Whether this is `weekdays`, which skips weekends.
+    pub(crate) fn is_weekdays(&self) -> bool {
+        self.special == Special::Weekdays
+    }
+}
+
+/// Add each unit.
+///
+/// # Panics
+///
+/// Panics if a unit overflows. Use `Duration::checked_add` to handle this.
 impl ops::Add for Duration {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        Duration {
-            years: self.years + other.years,
-            months: self.months + other.months,
-            days: self.days + other.days,
-            hours: self.hours + other.hours,
-            minutes: self.minutes + other.minutes,
-            seconds: self.seconds + other.seconds,
+        self.checked_add(other)
+            .expect("duration overflow in addition")
+    }
+}
+
+/// Total durations, e.g. estimates across tasks.
+///
+/// Adds each unit, then smooths the result. Months and years aren't collapsed into days, since
+/// months may have a different number of days, so P1M + P20D stays P1M20D. Comparisons still
+/// collapse them, so it equals P50D.
+///
+/// e.g. [P1D, PT12H, PT12H] -> P2D
+///
+/// # Panics
+///
+/// Panics if a unit overflows, as `Add` does.
+impl iter::Sum for Duration {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let mut total = iter.fold(Duration::default(), |total, d| total + d);
+        total.smooth();
+        total
+    }
+}
+
+/// See `Sum<Duration>`.
+impl<'a> iter::Sum<&'a Duration> for Duration {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.cloned().sum()
+    }
+}
+
+impl Duration {
+    /// Add each unit, or `None` if a unit overflows.
+    ///
+    /// See `Add`.
+    pub fn checked_add(self, other: Duration) -> Option<Duration> {
+        Some(Duration {
+            years: self.years.checked_add(other.years)?,
+            months: self.months.checked_add(other.months)?,
+            days: self.days.checked_add(other.days)?,
+            hours: self.hours.checked_add(other.hours)?,
+            minutes: self.minutes.checked_add(other.minutes)?,
+            seconds: self.seconds.checked_add(other.seconds)?,
             ..Default::default()
-        }
+        })
+    }
+    /// Scale by a factor, or `None` if the result has more days than can be represented.
+    ///
+    /// See `Mul<u32>`.
+    pub fn checked_mul(self, rhs: u32) -> Option<Duration> {
+        self.num_seconds()
+            .checked_mul(rhs as u64)
+            .and_then(Duration::from_num_seconds)
+    }
+    /// Divide by a divisor, or `None` if it's zero or the result has more days than can be
+    /// represented.
+    ///
+    /// See `Div<u32>`.
+    pub fn checked_div(self, rhs: u32) -> Option<Duration> {
+        self.num_seconds()
+            .checked_div(rhs as u64)
+            .and_then(Duration::from_num_seconds)
+    }
+}
+
+/// Scale a duration by a factor.
+///
+/// Operates on `num_seconds()`, so months and years are collapsed into days. The result is
+/// smoothed.
+///
+/// e.g. P2D * 3 -> P6D
+///
+/// # Panics
+///
+/// Panics if the result has more days than can be represented. Use `Duration::checked_mul` to
+/// handle this.
+impl ops::Mul<u32> for Duration {
+    type Output = Self;
+
+    fn mul(self, rhs: u32) -> Self {
+        self.checked_mul(rhs)
+            .expect("duration overflow in multiplication")
+    }
+}
+
+/// Divide a duration by a divisor.
+///
+/// Operates on `num_seconds()`, so months and years are collapsed into days. The result is
+/// truncated toward zero to the nearest second, and smoothed.
+///
+/// e.g. PT1H / 2 -> PT30M
+///
+/// # Panics
+///
+/// Panics if the divisor is zero, or the result has more days than can be represented, such as
+/// for years collapsed into days. Use `Duration::checked_div` to handle these.
+impl ops::Div<u32> for Duration {
+    type Output = Self;
+
+    fn div(self, rhs: u32) -> Self {
+        assert!(rhs != 0, "duration divided by zero");
+        self.checked_div(rhs)
+            .expect("duration overflow in division")
+    }
+}
+
+impl Duration {
+    /// Subtract `other`, or zero if it's longer, rather than underflowing.
+    ///
+    /// Operates on `num_seconds()`, so months and years are collapsed into days. The result is
+    /// smoothed. Never panics: a result with more days than can be represented is clamped to
+    /// `u32::MAX` days, 23 hours, 59 minutes, and 59 seconds.
+    ///
+    /// e.g. P2D - PT12H -> P1DT12H, P1D - P2D -> zero
+    pub fn saturating_sub(self, other: Duration) -> Duration {
+        Duration::from_num_seconds_saturating(
+            self.num_seconds().saturating_sub(other.num_seconds()),
+        )
     }
 }
 
+/// Numeric equality, comparing `num_seconds()`.
+///
+/// Calendar units are collapsed, so `P1M == P30D`. This suits time math, but not recurrence,
+/// where a month is not 30 days. Use `Duration::eq_calendar` to compare units as written.
 impl PartialEq for Duration {
     fn eq(&self, other: &Self) -> bool {
         self.num_seconds() == other.num_seconds()
     }
 }
 
+impl Duration {
+    /// Numeric equality within `tolerance_secs`, comparing `num_seconds()`.
+    ///
+    /// e.g. for elapsed times that differ by a second from rounding.
+    pub fn approx_eq(&self, other: &Duration, tolerance_secs: u32) -> bool {
+        self.num_seconds().abs_diff(other.num_seconds()) <= tolerance_secs.into()
+    }
+    /// Structural equality, comparing each unit without collapsing.
+    ///
+    /// e.g. `P1M` is not `P30D`, and `PT60M` is not `PT1H`. Weekdays only equals weekdays.
+    ///
+    /// Note that parsing turns ISO-8601 years into days, so `P1Y` equals `P365D` here too.
+    pub fn eq_calendar(&self, other: &Self) -> bool {
+        self.years == other.years
+            && self.months == other.months
+            && self.days == other.days
+            && self.hours == other.hours
+            && self.minutes == other.minutes
+            && self.seconds == other.seconds
+            && self.special == other.special
+    }
+}
+
+/// # Panics
+///
+/// Panics if the string isn't a valid duration. Use `str::parse` to handle invalid input.
 impl From<String> for Duration {
     fn from(s: String) -> Self {
         Duration::from_str(&s).expect("string turned into duration")
     }
 }
 
+/// # Panics
+///
+/// Panics if the string isn't a valid duration. Use `str::parse` to handle invalid input.
 impl From<&str> for Duration {
     fn from(s: &str) -> Self {
         Duration::from_str(s).expect("string turned into duration")
     }
 }
 
+/// Parsing
+impl Duration {
+    /// Parse an ISO-8601 duration only, e.g. `P1DT2H`.
+    ///
+    /// The whole string must be a duration.
+    pub fn from_iso(s: &str) -> Result<Self, crate::Error> {
+        Duration::parse_all(s, parse_duration_iso_8601)
+    }
+    /// Parse an ISO-8601 duration only, as the standard allows.
+    ///
+    /// Unlike `from_iso`, weeks must be on their own, e.g. `P2W` but not `P1W2D`.
+    pub fn from_iso_strict(s: &str) -> Result<Self, crate::Error> {
+        let duration = Duration::from_iso(s)?;
+        if s.contains('W') {
+            let weeks_only = s
+                .trim()
+                .strip_prefix('P')
+                .and_then(|s| s.strip_suffix('W'))
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+            if !weeks_only {
+                return Err(crate::Error::Parse(format!(
+                    "invalid duration {s:?}, weeks can't be combined with other designators"
+                )));
+            }
+        }
+        Ok(duration)
+    }
+    /// Parse a human-readable duration only, e.g. `5 days` or `weekly`.
+    ///
+    /// A bare number is seconds, e.g. `3600`. The whole string must be a duration.
+    pub fn from_human(s: &str) -> Result<Self, crate::Error> {
+        Duration::parse_all(s, parse_duration_duration_format)
+    }
+    fn parse_all<'a, F>(s: &'a str, parser: F) -> Result<Self, crate::Error>
+    where
+        F: Fn(&'a str) -> IResult<&'a str, Duration>,
+    {
+        let (rest, mut duration) =
+            parser(s).map_err(|e| crate::Error::Parse(format!("invalid duration {s:?}: {e}")))?;
+        if !rest.trim().is_empty() {
+            return Err(crate::Error::Parse(format!(
+                "invalid duration {s:?}, unexpected {rest:?}"
+            )));
+        }
+        duration.source = Some(s.to_string());
+        Ok(duration)
+    }
+}
+
+/// A duration couldn't be parsed from the start of the input. See `Duration::parse_prefix`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationParseError {
+    input: String,
+    message: String,
+}
+
+impl fmt::Display for DurationParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid duration {:?}: {}", self.input, self.message)
+    }
+}
+
+impl std::error::Error for DurationParseError {}
+
+impl Duration {
+    /// Parse a duration from the start of `s`, returning it and the rest of `s`.
+    ///
+    /// Accepts the same formats as `FromStr`, but allows trailing input.
+    ///
+    /// e.g. `P1D then sleep` -> (`P1D`, ` then sleep`)
+    pub fn parse_prefix(s: &str) -> Result<(Duration, &str), DurationParseError> {
+        let (rest, mut duration) = parse_duration(s).map_err(|e| DurationParseError {
+            input: s.to_string(),
+            message: e.to_string(),
+        })?;
+        duration.source = Some(s[..s.len() - rest.len()].trim_start().to_string());
+        Ok((duration, rest))
+    }
+}
+
+/// Parse either an ISO-8601 or a human-readable duration.
+///
+/// The whole string must be a duration, e.g. `P1D then` is rejected; see `Duration::parse_prefix`
+/// to allow trailing input. See `Duration::from_iso` and `Duration::from_human` to accept only
+/// one format.
 impl FromStr for Duration {
-    type Err = String;
+    type Err = crate::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let source = s.to_string();
-        let (_, mut duration) = parse_duration(s).map_err(|e| format!("{e}"))?;
-        duration.source = Some(source);
-        Ok(duration)
+        Duration::parse_all(s, parse_duration)
     }
 }
 
@@ -236,19 +731,22 @@ impl From<time::Duration> for Duration {
     }
 }
 
-/// FIXME: Add proper error return type
+/// Strings are parsed as durations. Numbers and dates can't be converted.
 impl TryFrom<UdaValue> for Duration {
-    //type Error = Box<dyn Error>;
-    type Error = ();
+    type Error = UdaConversionError;
     fn try_from(uda_value: UdaValue) -> Result<Self, Self::Error> {
         match uda_value {
             UdaValue::String(s) => match s.parse::<Duration>() {
                 Ok(d) => Ok(d),
-                Err(_) => Err(()),
+                Err(error) => Err(UdaConversionError::Unparseable {
+                    value: s,
+                    error: error.to_string(),
+                }),
             },
             UdaValue::Duration(d) => Ok(d),
-            // All other types are not supported
-            _ => Err(()),
+            UdaValue::Numeric(_) | UdaValue::Date(_) => Err(
+                UdaConversionError::IncompatibleVariant(uda_value.variant_name()),
+            ),
         }
     }
 }
@@ -281,6 +779,17 @@ impl<'de> Deserialize<'de> for Duration {
     }
 }
 
+/// Parse digits as a number of `unit`s, failing rather than overflowing.
+///
+/// e.g. `count(input, "2", 7)` -> `14`
+fn count<'a>(input: &'a str, digits: &str, unit: u32) -> Result<u32, nom::Err<Error<&'a str>>> {
+    digits
+        .parse::<u32>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| nom::Err::Error(Error::new(input, ErrorKind::TooLarge)))
+}
+
 /// Parse seconds with a number
 fn parse_seconds_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
     context("seconds", |input: &'a str| {
@@ -297,7 +806,7 @@ fn parse_seconds_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
             tag("s"),
         ))(input)?;
         // Turn into a duration
-        Ok((input, Duration::seconds(seconds.parse::<u32>().unwrap())))
+        Ok((input, Duration::seconds(count(input, seconds, 1)?)))
     })(input)
 }
 
@@ -314,15 +823,36 @@ fn parse_seconds_literal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
     })(input)
 }
 
-/// Parse seconds with or without a number
+/// Parse a bare number as seconds, as Taskwarrior does
+///
+/// The number must be all that's left, so `5x` isn't read as 5 seconds.
 ///
-/// e.g. `5 seconds`, `second`, `sec`
+/// e.g. `0`, `3600`
+fn parse_seconds_bare<'a>(input: &'a str) -> IResult<&'a str, Duration> {
+    context("seconds", |input: &'a str| {
+        // Digit
+        let (input, seconds) = digit1(input)?;
+        // Any amount of space, then nothing else
+        let (input, _) = space0(input)?;
+        let (input, _) = eof(input)?;
+        // Turn into a duration
+        Ok((input, Duration::seconds(count(input, seconds, 1)?)))
+    })(input)
+}
+
+/// Parse seconds with or without a number, or a bare number
+///
+/// e.g. `5 seconds`, `second`, `sec`, `3600`
 fn parse_seconds<'a>(input: &'a str) -> IResult<&'a str, Duration> {
     context("seconds", |input: &'a str| {
         // Any amount of space
         let (input, _) = space0(input)?;
         // Parse using any of the known formats
-        let (input, duration) = alt((parse_seconds_ordinal, parse_seconds_literal))(input)?;
+        let (input, duration) = alt((
+            parse_seconds_ordinal,
+            parse_seconds_literal,
+            parse_seconds_bare,
+        ))(input)?;
         Ok((input, duration))
     })(input)
 }
@@ -337,7 +867,7 @@ fn parse_minutes_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Minutes literal
         let (input, _) = alt((tag("minutes"), tag("minute"), tag("mins"), tag("min")))(input)?;
         // Turn into a duration
-        Ok((input, Duration::minutes(minutes.parse::<u32>().unwrap())))
+        Ok((input, Duration::minutes(count(input, minutes, 1)?)))
     })(input)
 }
 
@@ -377,7 +907,7 @@ fn parse_hours_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Hours literal
         let (input, _) = alt((tag("hours"), tag("hour"), tag("hrs"), tag("hr"), tag("h")))(input)?;
         // Turn into a duration
-        Ok((input, Duration::hours(hours.parse::<u32>().unwrap())))
+        Ok((input, Duration::hours(count(input, hours, 1)?)))
     })(input)
 }
 
@@ -417,7 +947,7 @@ fn parse_days_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Days literal
         let (input, _) = alt((tag("days"), tag("day"), tag("daily"), tag("d")))(input)?;
         // Turn into a duration
-        Ok((input, Duration::days(days.parse::<u32>().unwrap())))
+        Ok((input, Duration::days(count(input, days, 1)?)))
     })(input)
 }
 
@@ -464,7 +994,7 @@ fn parse_weeks_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
             tag("w"),
         ))(input)?;
         // Turn into a duration
-        Ok((input, Duration::weeks(weeks.parse::<u32>().unwrap())))
+        Ok((input, Duration::days(count(input, weeks, 7)?)))
     })(input)
 }
 
@@ -511,7 +1041,7 @@ fn parse_months_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
             tag("m"),
         ))(input)?;
         // Turn into a duration
-        Ok((input, Duration::days(30 * months.parse::<u32>().unwrap())))
+        Ok((input, Duration::days(count(input, months, 30)?)))
     })(input)
 }
 
@@ -560,7 +1090,7 @@ fn parse_years_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
             tag("y"),
         ))(input)?;
         // Turn into a duration
-        Ok((input, Duration::days(365 * years.parse::<u32>().unwrap())))
+        Ok((input, Duration::days(count(input, years, 365)?)))
     })(input)
 }
 
@@ -607,13 +1137,13 @@ fn parse_weekdays<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         let (input, _) = alt((tag("weekdays"),))(input)?;
 
         // If no ordinal, then special should be Special::Weekdays
-        let special: Special = if let None = digit {
+        let special: Special = if digit.is_none() {
             Special::Weekdays
         } else {
             Special::None
         };
 
-        let mut duration = Duration::days(digit.unwrap_or("1").parse::<u32>().unwrap());
+        let mut duration = Duration::days(count(input, digit.unwrap_or("1"), 1)?);
         duration.special = special;
         duration.source = Some(source);
 
@@ -638,7 +1168,7 @@ fn parse_fortnights<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Turn into a duration
         Ok((
             input,
-            Duration::days(14 * digit.unwrap_or("1").parse::<u32>().unwrap()),
+            Duration::days(count(input, digit.unwrap_or("1"), 14)?),
         ))
     })(input)
 }
@@ -661,7 +1191,7 @@ fn parse_sennights<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Turn into a duration
         Ok((
             input,
-            Duration::days(7 * digit.unwrap_or("1").parse::<u32>().unwrap()),
+            Duration::days(count(input, digit.unwrap_or("1"), 7)?),
         ))
     })(input)
 }
@@ -683,7 +1213,7 @@ fn parse_biweekly<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Turn into a duration
         Ok((
             input,
-            Duration::days(14 * digit.unwrap_or("1").parse::<u32>().unwrap()),
+            Duration::days(count(input, digit.unwrap_or("1"), 14)?),
         ))
     })(input)
 }
@@ -705,7 +1235,7 @@ fn parse_bimonthly<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Turn into a duration
         Ok((
             input,
-            Duration::days(61 * digit.unwrap_or("1").parse::<u32>().unwrap()),
+            Duration::days(count(input, digit.unwrap_or("1"), 61)?),
         ))
     })(input)
 }
@@ -732,7 +1262,7 @@ fn parse_quarterly_ordinal<'a>(input: &'a str) -> IResult<&'a str, Duration> {
             tag("q"),
         ))(input)?;
         // Turn into a duration
-        Ok((input, Duration::days(91 * digit.parse::<u32>().unwrap())))
+        Ok((input, Duration::days(count(input, digit, 91)?)))
     })(input)
 }
 
@@ -781,7 +1311,7 @@ fn parse_semiannual<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Turn into a duration
         Ok((
             input,
-            Duration::days(183 * digit.unwrap_or("1").parse::<u32>().unwrap()),
+            Duration::days(count(input, digit.unwrap_or("1"), 183)?),
         ))
     })(input)
 }
@@ -802,7 +1332,7 @@ fn parse_annual<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Turn into a duration
         Ok((
             input,
-            Duration::days(365 * digit.unwrap_or("1").parse::<u32>().unwrap()),
+            Duration::days(count(input, digit.unwrap_or("1"), 365)?),
         ))
     })(input)
 }
@@ -823,7 +1353,7 @@ fn parse_biannual<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Turn into a duration
         Ok((
             input,
-            Duration::days(730 * digit.unwrap_or("1").parse::<u32>().unwrap()),
+            Duration::days(count(input, digit.unwrap_or("1"), 730)?),
         ))
     })(input)
 }
@@ -842,7 +1372,7 @@ fn parse_biyearly<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         // Turn into a duration
         Ok((
             input,
-            Duration::days(730 * digit.unwrap_or("1").parse::<u32>().unwrap()),
+            Duration::days(count(input, digit.unwrap_or("1"), 730)?),
         ))
     })(input)
 }
@@ -885,6 +1415,14 @@ fn parse_duration_iso_8601<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         let (input, _) = space0(input)?;
         // Literal `P`
         let (input, _) = tag("P")(input)?;
+        // Parse the optional week: `1W`
+        //
+        // Strictly, weeks can't be combined with other designators, but some tools emit `P1W2D`.
+        // See `Duration::from_iso_strict`.
+        let (input, weeks) = opt(map_res(
+            tuple((digit1, tag("W"))),
+            |(weeks, _): (&str, &str)| weeks.parse::<u32>(),
+        ))(input)?;
         // Parse the optional year: `1Y`
         let (input, years) = opt({
             map_res(tuple((digit1, tag("Y"))), |(years, _): (&str, &str)| {
@@ -922,23 +1460,69 @@ fn parse_duration_iso_8601<'a>(input: &'a str) -> IResult<&'a str, Duration> {
         ))(input)?;
 
         // Turn into a duration
-        Ok((
-            input,
-            Duration::days(days.unwrap_or(0))
-                + Duration::days(years.unwrap_or(0) * 365)
-                + Duration::months(months.unwrap_or(0))
-                + Duration::hours(hours.unwrap_or(0))
-                + Duration::minutes(minutes.unwrap_or(0))
-                + Duration::seconds(seconds.unwrap_or(0)),
-        ))
+        let too_large = || nom::Err::Error(Error::new(input, ErrorKind::TooLarge));
+        let duration = [
+            Duration::days(weeks.unwrap_or(0).checked_mul(7).ok_or_else(too_large)?),
+            Duration::days(years.unwrap_or(0).checked_mul(365).ok_or_else(too_large)?),
+            Duration::months(months.unwrap_or(0)),
+            Duration::hours(hours.unwrap_or(0)),
+            Duration::minutes(minutes.unwrap_or(0)),
+            Duration::seconds(seconds.unwrap_or(0)),
+        ]
+        .into_iter()
+        .try_fold(Duration::days(days.unwrap_or(0)), Duration::checked_add)
+        .ok_or_else(too_large)?;
+        Ok((input, duration))
     })(input)
 }
 
+/// Parse a canonical ISO-8601 duration without nom
+///
+/// Only accepts input made entirely of `P[nY][nM][nD][T[nH][nM][nS]]`, with the designators in
+/// order. Anything else returns `None` and is left to `parse_duration_iso_8601`, which gives the
+/// same result for every input accepted here.
+fn parse_duration_iso_8601_fast(input: &str) -> Option<Duration> {
+    /// Parse `<digits><designator>` pairs, with designators in the given order.
+    fn fields(mut input: &str, designators: [u8; 3]) -> Option<[u32; 3]> {
+        let mut values = [0; 3];
+        let mut next = 0;
+        while !input.is_empty() {
+            let digits = input.bytes().take_while(u8::is_ascii_digit).count();
+            let designator = *input.as_bytes().get(digits)?;
+            let index = next + designators[next..].iter().position(|d| *d == designator)?;
+            if digits == 0 {
+                return None;
+            }
+            values[index] = input[..digits].parse().ok()?;
+            next = index + 1;
+            input = &input[digits + 1..];
+        }
+        Some(values)
+    }
+
+    let input = input.strip_prefix('P')?;
+    let (date, time) = input.split_once('T').unwrap_or((input, ""));
+    let [years, months, days] = fields(date, *b"YMD")?;
+    let [hours, minutes, seconds] = fields(time, *b"HMS")?;
+    Some(Duration {
+        months,
+        days: years.checked_mul(365)?.checked_add(days)?,
+        hours,
+        minutes,
+        seconds,
+        ..Default::default()
+    })
+}
+
 /// Combine both duration parsers into one
 pub fn parse_duration<'a>(input: &'a str) -> IResult<&'a str, Duration> {
     context("duration", |input: &'a str| {
         // Any amount of space
         let (input, _) = space0(input)?;
+        // Fast path for the canonical ISO-8601 format, as exported by Taskwarrior
+        if let Some(duration) = parse_duration_iso_8601_fast(input) {
+            return Ok((&input[input.len()..], duration));
+        }
         // Parse using any of the known formats
         let (input, duration) =
             alt((parse_duration_iso_8601, parse_duration_duration_format))(input)?;
@@ -949,6 +1533,7 @@ pub fn parse_duration<'a>(input: &'a str) -> IResult<&'a str, Duration> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn seconds() {
@@ -978,6 +1563,27 @@ mod tests {
         assert_eq!(duration, Duration::seconds(5));
     }
     #[test]
+    fn bare_seconds() {
+        assert_eq!("0".parse::<Duration>().unwrap(), Duration::seconds(0));
+        assert_eq!("0".parse::<Duration>().unwrap().num_seconds(), 0);
+        assert_eq!("3600".parse::<Duration>().unwrap(), Duration::hours(1));
+        assert_eq!(
+            Duration::from_human("3600 ").unwrap(),
+            Duration::seconds(3600)
+        );
+        assert_eq!("3600".parse::<Duration>().unwrap().to_string(), "3600");
+        // Only a whole number
+        assert!(Duration::from_human("5x").is_err());
+        assert!("99999999999".parse::<Duration>().is_err());
+        assert!(Duration::from_iso("3600").is_err());
+        // ISO-8601 is unaffected
+        assert_eq!("P1D".parse::<Duration>().unwrap(), Duration::days(1));
+        assert!("P1D"
+            .parse::<Duration>()
+            .unwrap()
+            .eq_calendar(&Duration::days(1)));
+    }
+    #[test]
     fn seconds_spaces() {
         let input = "5seconds";
         let (input, duration) = parse_seconds(input).unwrap();
@@ -2085,6 +2691,57 @@ mod tests {
                 + Duration::seconds(50)
         );
     }
+    #[test]
+    fn iso_8601_fast_path() {
+        let inputs = [
+            "P",
+            "PT",
+            "P1Y",
+            "P1M",
+            "P1D",
+            "P10D",
+            "P01D",
+            "P1Y2M",
+            "P1Y3D",
+            "P2M3D",
+            "P1DT",
+            "PT50S",
+            "PT40M",
+            "PT1H",
+            "PT40M50S",
+            "PT12H40M50S",
+            "P1Y2M3DT12H40M50S",
+            "P1000D",
+            "P4294967295D",
+        ];
+        for input in inputs {
+            let (rest, slow) = parse_duration_iso_8601(input).unwrap();
+            assert_eq!(rest, "", "{input}");
+            let fast = parse_duration_iso_8601_fast(input).unwrap();
+            assert_eq!(format!("{fast:?}"), format!("{slow:?}"), "{input}");
+        }
+
+        // Left to the full parser
+        let inputs = [
+            "P1H",
+            "P1D1Y",
+            "P1Y1Y",
+            "PT1D",
+            "P1",
+            "PY",
+            "P1DT1H2D",
+            "P4294967296D",
+            "P4294967295Y",
+            "1 day",
+            "weekly",
+            "",
+        ];
+        for input in inputs {
+            assert!(parse_duration_iso_8601_fast(input).is_none(), "{input}");
+        }
+        assert_eq!(parse_duration("P1H").unwrap().1, Duration::hours(1));
+        assert_eq!(parse_duration(" P1D").unwrap(), ("", Duration::days(1)));
+    }
     /// Test the forward-facing parse.
     #[test]
     fn duration() {
@@ -2165,6 +2822,343 @@ mod tests {
 
         assert_eq!(&elapsed.to_string(), "PT2H");
     }
+    #[test]
+    fn add() {
+        assert_eq!(
+            Duration::days(1).checked_add(Duration::hours(2)),
+            Some("P1DT2H".parse().unwrap())
+        );
+        assert_eq!(
+            Duration::days(u32::MAX).checked_add(Duration::days(1)),
+            None
+        );
+        assert!("P1Y4294967295D".parse::<Duration>().is_err());
+        assert!("P1W4294967295D".parse::<Duration>().is_err());
+    }
+    #[test]
+    #[should_panic(expected = "duration overflow in addition")]
+    fn add_overflow() {
+        let _ = Duration::days(u32::MAX) + Duration::days(1);
+    }
+    #[test]
+    #[should_panic(expected = "duration overflow in addition")]
+    fn sum_overflow() {
+        let _: Duration = [Duration::days(u32::MAX), Duration::days(1)].iter().sum();
+    }
+    #[test]
+    fn multiply() {
+        let duration: Duration = "P2D".into();
+        assert_eq!(duration.clone() * 3, Duration::days(6));
+        assert_eq!((duration * 3).to_string(), "P6D".to_string());
+
+        let duration = Duration::hours(20) * 3;
+        assert_eq!(duration.to_string(), "P2DT12H".to_string());
+
+        // Checked
+        assert_eq!(Duration::days(2).checked_mul(3), Some(Duration::days(6)));
+        assert_eq!(Duration::days(u32::MAX).checked_mul(2), None);
+        assert_eq!(Duration::years(u32::MAX).checked_mul(u32::MAX), None);
+    }
+    #[test]
+    fn sum() {
+        let durations: Vec<Duration> = ["P1D", "PT12H", "PT12H"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let total: Duration = durations.iter().sum();
+        assert_eq!(total.to_string(), "P2D");
+        assert_eq!(durations.into_iter().sum::<Duration>(), Duration::days(2));
+
+        // Months aren't collapsed
+        let total: Duration = [Duration::months(1), Duration::days(20)].into_iter().sum();
+        assert_eq!(total.to_string(), "P1M20D");
+        assert_eq!(total, Duration::days(50));
+
+        let total: Duration = std::iter::empty::<Duration>().sum();
+        assert_eq!(total, Duration::seconds(0));
+    }
+    #[test]
+    fn components() {
+        assert_eq!(
+            Duration::seconds(9010).components(),
+            DurationComponents {
+                hours: 2,
+                minutes: 30,
+                seconds: 10,
+                ..Default::default()
+            }
+        );
+        // However it was built
+        assert_eq!(
+            Duration::minutes(150).components(),
+            (Duration::hours(2) + Duration::minutes(30)).components()
+        );
+        let duration = Duration::years(1) + Duration::months(2) + Duration::days(3);
+        assert_eq!(
+            duration.components(),
+            DurationComponents {
+                years: 1,
+                months: 2,
+                days: 3,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Duration::days(400).components(),
+            DurationComponents {
+                years: 1,
+                months: 1,
+                days: 5,
+                ..Default::default()
+            }
+        );
+
+        let units = DurationUnits {
+            days_per_month: 30.5,
+            days_per_year: 365.0,
+        };
+        assert_eq!(
+            Duration::days(61).components_with(&units),
+            DurationComponents {
+                months: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Duration::default().components(),
+            DurationComponents::default()
+        );
+    }
+    #[test]
+    fn round_to() {
+        assert_eq!(
+            Duration::minutes(90).round_to(DurationUnit::Hour),
+            Duration::hours(2)
+        );
+        // Half rounds up
+        assert_eq!(
+            Duration::minutes(30).round_to(DurationUnit::Hour),
+            Duration::hours(1)
+        );
+        assert_eq!(
+            Duration::minutes(29).round_to(DurationUnit::Hour),
+            Duration::hours(0)
+        );
+        assert_eq!(
+            (Duration::days(1) + Duration::hours(11)).round_to(DurationUnit::Day),
+            Duration::days(1)
+        );
+        assert_eq!(
+            Duration::hours(12).round_to(DurationUnit::Day).to_string(),
+            "P1D"
+        );
+        assert_eq!(
+            Duration::seconds(90).round_to(DurationUnit::Minute),
+            Duration::minutes(2)
+        );
+        assert_eq!(
+            Duration::seconds(7).round_to(DurationUnit::Second),
+            Duration::seconds(7)
+        );
+        // Months are collapsed
+        assert_eq!(
+            Duration::months(1).round_to(DurationUnit::Day).to_string(),
+            "P30D"
+        );
+    }
+    #[test]
+    fn approx_eq() {
+        let a = Duration::minutes(90);
+        let b = Duration::minutes(90) + Duration::seconds(1);
+        assert!(a.approx_eq(&b, 2));
+        assert!(b.approx_eq(&a, 1));
+        assert!(!a.approx_eq(&b, 0));
+        assert!(a.approx_eq(&a, 0));
+        // Collapsed, as with `==`
+        assert!(Duration::months(1).approx_eq(&Duration::days(30), 0));
+    }
+    #[test]
+    fn to_iso_string() {
+        let duration = "5 days".parse::<Duration>().unwrap();
+        assert_eq!(duration.to_iso_string(), "P5D");
+        assert_eq!(duration.to_string(), "5 days");
+
+        let duration = "P1M".parse::<Duration>().unwrap();
+        assert_eq!(duration.to_iso_string(), "P1M");
+        assert_eq!(Duration::hours(36).to_iso_string(), "PT36H");
+        assert_eq!(Duration::default().to_iso_string(), "PT0S");
+        assert_eq!(Duration::default().to_string(), "PT0S");
+        assert_eq!(
+            "weekdays".parse::<Duration>().unwrap().to_iso_string(),
+            "weekdays"
+        );
+    }
+    #[test]
+    fn saturating_sub() {
+        let day: Duration = "P1D".into();
+        let two_days: Duration = "P2D".into();
+        assert_eq!(
+            day.clone().saturating_sub(two_days.clone()),
+            Duration::default()
+        );
+        assert_eq!(
+            two_days.saturating_sub(Duration::hours(12)).to_string(),
+            "P1DT12H"
+        );
+        assert_eq!(day.clone().saturating_sub(day), Duration::default());
+        // Clamped
+        let max = Duration::years(u32::MAX).saturating_sub(Duration::default());
+        assert_eq!(max.to_iso_string(), format!("P{}DT23H59M59S", u32::MAX));
+        // Collapsed
+        assert_eq!(
+            Duration::months(1).saturating_sub(Duration::days(1)),
+            Duration::days(29)
+        );
+    }
+    #[test]
+    fn divide() {
+        let duration: Duration = "PT1H".into();
+        assert_eq!(duration.clone() / 2, Duration::minutes(30));
+        assert_eq!((duration / 2).to_string(), "PT30M".to_string());
+
+        // Truncates toward zero
+        let duration = Duration::seconds(5) / 2;
+        assert_eq!(duration, Duration::seconds(2));
+
+        // Checked
+        assert_eq!(
+            Duration::hours(1).checked_div(2),
+            Some(Duration::minutes(30))
+        );
+        assert_eq!(Duration::hours(1).checked_div(0), None);
+        assert_eq!(Duration::years(u32::MAX).checked_div(1), None);
+    }
+    #[test]
+    #[should_panic(expected = "duration divided by zero")]
+    fn divide_by_zero() {
+        let _ = Duration::hours(1) / 0;
+    }
+    #[test]
+    fn num_seconds_with() {
+        let duration = Duration::years(1) + Duration::months(2) + Duration::days(3);
+        assert_eq!(
+            duration.num_seconds_with(&DurationUnits::default()),
+            duration.num_seconds()
+        );
+
+        let units = DurationUnits {
+            days_per_month: 30.44,
+            days_per_year: 365.25,
+        };
+        assert_eq!(Duration::months(1).num_seconds_with(&units), 2_630_016);
+        assert_eq!(Duration::years(1).num_seconds_with(&units), 31_557_600);
+        assert_eq!(
+            duration.num_seconds_with(&units),
+            31_557_600 + 2 * 2_630_016 + 3 * 86_400
+        );
+        assert_eq!(Duration::hours(1).num_seconds_with(&units), 3600);
+    }
+    #[test]
+    fn num_seconds_large() {
+        assert_eq!(Duration::years(200).num_seconds(), 6_307_200_000);
+        let max = Duration::years(u32::MAX) + Duration::months(u32::MAX) + Duration::days(u32::MAX);
+        assert_eq!(
+            max.num_seconds(),
+            u32::MAX as u64 * (365 + 30 + 1) * 24 * 60 * 60
+        );
+        assert_eq!(Duration::years(200), Duration::days(73_000));
+        assert_eq!((Duration::years(200) / 2).to_string(), "P36500D");
+        assert_eq!((Duration::years(100) * 2).to_string(), "P73000D");
+    }
+    #[test]
+    fn to_human_string() {
+        assert_eq!(Duration::days(14).to_human_string(), "2 weeks");
+        assert_eq!(Duration::days(1).to_human_string(), "1 day");
+        assert_eq!(Duration::days(10).to_human_string(), "10 days");
+        assert_eq!(Duration::minutes(90).to_human_string(), "90 minutes");
+        assert_eq!(Duration::hours(48).to_human_string(), "2 days");
+        assert_eq!(Duration::seconds(61).to_human_string(), "61 seconds");
+        assert_eq!(Duration::months(3).to_human_string(), "3 months");
+        assert_eq!(Duration::years(1).to_human_string(), "1 year");
+        assert_eq!(Duration::seconds(0).to_human_string(), "0 seconds");
+        assert_eq!(Duration::from("weekdays").to_human_string(), "weekdays");
+        // Parses back to the same duration
+        for duration in [
+            Duration::days(14),
+            Duration::minutes(90),
+            Duration::months(3),
+            Duration::years(2),
+            Duration::days(1) + Duration::hours(1),
+        ] {
+            let human = duration.to_human_string();
+            assert_eq!(Duration::from_human(&human).unwrap(), duration, "{human}");
+        }
+    }
+    #[test]
+    fn from_iso_and_human() {
+        assert_eq!(Duration::from_iso("P1D").unwrap(), Duration::days(1));
+        assert_eq!(Duration::from_iso("PT2H").unwrap().to_string(), "PT2H");
+        assert!(Duration::from_iso("weekly").is_err());
+        assert!(Duration::from_iso("1 day").is_err());
+        assert!(Duration::from_iso("P1Dx").is_err());
+
+        // Weeks, combined leniently
+        assert_eq!(Duration::from_iso("P2W").unwrap(), Duration::days(14));
+        assert_eq!(Duration::from_iso("P1W2D").unwrap(), Duration::days(9));
+        assert_eq!("P1W2D".parse::<Duration>().unwrap(), Duration::days(9));
+        assert_eq!(
+            Duration::from_iso("P1WT12H").unwrap(),
+            Duration::days(7) + Duration::hours(12)
+        );
+        assert_eq!(Duration::from_iso("P1W2D").unwrap().to_string(), "P1W2D");
+        assert!(Duration::from_iso("P1D1W").is_err());
+        assert!(Duration::from_iso("P4294967295W").is_err());
+
+        assert_eq!(
+            Duration::from_iso_strict("P2W").unwrap(),
+            Duration::days(14)
+        );
+        assert_eq!(
+            Duration::from_iso_strict("P1Y2D").unwrap(),
+            Duration::days(367)
+        );
+        assert!(Duration::from_iso_strict("P1W2D").is_err());
+        assert!(Duration::from_iso_strict("P1WT12H").is_err());
+        assert!(Duration::from_iso_strict("weekly").is_err());
+
+        assert_eq!(Duration::from_human("5 days").unwrap(), Duration::days(5));
+        assert_eq!(Duration::from_human("weekly").unwrap(), Duration::weeks(1));
+        assert_eq!(
+            Duration::from_human("weekly").unwrap().to_string(),
+            "weekly"
+        );
+        assert!(Duration::from_human("P1D").is_err());
+        assert!(Duration::from_human("5 days later").is_err());
+
+        // Both are accepted by `FromStr`
+        assert_eq!("P1D".parse::<Duration>().unwrap(), Duration::days(1));
+        assert_eq!("weekly".parse::<Duration>().unwrap(), Duration::weeks(1));
+    }
+    #[test]
+    fn equality() {
+        let month: Duration = "P1M".into();
+        let thirty_days: Duration = "P30D".into();
+        // Numeric
+        assert_eq!(month, thirty_days);
+        assert_eq!(Duration::hours(1), Duration::minutes(60));
+        // Calendar
+        assert!(!month.eq_calendar(&thirty_days));
+        assert!(!Duration::hours(1).eq_calendar(&Duration::minutes(60)));
+        assert!(!Duration::years(1).eq_calendar(&Duration::days(365)));
+        assert!(month.eq_calendar(&Duration::months(1)));
+        assert!(Duration::hours(1).eq_calendar(&Duration::hours(1)));
+        // Source doesn't matter
+        assert!(Duration::from("PT1H").eq_calendar(&Duration::hours(1)));
+
+        let weekdays: Duration = "weekdays".into();
+        assert!(weekdays.eq_calendar(&"weekdays".into()));
+        assert!(!weekdays.eq_calendar(&Duration::days(1)));
+    }
     /// Verify that the deserialization matches the serialization, unless math is done.
     #[test]
     fn source() {
@@ -2194,4 +3188,83 @@ mod tests {
             "P1M30D".to_string()
         );
     }
+    #[test]
+    fn try_from_uda() {
+        let duration = Duration::try_from(UdaValue::String("PT2H".to_string())).unwrap();
+        assert_eq!(duration, Duration::hours(2));
+
+        match Duration::try_from(UdaValue::String("soon".to_string())) {
+            Err(UdaConversionError::Unparseable { value, error }) => {
+                assert_eq!(value, "soon");
+                assert!(!error.is_empty());
+            }
+            other => panic!("expected unparseable, got {other:?}"),
+        }
+        let e = Duration::try_from(UdaValue::Numeric(2.0)).unwrap_err();
+        assert_eq!(e, UdaConversionError::IncompatibleVariant("Numeric"));
+        assert_eq!(e.to_string(), "cannot convert a Numeric UDA");
+        let e = Duration::try_from(UdaValue::Date(chrono::Utc::now())).unwrap_err();
+        assert_eq!(e, UdaConversionError::IncompatibleVariant("Date"));
+    }
+    #[test]
+    fn parse_prefix() {
+        let (duration, rest) = Duration::parse_prefix("P1D then sleep").unwrap();
+        assert_eq!(duration, Duration::days(1));
+        assert_eq!(duration.to_string(), "P1D");
+        assert_eq!(rest, " then sleep");
+
+        let (duration, rest) = Duration::parse_prefix(" 2 weeks, then stop").unwrap();
+        assert_eq!(duration, Duration::weeks(2));
+        assert_eq!(duration.to_string(), "2 weeks");
+        assert_eq!(rest, ", then stop");
+
+        let (_, rest) = Duration::parse_prefix("weekly").unwrap();
+        assert_eq!(rest, "");
+
+        // Unlike `FromStr`
+        assert!("P1D then sleep".parse::<Duration>().is_err());
+
+        let e = Duration::parse_prefix("then P1D").unwrap_err();
+        assert!(
+            e.to_string().starts_with("invalid duration \"then P1D\""),
+            "{e}"
+        );
+    }
+    #[test]
+    fn add_to() {
+        let dt = Utc.with_ymd_and_hms(2022, 1, 31, 8, 30, 0).unwrap();
+        assert_eq!(
+            Duration::months(1).add_to(dt),
+            Some(Utc.with_ymd_and_hms(2022, 2, 28, 8, 30, 0).unwrap())
+        );
+        assert_eq!(
+            "P1Y2DT1H".parse::<Duration>().unwrap().add_to(dt),
+            Some(Utc.with_ymd_and_hms(2023, 2, 2, 9, 30, 0).unwrap())
+        );
+        // 2022-02-04 is a Friday
+        let friday = Utc.with_ymd_and_hms(2022, 2, 4, 8, 30, 0).unwrap();
+        assert_eq!(
+            "weekdays".parse::<Duration>().unwrap().add_to(friday),
+            Some(Utc.with_ymd_and_hms(2022, 2, 7, 8, 30, 0).unwrap())
+        );
+        assert_eq!(Duration::years(u32::MAX).add_to(dt), None);
+
+        // No drift
+        assert_eq!(
+            Duration::months(1).add_times_to(dt, 2),
+            Some(Utc.with_ymd_and_hms(2022, 3, 31, 8, 30, 0).unwrap())
+        );
+        assert_eq!(
+            "P1DT1H".parse::<Duration>().unwrap().add_times_to(dt, 3),
+            Some(Utc.with_ymd_and_hms(2022, 2, 3, 11, 30, 0).unwrap())
+        );
+        assert_eq!(
+            "weekdays"
+                .parse::<Duration>()
+                .unwrap()
+                .add_times_to(friday, 6),
+            Some(Utc.with_ymd_and_hms(2022, 2, 14, 8, 30, 0).unwrap())
+        );
+        assert_eq!(Duration::months(1).add_times_to(dt, 0), Some(dt));
+    }
 }
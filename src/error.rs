@@ -0,0 +1,168 @@
+//! The error type shared by fallible operations across the crate.
+use std::fmt;
+use std::io;
+
+#[cfg(feature = "exec")]
+use crate::exec::ExecError;
+#[cfg(feature = "taskchampion-db")]
+use crate::taskchampion::DbError;
+use crate::{BuildError, StateError, UdaConversionError};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The JSON couldn't be read or written as a task.
+    Json(serde_json::Error),
+    /// Reading or writing failed.
+    Io(io::Error),
+    /// A value couldn't be parsed, such as a date, duration, or data file line.
+    Parse(String),
+    /// A task couldn't be built.
+    Build(BuildError),
+    /// A value or change was rejected, such as an invalid tag or status transition.
+    Validation(String),
+    /// A UDA couldn't be converted to another type.
+    UdaConversion(UdaConversionError),
+    /// A task couldn't be started, stopped, or completed.
+    State(StateError),
+    /// Running the `task` binary failed.
+    #[cfg(feature = "exec")]
+    Exec(ExecError),
+    /// Reading a TaskChampion database failed.
+    #[cfg(feature = "taskchampion-db")]
+    Db(DbError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Json(e) => write!(f, "invalid task JSON: {e}"),
+            Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::Parse(s) => write!(f, "failed to parse: {s}"),
+            Error::Build(e) => write!(f, "failed to build task: {e}"),
+            Error::Validation(s) => write!(f, "validation failed: {s}"),
+            Error::UdaConversion(e) => write!(f, "invalid UDA: {e}"),
+            Error::State(e) => write!(f, "invalid state: {e}"),
+            #[cfg(feature = "exec")]
+            Error::Exec(e) => write!(f, "{e}"),
+            #[cfg(feature = "taskchampion-db")]
+            Error::Db(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Json(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Build(e) => Some(e),
+            Error::UdaConversion(e) => Some(e),
+            Error::State(e) => Some(e),
+            #[cfg(feature = "exec")]
+            Error::Exec(e) => Some(e),
+            #[cfg(feature = "taskchampion-db")]
+            Error::Db(e) => Some(e),
+            Error::Parse(_) | Error::Validation(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Self {
+        Error::Build(e)
+    }
+}
+
+impl From<UdaConversionError> for Error {
+    fn from(e: UdaConversionError) -> Self {
+        Error::UdaConversion(e)
+    }
+}
+
+impl From<StateError> for Error {
+    fn from(e: StateError) -> Self {
+        Error::State(e)
+    }
+}
+
+#[cfg(feature = "exec")]
+impl From<ExecError> for Error {
+    fn from(e: ExecError) -> Self {
+        Error::Exec(e)
+    }
+}
+
+#[cfg(feature = "taskchampion-db")]
+impl From<DbError> for Error {
+    fn from(e: DbError) -> Self {
+        Error::Db(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn display() {
+        let json = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let e = Error::from(json);
+        assert!(e.to_string().starts_with("invalid task JSON: EOF"), "{e}");
+        assert!(e.source().is_some());
+
+        let e = Error::from(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(e.to_string(), "I/O error: no such file");
+
+        let e = Error::Parse("invalid date for due: \"tomorrow\"".to_string());
+        assert_eq!(
+            e.to_string(),
+            "failed to parse: invalid date for due: \"tomorrow\""
+        );
+        assert!(e.source().is_none());
+
+        let e = Error::from(BuildError::Missing("uuid"));
+        assert_eq!(
+            e.to_string(),
+            "failed to build task: missing required field: uuid"
+        );
+
+        let e = Error::Validation("invalid status transition: Deleted -> Completed".to_string());
+        assert_eq!(
+            e.to_string(),
+            "validation failed: invalid status transition: Deleted -> Completed"
+        );
+
+        let e = Error::from(UdaConversionError::IncompatibleVariant("Date"));
+        assert_eq!(e.to_string(), "invalid UDA: cannot convert a Date UDA");
+
+        let e = Error::from(StateError::NotStarted);
+        assert_eq!(e.to_string(), "invalid state: task isn't started");
+    }
+    #[cfg(feature = "exec")]
+    #[test]
+    fn display_exec() {
+        let e = Error::from(ExecError::Parse("no tasks".to_string()));
+        assert_eq!(e.to_string(), "failed to parse task output: no tasks");
+        assert!(e.source().is_some());
+    }
+    #[cfg(feature = "taskchampion-db")]
+    #[test]
+    fn display_db() {
+        let e = Error::from(DbError::Parse("missing description".to_string()));
+        assert!(e.to_string().contains("missing description"), "{e}");
+        assert!(e.source().is_some());
+    }
+}
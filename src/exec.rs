@@ -0,0 +1,291 @@
+//! Run the `task` binary and parse its output.
+//!
+//! ```rust no_run
+//! use tasklib::prelude::*;
+//!
+//! let task_command = TaskCommand::new().rc("/home/.taskrc").data("/home/.task");
+//! let tasks: Vec<Task> = task_command.export("project:Daily").expect("export tasks");
+//! ```
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+use uuid::Uuid;
+
+use crate::cli::split_words;
+use crate::Task;
+
+#[derive(Debug)]
+pub enum ExecError {
+    /// The binary couldn't be run.
+    Io(io::Error),
+    /// The binary ran, but exited unsuccessfully.
+    Failed { code: Option<i32>, stderr: String },
+    /// The exported JSON couldn't be parsed.
+    Json(serde_json::Error),
+    /// The output couldn't be understood.
+    Parse(String),
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::Io(e) => write!(f, "failed to run task: {e}"),
+            ExecError::Failed {
+                code: Some(code),
+                stderr,
+            } => write!(f, "task exited with status {code}: {stderr}"),
+            ExecError::Failed { code: None, stderr } => {
+                write!(f, "task was terminated by a signal: {stderr}")
+            }
+            ExecError::Json(e) => write!(f, "failed to parse exported tasks: {e}"),
+            ExecError::Parse(s) => write!(f, "failed to parse task output: {s}"),
+        }
+    }
+}
+
+impl Error for ExecError {}
+
+impl From<io::Error> for ExecError {
+    fn from(e: io::Error) -> Self {
+        ExecError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ExecError {
+    fn from(e: serde_json::Error) -> Self {
+        ExecError::Json(e)
+    }
+}
+
+/// Runs Taskwarrior commands.
+///
+/// Commands are run non-interactively, with confirmations turned off.
+#[derive(Debug, Clone)]
+pub struct TaskCommand {
+    /// Path to the `task` binary. Defaults to `task`, found on `PATH`.
+    binary: PathBuf,
+    /// Override for the `.taskrc` file, passed as `rc:<path>`.
+    rc: Option<PathBuf>,
+    /// Override for the data location, passed as `rc.data.location=<path>`.
+    data: Option<PathBuf>,
+}
+
+impl Default for TaskCommand {
+    fn default() -> Self {
+        TaskCommand {
+            binary: PathBuf::from("task"),
+            rc: None,
+            data: None,
+        }
+    }
+}
+
+/// Constructors
+impl TaskCommand {
+    pub fn new() -> Self {
+        TaskCommand {
+            ..Default::default()
+        }
+    }
+    pub fn binary<T: Into<PathBuf>>(mut self, binary: T) -> Self {
+        self.binary = binary.into();
+        self
+    }
+    pub fn rc<T: Into<PathBuf>>(mut self, rc: T) -> Self {
+        self.rc = Some(rc.into());
+        self
+    }
+    pub fn data<T: Into<PathBuf>>(mut self, data: T) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+}
+
+/// Commands
+///
+/// Filters and arguments are split into words as a shell would, so quoted text stays one
+/// argument, e.g. `description:"Task to do."`.
+impl TaskCommand {
+    /// Export the tasks matching `filter`.
+    ///
+    /// Runs `task <filter> export`.
+    pub fn export(&self, filter: &str) -> Result<Vec<Task>, ExecError> {
+        let mut args = split_words(filter);
+        args.push("export".to_string());
+        let stdout = self.run(&args)?;
+        Ok(serde_json::from_str(&stdout)?)
+    }
+    /// Add a task, returning it.
+    ///
+    /// Runs `task add <args>`.
+    pub fn add(&self, args: &str) -> Result<Task, ExecError> {
+        let mut command = vec!["rc.verbose=new-uuid".to_string(), "add".to_string()];
+        command.extend(split_words(args));
+        let stdout = self.run(&command)?;
+        // e.g. "Created task d67fce70-c0b6-43c5-affc-a21e64567d40."
+        let uuid = stdout
+            .split_whitespace()
+            .find_map(|word| Uuid::parse_str(word.trim_end_matches('.')).ok())
+            .ok_or_else(|| ExecError::Parse(format!("no uuid in output: {stdout}")))?;
+        self.get(&uuid)
+    }
+    /// Modify a task, returning it.
+    ///
+    /// Runs `task <uuid> modify <args>`.
+    pub fn modify(&self, uuid: &Uuid, args: &str) -> Result<Task, ExecError> {
+        let mut command = vec![uuid.to_string(), "modify".to_string()];
+        command.extend(split_words(args));
+        self.run(&command)?;
+        self.get(uuid)
+    }
+    /// Complete a task, returning it.
+    ///
+    /// Runs `task <uuid> done`.
+    pub fn done(&self, uuid: &Uuid) -> Result<Task, ExecError> {
+        self.run(&[uuid.to_string().as_str(), "done"])?;
+        self.get(uuid)
+    }
+    /// Export a single task by UUID.
+    fn get(&self, uuid: &Uuid) -> Result<Task, ExecError> {
+        self.export(&uuid.to_string())?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ExecError::Parse(format!("task not found: {uuid}")))
+    }
+    /// Run the binary with the overrides and `args`, returning stdout.
+    fn run<S: AsRef<OsStr>>(&self, args: &[S]) -> Result<String, ExecError> {
+        let mut command = Command::new(&self.binary);
+        if let Some(rc) = &self.rc {
+            command.arg(format!("rc:{}", rc.display()));
+        }
+        if let Some(data) = &self.data {
+            command.arg(format!("rc.data.location={}", data.display()));
+        }
+        command
+            .arg("rc.confirmation=off")
+            .arg("rc.bulk=0")
+            .args(args);
+
+        let output = command.output()?;
+        if !output.status.success() {
+            return Err(ExecError::Failed {
+                code: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+        String::from_utf8(output.stdout).map_err(|e| ExecError::Parse(e.to_string()))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    const TASK: &str = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","entry":"20220131T083000Z","modified":"20220131T083000Z","status":"pending"}"#;
+
+    /// Write a stub `task` binary that logs its arguments and prints canned output.
+    fn stub(dir: &Path) -> PathBuf {
+        let binary = dir.join("task");
+        let script = format!(
+            r#"#!/bin/sh
+line=
+for arg in "$@"; do
+    case "$arg" in
+        *" "*) arg="\"$arg\"" ;;
+    esac
+    line="${{line:+$line }}$arg"
+done
+echo "$line" >> "{log}"
+for arg in "$@"; do
+    case "$arg" in
+        export) echo '[{TASK}]'; exit 0 ;;
+        add) echo 'Created task d67fce70-c0b6-43c5-affc-a21e64567d40.'; exit 0 ;;
+        fail) echo 'Invalid command' >&2; exit 2 ;;
+    esac
+done
+"#,
+            log = dir.join("log").display(),
+        );
+        fs::write(&binary, script).unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        binary
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tasklib-exec-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn export() {
+        let dir = temp_dir("export");
+        let task_command = TaskCommand::new()
+            .binary(stub(&dir))
+            .rc("/tmp/.taskrc")
+            .data("/tmp/.task");
+
+        let tasks = task_command.export("project:Daily +WORK").unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description(), "Task to do.");
+
+        let log = fs::read_to_string(dir.join("log")).unwrap();
+        assert_eq!(
+            log.trim(),
+            "rc:/tmp/.taskrc rc.data.location=/tmp/.task rc.confirmation=off rc.bulk=0 project:Daily +WORK export"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn add_modify_done() {
+        let dir = temp_dir("add");
+        let task_command = TaskCommand::new().binary(stub(&dir));
+
+        let task = task_command.add("Task to do.").unwrap();
+        let uuid = *task.uuid();
+        assert_eq!(uuid.to_string(), "d67fce70-c0b6-43c5-affc-a21e64567d40");
+        task_command
+            .modify(&uuid, r#"project:Daily description:"Call 'Bob' back""#)
+            .unwrap();
+        task_command.done(&uuid).unwrap();
+
+        let log = fs::read_to_string(dir.join("log")).unwrap();
+        let commands: Vec<&str> = log.lines().collect();
+        assert_eq!(
+            commands,
+            vec![
+                "rc.confirmation=off rc.bulk=0 rc.verbose=new-uuid add Task to do.",
+                "rc.confirmation=off rc.bulk=0 d67fce70-c0b6-43c5-affc-a21e64567d40 export",
+                "rc.confirmation=off rc.bulk=0 d67fce70-c0b6-43c5-affc-a21e64567d40 modify project:Daily \"description:Call 'Bob' back\"",
+                "rc.confirmation=off rc.bulk=0 d67fce70-c0b6-43c5-affc-a21e64567d40 export",
+                "rc.confirmation=off rc.bulk=0 d67fce70-c0b6-43c5-affc-a21e64567d40 done",
+                "rc.confirmation=off rc.bulk=0 d67fce70-c0b6-43c5-affc-a21e64567d40 export",
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn failure() {
+        let dir = temp_dir("failure");
+        let task_command = TaskCommand::new().binary(stub(&dir));
+        match task_command.export("fail") {
+            Err(ExecError::Failed { code, stderr }) => {
+                assert_eq!(code, Some(2));
+                assert_eq!(stderr.trim(), "Invalid command");
+            }
+            other => panic!("expected failure, got {other:?}"),
+        }
+
+        let task_command = TaskCommand::new().binary(dir.join("missing"));
+        assert!(matches!(task_command.export(""), Err(ExecError::Io(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,497 @@
+//! Read and write tasks in Taskwarrior 2.x data files, `pending.data` and `completed.data`.
+//!
+//! These use the FF4 format, one task per line.
+//!
+//! e.g. `[description:"Task to do." entry:"1643617800" status:"pending" uuid:"..."]`
+//!
+//! * Dates are stored as Unix timestamps, e.g. `"1643617800"`
+//! * Tags and dependencies are stored as comma-separated lists
+//! * Annotations are stored as `annotation_<timestamp>` keys, with the description as the value
+//!
+//! Everything else is treated as a UDA.
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use chrono::{offset::Utc, DateTime};
+use serde_json::{Map, Value};
+
+use uuid::Uuid;
+
+use crate::{Error, Task, DATETIME_FORMAT};
+
+/// Attributes stored as Unix timestamps.
+const DATE_ATTRIBUTES: &[&str] = &[
+    "entry",
+    "modified",
+    "start",
+    "end",
+    "due",
+    "wait",
+    "scheduled",
+    "until",
+];
+
+/// Read every task from an FF4 data file.
+///
+/// Blank lines and `#` comment lines are skipped.
+pub fn read_ff4_file(path: &Path) -> Result<Vec<Task>, Error> {
+    parse_lines(&fs::read_to_string(path)?)
+}
+
+/// Parse every task in the contents of an FF4 data file. See `read_ff4_file`.
+fn parse_lines(contents: &str) -> Result<Vec<Task>, Error> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(n, line)| parse_line(line).map_err(|e| Error::Parse(format!("line {}: {e}", n + 1))))
+        .collect()
+}
+
+/// Parse a single FF4 line into a task.
+pub fn parse_ff4_line(line: &str) -> Result<Task, Error> {
+    parse_line(line).map_err(Error::Parse)
+}
+
+fn parse_line(line: &str) -> Result<Task, String> {
+    task_from_attributes(None, parse_attributes(line)?)
+}
+
+/// Parse an FF4 line into its attributes.
+///
+/// `[key:"value" key:"value"]`
+fn parse_attributes(line: &str) -> Result<HashMap<String, String>, String> {
+    let line = line.trim();
+    let body = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(|| "expected a line wrapped in '[' and ']'".to_string())?;
+
+    let mut attributes = HashMap::new();
+    let mut rest = body.trim_start();
+    while !rest.is_empty() {
+        let (key, after_key) = rest
+            .split_once(":\"")
+            .ok_or_else(|| format!("expected key:\"value\" at: {rest}"))?;
+        // Find the closing, unescaped, quote
+        let mut end = None;
+        let mut escaped = false;
+        for (i, c) in after_key.char_indices() {
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => {
+                    end = Some(i);
+                    break;
+                }
+                _ => escaped = false,
+            }
+        }
+        let end = end.ok_or_else(|| format!("unterminated value for {key}"))?;
+        attributes.insert(key.trim().to_string(), decode(&after_key[..end])?);
+        rest = after_key[end + 1..].trim_start();
+    }
+    Ok(attributes)
+}
+
+/// Entities used in FF4 values, and the text they stand for.
+const ENTITIES: &[(&str, &str)] = &[
+    ("&dquot;", "\""),
+    ("&open;", "["),
+    ("&close;", "]"),
+    ("&amp;", "&"),
+];
+
+/// Decode an FF4 value.
+///
+/// Handles JSON-style backslash escapes, and the `&dquot;`, `&open;`, `&close;`, and `&amp;`
+/// entities.
+fn decode(value: &str) -> Result<String, String> {
+    let mut decoded = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => decoded.push('"'),
+            Some('\\') => decoded.push('\\'),
+            Some('/') => decoded.push('/'),
+            Some('b') => decoded.push('\u{8}'),
+            Some('f') => decoded.push('\u{c}'),
+            Some('n') => decoded.push('\n'),
+            Some('r') => decoded.push('\r'),
+            Some('t') => decoded.push('\t'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let code = u32::from_str_radix(&hex, 16)
+                    .map_err(|e| format!("invalid unicode escape \\u{hex}: {e}"))?;
+                decoded.push(
+                    char::from_u32(code)
+                        .ok_or_else(|| format!("invalid unicode escape \\u{hex}"))?,
+                );
+            }
+            Some(c) => return Err(format!("invalid escape \\{c}")),
+            None => return Err("trailing backslash".to_string()),
+        }
+    }
+    Ok(decode_entities(&decoded))
+}
+
+/// Replace the entities in `value`, in a single pass so a decoded `&` never starts another one.
+fn decode_entities(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(i) = rest.find('&') {
+        decoded.push_str(&rest[..i]);
+        rest = &rest[i..];
+        match ENTITIES.iter().find(|(entity, _)| rest.starts_with(entity)) {
+            Some((entity, text)) => {
+                decoded.push_str(text);
+                rest = &rest[entity.len()..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Encode an FF4 value.
+///
+/// The inverse of `decode`, using JSON-style backslash escapes. Brackets are written as `&open;`
+/// and `&close;`, and `&` as `&amp;` only where it would otherwise be read as an entity.
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for (i, c) in value.char_indices() {
+        match c {
+            '[' => encoded.push_str("&open;"),
+            ']' => encoded.push_str("&close;"),
+            '&' if ENTITIES
+                .iter()
+                .any(|(entity, _)| value[i..].starts_with(entity)) =>
+            {
+                encoded.push_str("&amp;")
+            }
+            '"' => encoded.push_str("\\\""),
+            '\\' => encoded.push_str("\\\\"),
+            '\u{8}' => encoded.push_str("\\b"),
+            '\u{c}' => encoded.push_str("\\f"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            c if c.is_control() => encoded.push_str(&format!("\\u{:04x}", c as u32)),
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// FF4 Conversion
+impl Task {
+    /// Convert the task to a line of an FF4 data file.
+    ///
+    /// Only set fields are emitted, with keys in sorted order. `id` and `urgency` are computed by
+    /// Taskwarrior and are not emitted.
+    ///
+    /// Annotations are keyed by their timestamp, so, as Taskwarrior does, an annotation sharing
+    /// its second with an earlier one is written a second later.
+    ///
+    /// e.g. `[description:"Task to do." entry:"1643617800" status:"pending" uuid:"..."]`
+    pub fn to_ff4_line(&self) -> String {
+        let mut attributes: BTreeMap<String, String> = BTreeMap::new();
+
+        for (name, value) in &self.udas {
+            attributes.insert(name.clone(), value.to_string());
+        }
+        attributes.insert("uuid".to_string(), self.uuid.to_string());
+        attributes.insert("description".to_string(), self.description.clone());
+        attributes.insert(
+            "status".to_string(),
+            serde_json::to_value(&self.status)
+                .ok()
+                .and_then(|status| status.as_str().map(str::to_string))
+                .unwrap_or_default(),
+        );
+        attributes.insert("entry".to_string(), self.entry.timestamp().to_string());
+        attributes.insert(
+            "modified".to_string(),
+            self.modified.timestamp().to_string(),
+        );
+        let dates = [
+            ("start", &self.start),
+            ("end", &self.end),
+            ("due", &self.due),
+            ("wait", &self.wait),
+            ("scheduled", &self.scheduled),
+            ("until", &self.until),
+        ];
+        for (name, date) in dates {
+            if let Some(date) = date {
+                attributes.insert(name.to_string(), date.timestamp().to_string());
+            }
+        }
+        if !self.project.is_empty() {
+            attributes.insert("project".to_string(), self.project.clone());
+        }
+        if !self.tags.is_empty() {
+            attributes.insert("tags".to_string(), self.tags.join(","));
+        }
+        if !self.depends.is_empty() {
+            let depends: Vec<String> = self.depends.iter().map(Uuid::to_string).collect();
+            attributes.insert("depends".to_string(), depends.join(","));
+        }
+        let mut annotations: Vec<_> = self.annotations.iter().collect();
+        annotations.sort_by_key(|annotation| annotation.entry);
+        for annotation in annotations {
+            let mut timestamp = annotation.entry.timestamp();
+            while attributes.contains_key(&format!("annotation_{timestamp}")) {
+                timestamp += 1;
+            }
+            attributes.insert(
+                format!("annotation_{timestamp}"),
+                annotation.description.clone(),
+            );
+        }
+        if let Some(recur) = &self.recur {
+            attributes.insert("recur".to_string(), recur.to_string());
+        }
+        if let Some(mask) = &self.mask {
+            attributes.insert("mask".to_string(), mask.clone());
+        }
+        if let Some(imask) = self.imask {
+            attributes.insert("imask".to_string(), imask.to_string());
+        }
+        if let Some(parent) = &self.parent {
+            attributes.insert("parent".to_string(), parent.to_string());
+        }
+
+        let attributes: Vec<String> = attributes
+            .iter()
+            .map(|(key, value)| format!("{key}:\"{}\"", encode(value)))
+            .collect();
+        format!("[{}]", attributes.join(" "))
+    }
+}
+
+/// Map Taskwarrior's on-disk attributes onto a task.
+///
+/// Shared by the FF4 and TaskChampion readers. If `uuid` is given, it takes precedence over any
+/// `uuid` attribute.
+pub(crate) fn task_from_attributes(
+    uuid: Option<&str>,
+    attributes: HashMap<String, String>,
+) -> Result<Task, String> {
+    let mut object = Map::new();
+    let mut tags = Vec::new();
+    let mut depends = Vec::new();
+    let mut annotations = Vec::new();
+
+    for (key, value) in attributes {
+        if key == "tags" {
+            tags.extend(split_list(&value));
+        } else if let Some(tag) = key.strip_prefix("tag_") {
+            tags.push(tag.to_string());
+        } else if key == "depends" {
+            depends.extend(split_list(&value));
+        } else if let Some(dependency) = key.strip_prefix("dep_") {
+            depends.push(dependency.to_string());
+        } else if let Some(timestamp) = key.strip_prefix("annotation_") {
+            annotations.push((timestamp_to_tw(timestamp)?, value));
+        } else if DATE_ATTRIBUTES.contains(&key.as_str()) {
+            object.insert(key, Value::String(timestamp_to_tw(&value)?));
+        } else if key == "imask" {
+            let imask = value
+                .parse::<f64>()
+                .map_err(|e| format!("invalid imask {value}: {e}"))?;
+            object.insert(key, Value::from(imask));
+        } else {
+            object.insert(key, Value::String(value));
+        }
+    }
+    if let Some(uuid) = uuid {
+        object.insert("uuid".to_string(), Value::String(uuid.to_string()));
+    }
+    // Sort for a stable order, as the attributes are unordered
+    tags.sort();
+    tags.dedup();
+    depends.sort();
+    depends.dedup();
+    annotations.sort();
+    object.insert("tags".to_string(), Value::from(tags));
+    object.insert("depends".to_string(), Value::from(depends));
+    object.insert(
+        "annotations".to_string(),
+        Value::Array(
+            annotations
+                .into_iter()
+                .map(|(entry, description)| {
+                    let mut annotation = Map::new();
+                    annotation.insert("entry".to_string(), Value::String(entry));
+                    annotation.insert("description".to_string(), Value::String(description));
+                    Value::Object(annotation)
+                })
+                .collect(),
+        ),
+    );
+
+    serde_json::from_value(Value::Object(object)).map_err(|e| format!("invalid task: {e}"))
+}
+
+/// Split a comma-separated list, ignoring empty items.
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+}
+
+/// Unix timestamp -> Taskwarrior datetime string
+///
+/// e.g. "1643617800" -> "20220131T083000Z"
+fn timestamp_to_tw(timestamp: &str) -> Result<String, String> {
+    let seconds = timestamp
+        .parse::<i64>()
+        .map_err(|e| format!("invalid timestamp {timestamp}: {e}"))?;
+    let dt = DateTime::<Utc>::from_timestamp(seconds, 0)
+        .ok_or_else(|| format!("timestamp out of range: {timestamp}"))?;
+    Ok(dt.format(DATETIME_FORMAT).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+
+    const FIXTURE: &str = r#"[description:"Call \"Bob\" back" entry:"1643617800" modified:"1643617800" project:"Daily" status:"pending" tags:"WORK,phone" uuid:"d67fce70-c0b6-43c5-affc-a21e64567d40" annotation_1643617800:"Left a message" estimate:"PT2H"]
+[description:"Done &open;legacy&close;" end:"1643704200" entry:"1643617800" modified:"1643704200" status:"completed" depends:"d67fce70-c0b6-43c5-affc-a21e64567d40" uuid:"8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b"]
+"#;
+
+    #[test]
+    fn read_fixture() {
+        let path = std::env::temp_dir().join(format!("tasklib-ff4-{}.data", std::process::id()));
+        fs::write(&path, FIXTURE).unwrap();
+        let tasks = read_ff4_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+
+        let task = &tasks[0];
+        assert_eq!(task.description(), r#"Call "Bob" back"#);
+        assert_eq!(task.status(), &Status::Pending);
+        assert_eq!(task.project(), "Daily");
+        assert_eq!(task.tags(), &["WORK".to_string(), "phone".to_string()]);
+        assert_eq!(
+            task.entry().format(DATETIME_FORMAT).to_string(),
+            "20220131T083000Z"
+        );
+        assert_eq!(task.annotations().len(), 1);
+        assert_eq!(task.udas().get("estimate").unwrap(), "PT2H");
+
+        let task = &tasks[1];
+        assert_eq!(task.description(), "Done [legacy]");
+        assert_eq!(task.status(), &Status::Completed);
+        assert_eq!(task.depends(), &[*tasks[0].uuid()]);
+        assert_eq!(
+            task.end().unwrap().format(DATETIME_FORMAT).to_string(),
+            "20220201T083000Z"
+        );
+    }
+    #[test]
+    fn comments_and_blank_lines() {
+        let mut lines = FIXTURE.lines();
+        let contents = format!(
+            "# pending.data\n\n{}\n   \n  # Done\n{}\n",
+            lines.next().unwrap(),
+            lines.next().unwrap()
+        );
+        let tasks = parse_lines(&contents).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[1].description(), "Done [legacy]");
+
+        // Numbered as in the file
+        assert!(matches!(
+            parse_lines("# Comment\n\n[description:\"No uuid\"]\n"),
+            Err(Error::Parse(e)) if e.starts_with("line 3: ")
+        ));
+    }
+    #[test]
+    fn round_trip() {
+        for line in FIXTURE.lines() {
+            let task = parse_ff4_line(line).unwrap();
+            let emitted = task.to_ff4_line();
+            assert_eq!(parse_ff4_line(&emitted).unwrap(), task);
+        }
+
+        let task = parse_ff4_line(FIXTURE.lines().next().unwrap()).unwrap();
+        assert_eq!(
+            task.to_ff4_line(),
+            r#"[annotation_1643617800:"Left a message" description:"Call \"Bob\" back" entry:"1643617800" estimate:"PT2H" modified:"1643617800" project:"Daily" status:"pending" tags:"WORK,phone" uuid:"d67fce70-c0b6-43c5-affc-a21e64567d40"]"#
+        );
+    }
+    #[test]
+    fn escaping() {
+        let input = "quote \" backslash \\ newline \n tab \t bell \u{7}";
+        assert_eq!(decode(&encode(input)).unwrap(), input);
+        assert_eq!(encode("a\"b\n"), r#"a\"b\n"#);
+
+        // Brackets and entities
+        for input in [
+            "[x] done",
+            "&open; literally",
+            "&amp;open;",
+            "R&D",
+            "a & b",
+            "&",
+        ] {
+            assert_eq!(decode(&encode(input)).unwrap(), input);
+        }
+        assert_eq!(encode("[x] R&D &close;"), "&open;x&close; R&D &amp;close;");
+        assert_eq!(decode("&amp;open;").unwrap(), "&open;");
+
+        let mut task = parse_ff4_line(FIXTURE.lines().next().unwrap()).unwrap();
+        *task.description_mut() = "[draft] &dquot; &open;".to_string();
+        let line = task.to_ff4_line();
+        assert!(line.contains(r#"description:"&open;draft&close; &amp;dquot; &amp;open;""#));
+        assert_eq!(parse_ff4_line(&line).unwrap(), task);
+    }
+    #[test]
+    fn same_second_annotations() {
+        let mut task = parse_ff4_line(FIXTURE.lines().next().unwrap()).unwrap();
+        let entry = task.annotations()[0].entry;
+        task.annotations_mut().push(crate::Annotation {
+            entry,
+            description: "Called again".to_string(),
+        });
+        let line = task.to_ff4_line();
+        assert!(line.contains(r#"annotation_1643617800:"Left a message""#));
+        assert!(line.contains(r#"annotation_1643617801:"Called again""#));
+
+        let read = parse_ff4_line(&line).unwrap();
+        let descriptions: Vec<&str> = read
+            .annotations()
+            .iter()
+            .map(|annotation| annotation.description.as_str())
+            .collect();
+        assert_eq!(descriptions, ["Left a message", "Called again"]);
+        assert_eq!(
+            read.annotations()[1].entry,
+            entry + chrono::Duration::seconds(1)
+        );
+    }
+    #[test]
+    fn invalid_lines() {
+        assert!(parse_ff4_line("description:\"No brackets\"").is_err());
+        assert!(parse_ff4_line("[description:\"Unterminated]").is_err());
+        // Missing required fields
+        assert!(parse_ff4_line("[description:\"No uuid\"]").is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Which optional fields of a task are set, as a bitset for fast filtering.
+//!
+//! ```rust
+//! use tasklib::prelude::*;
+//!
+//! let task = Task::new("Water the plants").with_project("Garden");
+//! assert!(task.present_fields().contains(FieldMask::PROJECT));
+//! assert!(!task.present_fields().intersects(FieldMask::DUE | FieldMask::WAIT));
+//! ```
+use std::fmt;
+use std::ops;
+
+use crate::Task;
+
+/// A set of optional task fields. See `Task::present_fields`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FieldMask(u16);
+
+impl FieldMask {
+    pub const START: FieldMask = FieldMask(1 << 0);
+    pub const END: FieldMask = FieldMask(1 << 1);
+    pub const DUE: FieldMask = FieldMask(1 << 2);
+    pub const WAIT: FieldMask = FieldMask(1 << 3);
+    pub const SCHEDULED: FieldMask = FieldMask(1 << 4);
+    pub const UNTIL: FieldMask = FieldMask(1 << 5);
+    pub const PROJECT: FieldMask = FieldMask(1 << 6);
+    pub const URGENCY: FieldMask = FieldMask(1 << 7);
+    pub const RECUR: FieldMask = FieldMask(1 << 8);
+    pub const PARENT: FieldMask = FieldMask(1 << 9);
+
+    /// Every field, with its name as serialized.
+    const NAMES: [(FieldMask, &'static str); 10] = [
+        (FieldMask::START, "start"),
+        (FieldMask::END, "end"),
+        (FieldMask::DUE, "due"),
+        (FieldMask::WAIT, "wait"),
+        (FieldMask::SCHEDULED, "scheduled"),
+        (FieldMask::UNTIL, "until"),
+        (FieldMask::PROJECT, "project"),
+        (FieldMask::URGENCY, "urgency"),
+        (FieldMask::RECUR, "recur"),
+        (FieldMask::PARENT, "parent"),
+    ];
+
+    /// No fields.
+    pub const fn empty() -> Self {
+        FieldMask(0)
+    }
+    /// Every field.
+    pub const fn all() -> Self {
+        FieldMask((1 << 10) - 1)
+    }
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    /// Whether every field in `other` is in this set.
+    pub const fn contains(&self, other: FieldMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Whether any field in `other` is in this set.
+    pub const fn intersects(&self, other: FieldMask) -> bool {
+        self.0 & other.0 != 0
+    }
+    pub fn insert(&mut self, other: FieldMask) {
+        self.0 |= other.0;
+    }
+    pub fn remove(&mut self, other: FieldMask) {
+        self.0 &= !other.0;
+    }
+    /// Names of the fields in this set, as serialized.
+    pub fn names(&self) -> Vec<&'static str> {
+        FieldMask::NAMES
+            .iter()
+            .filter(|(field, _)| self.contains(*field))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// Lists field names, e.g. `FieldMask(due | project)`.
+impl fmt::Debug for FieldMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FieldMask({})", self.names().join(" | "))
+    }
+}
+
+impl ops::BitOr for FieldMask {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        FieldMask(self.0 | other.0)
+    }
+}
+
+impl ops::BitAnd for FieldMask {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        FieldMask(self.0 & other.0)
+    }
+}
+
+impl ops::Not for FieldMask {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        FieldMask(!self.0 & FieldMask::all().0)
+    }
+}
+
+/// Field Presence
+impl Task {
+    /// Which optional fields are set, to filter on without checking each field again.
+    ///
+    /// A project is set if it isn't empty, and urgency if it's finite, as serialized.
+    pub fn present_fields(&self) -> FieldMask {
+        let mut mask = FieldMask::empty();
+        for (field, present) in [
+            (FieldMask::START, self.start.is_some()),
+            (FieldMask::END, self.end.is_some()),
+            (FieldMask::DUE, self.due.is_some()),
+            (FieldMask::WAIT, self.wait.is_some()),
+            (FieldMask::SCHEDULED, self.scheduled.is_some()),
+            (FieldMask::UNTIL, self.until.is_some()),
+            (FieldMask::PROJECT, !self.project.is_empty()),
+            (
+                FieldMask::URGENCY,
+                self.urgency.is_some_and(|urgency| urgency.is_finite()),
+            ),
+            (FieldMask::RECUR, self.recur.is_some()),
+            (FieldMask::PARENT, self.parent.is_some()),
+        ] {
+            if present {
+                mask.insert(field);
+            }
+        }
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn due_and_project() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220202T083000Z",
+            "project": "Home"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let mask = task.present_fields();
+
+        assert_eq!(mask, FieldMask::DUE | FieldMask::PROJECT);
+        assert!(mask.contains(FieldMask::DUE));
+        assert!(!mask.contains(FieldMask::DUE | FieldMask::WAIT));
+        assert!(mask.intersects(FieldMask::DUE | FieldMask::WAIT));
+        assert!(!mask.intersects(FieldMask::START | FieldMask::END));
+        assert_eq!(mask.names(), ["due", "project"]);
+        assert_eq!(format!("{mask:?}"), "FieldMask(due | project)");
+        assert_eq!((!mask).names().len(), 8);
+
+        // Unset urgency, as serialized
+        *task.urgency_mut() = Some(f64::NAN);
+        task.project_mut().clear();
+        assert_eq!(task.present_fields(), FieldMask::DUE);
+    }
+    #[test]
+    fn set_operations() {
+        let mut mask = FieldMask::empty();
+        assert!(mask.is_empty());
+        mask.insert(FieldMask::START | FieldMask::RECUR);
+        assert_eq!(mask.bits(), 0b1_0000_0001);
+        mask.remove(FieldMask::START);
+        assert_eq!(mask, FieldMask::RECUR);
+        assert_eq!(mask & FieldMask::all(), mask);
+        assert_eq!(!FieldMask::all(), FieldMask::empty());
+        assert_eq!(FieldMask::all().names().len(), 10);
+    }
+}
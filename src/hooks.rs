@@ -0,0 +1,170 @@
+//! Run Taskwarrior hooks.
+//!
+//! Taskwarrior passes a hook tasks as JSON lines on stdin. The hook writes the task to keep as a
+//! JSON line on stdout, followed by any feedback. Exiting with failure rejects the change, and
+//! Taskwarrior shows the feedback instead.
+//!
+//! ```rust no_run
+//! use std::process::ExitCode;
+//! use tasklib::prelude::*;
+//!
+//! fn main() -> ExitCode {
+//!     run_on_add(|task| {
+//!         if task.description().is_empty() {
+//!             return Err("A description is required.".to_string());
+//!         }
+//!         Ok(task)
+//!     })
+//! }
+//! ```
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use crate::{Error, Task};
+
+/// Run an `on-add` hook over stdin and stdout, returning the exit code for Taskwarrior.
+///
+/// `f` is given the added task and returns the task to add, or feedback rejecting it. See
+/// `on_add`.
+pub fn run_on_add<F>(f: F) -> ExitCode
+where
+    F: FnOnce(Task) -> Result<Task, String>,
+{
+    exit_code(on_add(io::stdin().lock(), io::stdout().lock(), f))
+}
+
+/// Run an `on-modify` hook over stdin and stdout, returning the exit code for Taskwarrior.
+///
+/// `f` is given the original task and the modified task, and returns the task to keep, or feedback
+/// rejecting the change. See `on_modify`.
+pub fn run_on_modify<F>(f: F) -> ExitCode
+where
+    F: FnOnce(&Task, Task) -> Result<Task, String>,
+{
+    exit_code(on_modify(io::stdin().lock(), io::stdout().lock(), f))
+}
+
+/// Run an `on-add` hook, reading the added task from `input` and writing the task to add to
+/// `output`.
+///
+/// Returns `Error::Validation` with the feedback if `f` rejects the task, or another error if the
+/// task can't be read or written.
+pub fn on_add<R, W, F>(mut input: R, mut output: W, f: F) -> Result<(), Error>
+where
+    R: BufRead,
+    W: Write,
+    F: FnOnce(Task) -> Result<Task, String>,
+{
+    let task = read_task(&mut input)?;
+    write_task(&mut output, &f(task).map_err(Error::Validation)?)
+}
+
+/// Run an `on-modify` hook, reading the original and modified tasks from `input` and writing the
+/// task to keep to `output`.
+///
+/// Returns `Error::Validation` with the feedback if `f` rejects the change, or another error if
+/// the tasks can't be read or written.
+pub fn on_modify<R, W, F>(mut input: R, mut output: W, f: F) -> Result<(), Error>
+where
+    R: BufRead,
+    W: Write,
+    F: FnOnce(&Task, Task) -> Result<Task, String>,
+{
+    let original = read_task(&mut input)?;
+    let modified = read_task(&mut input)?;
+    write_task(
+        &mut output,
+        &f(&original, modified).map_err(Error::Validation)?,
+    )
+}
+
+/// Read a task from a line of `input`.
+fn read_task<R: BufRead>(input: &mut R) -> Result<Task, Error> {
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    if line.trim().is_empty() {
+        return Err(Error::Parse("missing task JSON".to_string()));
+    }
+    line.parse()
+}
+
+/// Write a task as a line to `output`.
+fn write_task<W: Write>(output: &mut W, task: &Task) -> Result<(), Error> {
+    task.to_writer(output)?;
+    writeln!(output)?;
+    Ok(())
+}
+
+/// Show any feedback, and exit with failure if there is some.
+///
+/// Feedback from the hook is shown as is; other errors are shown with their description.
+fn exit_code(result: Result<(), Error>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Error::Validation(feedback)) => {
+            println!("{feedback}");
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            println!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASK: &str = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","status":"pending","entry":"20220131T083000Z","modified":"20220131T083000Z"}"#;
+    const MODIFIED: &str = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","status":"pending","entry":"20220131T083000Z","modified":"20220131T090000Z","due":"20220202T160000Z"}"#;
+
+    #[test]
+    fn on_add() {
+        let mut output = Vec::new();
+        super::on_add(format!("{TASK}\n").as_bytes(), &mut output, |mut task| {
+            *task.project_mut() = "Inbox".to_string();
+            Ok(task)
+        })
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with('\n'));
+        assert_eq!(output.lines().count(), 1);
+        assert_eq!(output.parse::<Task>().unwrap().project(), "Inbox");
+
+        // Rejected
+        let mut output = Vec::new();
+        let result = super::on_add(TASK.as_bytes(), &mut output, |_| {
+            Err("No new tasks.".to_string())
+        });
+        assert!(matches!(result, Err(Error::Validation(e)) if e == "No new tasks."));
+        assert!(output.is_empty());
+
+        // Invalid input
+        assert!(super::on_add("{}".as_bytes(), Vec::new(), Ok).is_err());
+        assert!(matches!(
+            super::on_add("".as_bytes(), Vec::new(), Ok),
+            Err(Error::Parse(e)) if e == "missing task JSON"
+        ));
+    }
+    #[test]
+    fn on_modify() {
+        let input = format!("{TASK}\n{MODIFIED}\n");
+        let mut output = Vec::new();
+        super::on_modify(input.as_bytes(), &mut output, |original, mut task| {
+            assert!(original.due().is_none());
+            task.add_tag("due").map_err(|e| e.to_string())?;
+            Ok(task)
+        })
+        .unwrap();
+        let task = String::from_utf8(output).unwrap().parse::<Task>().unwrap();
+        assert_eq!(task.tags(), &["due"]);
+        assert!(task.due().is_some());
+
+        // Missing the modified task
+        assert!(matches!(
+            super::on_modify(format!("{TASK}\n").as_bytes(), Vec::new(), |_, task| Ok(task)),
+            Err(Error::Parse(e)) if e == "missing task JSON"
+        ));
+    }
+}
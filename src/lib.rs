@@ -55,20 +55,39 @@
 //! args.task_version(); // "2.6.3"
 //! ```
 
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::io::{self, Read, Write};
 use std::str::FromStr;
 use std::string::ToString;
 use uuid::Uuid;
 
-use chrono::{self, offset::Utc, DateTime, NaiveDateTime};
+use chrono::{self, offset::Utc, DateTime, NaiveDateTime, SubsecRound};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use duration::Duration;
-use udas::UdaValue;
+pub use error::Error;
+use udas::{Uda, UdaConversionError, UdaValidationError, UdaValue};
 
+mod display;
 mod duration;
+mod error;
+#[cfg(feature = "exec")]
+mod exec;
+mod ff4;
+mod fieldmask;
+mod hooks;
+mod object;
+#[cfg(feature = "taskchampion-db")]
+mod taskchampion;
+mod taskrc;
+mod taskref;
+mod tasks;
+mod taskset;
+#[cfg(feature = "tz")]
+mod tz;
+mod writer;
 
 const DATETIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
 
@@ -92,11 +111,9 @@ where
         where
             E: de::Error,
         {
-            Ok(DateTime::<Utc>::from_naive_utc_and_offset(
-                NaiveDateTime::parse_from_str(v, DATETIME_FORMAT)
-                    .expect("string turned into datetime"),
-                Utc,
-            ))
+            let dt = NaiveDateTime::parse_from_str(v, DATETIME_FORMAT)
+                .map_err(|e| E::custom(format!("invalid datetime {v:?}: {e}")))?;
+            Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
         }
     }
     deserializer.deserialize_any(DateTimeStringVisitor)
@@ -122,16 +139,124 @@ where
         where
             E: de::Error,
         {
-            Ok(Some(DateTime::<Utc>::from_naive_utc_and_offset(
-                NaiveDateTime::parse_from_str(v, DATETIME_FORMAT)
-                    .expect("string turned into datetime"),
-                Utc,
-            )))
+            let dt = NaiveDateTime::parse_from_str(v, DATETIME_FORMAT)
+                .map_err(|e| E::custom(format!("invalid datetime {v:?}: {e}")))?;
+            Ok(Some(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)))
         }
     }
     deserializer.deserialize_any(DateTimeStringVisitor)
 }
 
+/// Drop any fraction of a second.
+///
+/// Taskwarrior dates have second precision, so this keeps a date equal to its serialized form.
+fn truncate_to_seconds(dt: DateTime<Utc>) -> DateTime<Utc> {
+    dt.trunc_subsecs(0)
+}
+
+/// The current time, to the second.
+fn now_to_seconds() -> DateTime<Utc> {
+    truncate_to_seconds(Utc::now())
+}
+
+/// Urgency deserializer, accepting a number or a numeric string
+///
+/// e.g. `9.9` or `"9.9"` -> Some(9.9)
+fn urgency_de<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct UrgencyVisitor;
+
+    impl<'de> de::Visitor<'de> for UrgencyVisitor {
+        type Value = Option<f64>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number or a string containing a number")
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Some(v))
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Some(v as f64))
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Some(v as f64))
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            v.trim()
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|e| E::custom(format!("invalid urgency {v:?}: {e}")))
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(None)
+        }
+    }
+    deserializer.deserialize_any(UrgencyVisitor)
+}
+
+/// Tags deserializer, accepting an array of tags or a comma-separated string
+///
+/// e.g. `["home", "garden"]` or `"home,garden"` -> ["home", "garden"]
+fn tags_de<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct TagsVisitor;
+
+    impl<'de> de::Visitor<'de> for TagsVisitor {
+        type Value = Vec<String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an array of tags or a comma-separated string of tags")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::SeqAccess<'de>,
+        {
+            let mut tags = Vec::new();
+            while let Some(tag) = seq.next_element()? {
+                tags.push(tag);
+            }
+            Ok(tags)
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(v.split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect())
+        }
+    }
+    deserializer.deserialize_any(TagsVisitor)
+}
+
 /// Taskwarrior str to DateTime<Utc> serializer
 ///
 /// DateTime<Utc> -> String
@@ -149,9 +274,180 @@ fn tw_dt_to_str_opt_se<S: Serializer>(dt: &Option<DateTime<Utc>>, s: S) -> Resul
     }
 }
 
+/// Dependencies of a task.
+///
+/// Remembers whether there have been any dependencies, so that clearing them serializes as
+/// `"depends":[]` rather than leaving the field out. On import, Taskwarrior leaves fields that are
+/// left out untouched, so a task without `depends` wouldn't clear its existing dependencies.
+#[derive(Debug, Clone, Default)]
+struct Depends {
+    uuids: Vec<Uuid>,
+    /// Whether there have been dependencies, when deserialized or modified.
+    had: bool,
+}
+
+impl Depends {
+    /// Whether `depends` can be left out, as it has never been set.
+    fn is_unset(&self) -> bool {
+        self.uuids.is_empty() && !self.had
+    }
+    fn as_mut_vec(&mut self) -> &mut Vec<Uuid> {
+        self.had |= !self.uuids.is_empty();
+        &mut self.uuids
+    }
+}
+
+/// Compares dependencies only.
+impl PartialEq for Depends {
+    fn eq(&self, other: &Self) -> bool {
+        self.uuids == other.uuids
+    }
+}
+
+impl std::ops::Deref for Depends {
+    type Target = Vec<Uuid>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.uuids
+    }
+}
+
+impl From<Vec<Uuid>> for Depends {
+    fn from(uuids: Vec<Uuid>) -> Self {
+        Depends {
+            had: !uuids.is_empty(),
+            uuids,
+        }
+    }
+}
+
+impl Serialize for Depends {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.uuids.serialize(serializer)
+    }
+}
+
+/// Accepts an array of UUIDs, or a comma-separated string of UUIDs as in older exports.
+impl<'de> Deserialize<'de> for Depends {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DependsVisitor;
+
+        impl<'de> de::Visitor<'de> for DependsVisitor {
+            type Value = Vec<Uuid>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array of UUIDs or a comma-separated string of UUIDs")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut uuids = Vec::new();
+                while let Some(uuid) = seq.next_element()? {
+                    uuids.push(uuid);
+                }
+                Ok(uuids)
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|uuid| !uuid.is_empty())
+                    .map(|uuid| {
+                        Uuid::parse_str(uuid)
+                            .map_err(|e| E::custom(format!("invalid uuid {uuid:?}: {e}")))
+                    })
+                    .collect()
+            }
+        }
+        Ok(Depends::from(deserializer.deserialize_any(DependsVisitor)?))
+    }
+}
+
+/// UUID of a task.
+///
+/// Taskwarrior may call an `on-add` hook before assigning a UUID, so a missing UUID is replaced by
+/// a random one when deserializing. Remembers whether it was, see `Task::uuid_synthesized`.
+#[derive(Debug, Clone)]
+struct TaskUuid {
+    uuid: Uuid,
+    /// Whether the UUID was generated, as the task didn't have one.
+    synthesized: bool,
+}
+
+impl TaskUuid {
+    fn as_mut(&mut self) -> &mut Uuid {
+        self.synthesized = false;
+        &mut self.uuid
+    }
+}
+
+impl Default for TaskUuid {
+    fn default() -> Self {
+        TaskUuid {
+            uuid: Uuid::new_v4(),
+            synthesized: true,
+        }
+    }
+}
+
+/// Compares the UUID only.
+impl PartialEq for TaskUuid {
+    fn eq(&self, other: &Self) -> bool {
+        self.uuid == other.uuid
+    }
+}
+
+impl std::ops::Deref for TaskUuid {
+    type Target = Uuid;
+
+    fn deref(&self) -> &Self::Target {
+        &self.uuid
+    }
+}
+
+impl From<Uuid> for TaskUuid {
+    fn from(uuid: Uuid) -> Self {
+        TaskUuid {
+            uuid,
+            synthesized: false,
+        }
+    }
+}
+
+impl Serialize for TaskUuid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.uuid.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskUuid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(TaskUuid::from(Uuid::deserialize(deserializer)?))
+    }
+}
+
+/// The JSON a task was parsed from, kept by `Task::from_str_preserving`.
+///
+/// Doesn't affect equality, so a task equals its copy parsed without it.
+#[derive(Debug, Clone, Default)]
+struct Source(Option<serde_json::Value>);
+
+impl PartialEq for Source {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
 /// See all columns using `task columns` and `task _columns`.
 ///
 /// UDAs will only deserialize to a string or numeric type. Durations and dates will be parsed to a string.
+///
+/// UDAs are kept sorted by name, so they serialize in the same order every time.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Task {
     /// Task ID
@@ -161,7 +457,8 @@ pub struct Task {
     /// This is temporary and may not exist for some tasks.
     #[serde(skip_serializing_if = "Option::is_none")]
     id: Option<usize>,
-    uuid: Uuid,
+    #[serde(default)]
+    uuid: TaskUuid,
     description: String,
     #[serde(
         serialize_with = "tw_dt_to_str_opt_se",
@@ -177,9 +474,11 @@ pub struct Task {
         default
     )]
     end: Option<DateTime<Utc>>,
+    /// Defaults to now when missing, as in hand-written JSON.
     #[serde(
         serialize_with = "tw_dt_to_str_se",
-        deserialize_with = "tw_str_to_dt_de"
+        deserialize_with = "tw_str_to_dt_de",
+        default = "now_to_seconds"
     )]
     entry: DateTime<Utc>,
     #[serde(
@@ -210,9 +509,10 @@ pub struct Task {
         default
     )]
     due: Option<DateTime<Utc>>,
+    /// Serialized as `[]` once cleared, see `Depends`.
     #[serde(default)]
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    depends: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Depends::is_unset")]
+    depends: Depends,
     /// <https://taskwarrior.org/docs/commands/columns/>
     /// Type: numeric
     #[serde(default)]
@@ -237,17 +537,32 @@ pub struct Task {
     #[serde(skip_serializing_if = "String::is_empty")]
     project: String,
     status: Status,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "tags_de")]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tags: Vec<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Non-finite values, e.g. NaN from a division by zero, have no JSON representation and
+    /// aren't serialized.
+    #[serde(
+        skip_serializing_if = "is_unset_urgency",
+        deserialize_with = "urgency_de",
+        default
+    )]
     urgency: Option<f64>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     annotations: Vec<Annotation>,
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     #[serde(flatten)]
-    udas: HashMap<String, UdaValue>,
+    udas: BTreeMap<String, UdaValue>,
+    #[serde(skip)]
+    source: Source,
+}
+
+/// Whether urgency should be left out when serializing.
+///
+/// Unset, NaN, and infinite values are left out.
+fn is_unset_urgency(urgency: &Option<f64>) -> bool {
+    !matches!(urgency, Some(urgency) if urgency.is_finite())
 }
 
 /// Getters (Immutable)
@@ -256,7 +571,7 @@ impl Task {
         &self.id
     }
     pub fn uuid(&self) -> &Uuid {
-        &self.uuid
+        &self.uuid.uuid
     }
     pub fn description(&self) -> &str {
         &self.description
@@ -267,6 +582,33 @@ impl Task {
     pub fn end(&self) -> Option<&DateTime<Utc>> {
         self.end.as_ref()
     }
+    pub fn scheduled(&self) -> Option<&DateTime<Utc>> {
+        self.scheduled.as_ref()
+    }
+    pub fn until(&self) -> Option<&DateTime<Utc>> {
+        self.until.as_ref()
+    }
+    pub fn wait(&self) -> Option<&DateTime<Utc>> {
+        self.wait.as_ref()
+    }
+    pub fn due(&self) -> Option<&DateTime<Utc>> {
+        self.due.as_ref()
+    }
+    pub fn depends(&self) -> &[Uuid] {
+        &self.depends
+    }
+    pub fn imask(&self) -> Option<f64> {
+        self.imask
+    }
+    pub fn mask(&self) -> Option<&str> {
+        self.mask.as_deref()
+    }
+    pub fn parent(&self) -> Option<&Uuid> {
+        self.parent.as_ref()
+    }
+    pub fn recur(&self) -> Option<&Duration> {
+        self.recur.as_ref()
+    }
     pub fn entry(&self) -> &DateTime<Utc> {
         &self.entry
     }
@@ -288,7 +630,7 @@ impl Task {
     pub fn annotations(&self) -> &[Annotation] {
         &self.annotations
     }
-    pub fn udas(&self) -> &HashMap<String, UdaValue> {
+    pub fn udas(&self) -> &BTreeMap<String, UdaValue> {
         &self.udas
     }
 }
@@ -299,7 +641,7 @@ impl Task {
         &mut self.id
     }
     pub fn uuid_mut(&mut self) -> &mut Uuid {
-        &mut self.uuid
+        self.uuid.as_mut()
     }
     pub fn description_mut(&mut self) -> &mut String {
         &mut self.description
@@ -310,6 +652,33 @@ impl Task {
     pub fn end_mut(&mut self) -> &mut Option<DateTime<Utc>> {
         &mut self.end
     }
+    pub fn scheduled_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.scheduled
+    }
+    pub fn until_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.until
+    }
+    pub fn wait_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.wait
+    }
+    pub fn due_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.due
+    }
+    pub fn depends_mut(&mut self) -> &mut Vec<Uuid> {
+        self.depends.as_mut_vec()
+    }
+    pub fn imask_mut(&mut self) -> &mut Option<f64> {
+        &mut self.imask
+    }
+    pub fn mask_mut(&mut self) -> &mut Option<String> {
+        &mut self.mask
+    }
+    pub fn parent_mut(&mut self) -> &mut Option<Uuid> {
+        &mut self.parent
+    }
+    pub fn recur_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.recur
+    }
     pub fn entry_mut(&mut self) -> &mut DateTime<Utc> {
         &mut self.entry
     }
@@ -322,6 +691,7 @@ impl Task {
     pub fn status_mut(&mut self) -> &mut Status {
         &mut self.status
     }
+    /// Tags set here aren't normalized. See `add_tag`.
     pub fn tags_mut(&mut self) -> &mut Vec<String> {
         &mut self.tags
     }
@@ -331,1496 +701,5727 @@ impl Task {
     pub fn annotations_mut(&mut self) -> &mut Vec<Annotation> {
         &mut self.annotations
     }
-    pub fn udas_mut(&mut self) -> &mut HashMap<String, UdaValue> {
+    pub fn udas_mut(&mut self) -> &mut BTreeMap<String, UdaValue> {
         &mut self.udas
     }
 }
 
-/// Constructors
-impl Task {
-    pub fn from_reader(reader: impl Read) -> Result<Self, serde_json::Error> {
-        serde_json::from_reader(reader)
+/// Normalize a tag as given on the command line.
+///
+/// e.g. `+urgent` -> `urgent`
+///
+/// A leading `-` denotes tag removal in Taskwarrior and is rejected, as is whitespace within the
+/// tag.
+fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('+').unwrap_or(tag);
+    if tag.starts_with('-') {
+        return Err(format!("invalid tag, leading '-' denotes removal: {tag}"));
     }
-    /// Reads JSON from stdin and parses it into a Task.
-    ///
-    /// Only takes the first line of input.
-    pub fn from_stdin() -> Result<Self, io::Error> {
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        match serde_json::from_str(&input) {
-            Ok(task) => Ok(task),
-            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e)),
-        }
+    if tag.is_empty() {
+        return Err("invalid tag, tag is empty".to_string());
+    }
+    if tag.contains(char::is_whitespace) {
+        return Err(format!("invalid tag, contains whitespace: {tag:?}"));
     }
+    Ok(tag.to_string())
 }
 
-/// Conversion Methods
+/// Modification
 impl Task {
-    /// Convert Task to JSON object.
-    pub fn to_json(&self) -> serde_json::Value {
-        serde_json::to_value(self).unwrap()
+    /// Stamp `modified` with the current time, to the second.
+    pub fn touch(&mut self) {
+        self.modified = now_to_seconds();
     }
-    /// Convert Task to JSON string.
-    pub fn to_json_string(&self) -> String {
-        serde_json::to_string(self).unwrap()
+    /// Truncate every date to whole seconds, as serialized.
+    ///
+    /// Dates set directly, e.g. with `due_mut`, may carry fractions of a second that are lost when
+    /// serialized. After normalizing, the task equals its serialized form.
+    pub fn normalize(&mut self) {
+        for dt in [&mut self.entry, &mut self.modified] {
+            *dt = truncate_to_seconds(*dt);
+        }
+        for dt in [
+            &mut self.start,
+            &mut self.end,
+            &mut self.scheduled,
+            &mut self.until,
+            &mut self.wait,
+            &mut self.due,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *dt = truncate_to_seconds(*dt);
+        }
+        for annotation in &mut self.annotations {
+            annotation.entry = truncate_to_seconds(annotation.entry);
+        }
     }
-    /// Write JSON representation of Task to handle.
-    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<(), io::Error> {
-        match writer.write(self.to_string().as_bytes()) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
+    /// Remove the due date, stamping `modified` if there was one.
+    ///
+    /// As `task modify due:`.
+    pub fn clear_due(&mut self) {
+        if self.due.take().is_some() {
+            self.touch();
         }
     }
-    /// Write JSON representation of Task to stdout.
-    pub fn to_stdout(&self) -> Result<(), io::Error> {
-        self.to_writer(&mut io::stdout())
+    /// Remove the wait date, stamping `modified` if there was one.
+    pub fn clear_wait(&mut self) {
+        if self.wait.take().is_some() {
+            self.touch();
+        }
     }
-}
-
-/// ToString (JSON)
-///
-/// Uses JSON as this is the most common use case for converting a Task to a string.
-impl ToString for Task {
-    fn to_string(&self) -> String {
-        self.to_json_string()
+    /// Remove the scheduled date, stamping `modified` if there was one.
+    pub fn clear_scheduled(&mut self) {
+        if self.scheduled.take().is_some() {
+            self.touch();
+        }
     }
-}
-
-impl FromStr for Task {
-    type Err = serde_json::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let task: Task = serde_json::from_str(s)?;
-        Ok(task)
+    /// Remove the until date, stamping `modified` if there was one.
+    pub fn clear_until(&mut self) {
+        if self.until.take().is_some() {
+            self.touch();
+        }
+    }
+    /// Remove every date but `entry` and `modified`, stamping `modified` if any were removed.
+    ///
+    /// i.e. `start`, `end`, `scheduled`, `until`, `wait`, and `due`.
+    pub fn clear_all_dates(&mut self) {
+        let mut cleared = false;
+        for dt in [
+            &mut self.start,
+            &mut self.end,
+            &mut self.scheduled,
+            &mut self.until,
+            &mut self.wait,
+            &mut self.due,
+        ] {
+            cleared |= dt.take().is_some();
+        }
+        if cleared {
+            self.touch();
+        }
+    }
+    /// Clear the fields Taskwarrior manages itself, so the task can be passed to `task import`.
+    ///
+    /// * `id` is the working set index, assigned by Taskwarrior and changing as tasks are
+    ///   completed, so an imported one could refer to another task
+    /// * `urgency` is calculated by Taskwarrior from the other fields, and recalculated on export
+    ///
+    /// `modified` is kept, so an import doesn't look like a newer change than it is. Call `touch`
+    /// afterwards to have it refreshed.
+    pub fn sanitize_for_import(&mut self) {
+        self.id = None;
+        self.urgency = None;
+    }
+    /// Set the status, if allowed by `Status::can_transition_to`, and stamp `modified`.
+    ///
+    /// Closing the task sets `end` to now, unless it's already set, and reopening it clears `end`.
+    pub fn set_status_checked(&mut self, status: Status) -> Result<(), Error> {
+        if !self.status.can_transition_to(&status) {
+            return Err(Error::Validation(format!(
+                "invalid status transition: {:?} -> {:?}",
+                self.status, status
+            )));
+        }
+        self.status = status;
+        self.touch();
+        if self.status.is_closed() {
+            self.end.get_or_insert(self.modified);
+        } else {
+            self.end = None;
+        }
+        Ok(())
+    }
+    /// Set a field from a string, as in `task modify key:value`, and stamp `modified`.
+    ///
+    /// * Dates are `YYYYMMDDTHHMMSSZ`, `YYYY-MM-DD`, or RFC 3339
+    /// * `tags` are separated by spaces, and `depends` by commas
+    /// * Unknown names are UDAs, kept as strings
+    ///
+    /// An empty value clears the field, e.g. `due:`. Fields Taskwarrior manages itself, such as
+    /// `uuid` and `urgency`, can't be set, and required fields can't be cleared. Setting
+    /// `modified` itself leaves it as given.
+    pub fn set_field(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let date = |value: &str| {
+            parse_inline_date(value)
+                .ok_or_else(|| Error::Parse(format!("invalid date for {name}: {value:?}")))
+        };
+        let date_opt = |value: &str| match value {
+            "" => Ok(None),
+            _ => date(value).map(Some),
+        };
+        if value.is_empty() && ["description", "status", "entry", "modified"].contains(&name) {
+            return Err(Error::Validation(format!(
+                "cannot clear required field: {name}"
+            )));
+        }
+        match name {
+            "description" => self.description = value.to_string(),
+            "status" => {
+                self.status = serde_json::from_value(serde_json::Value::from(value))
+                    .map_err(|_| Error::Parse(format!("invalid status {value:?}")))?
+            }
+            "project" => self.project = value.to_string(),
+            "tags" => {
+                self.tags = value
+                    .split_whitespace()
+                    .map(normalize_tag)
+                    .collect::<Result<_, _>>()
+                    .map_err(Error::Validation)?
+            }
+            "depends" => {
+                *self.depends.as_mut_vec() = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|uuid| !uuid.is_empty())
+                    .map(|uuid| {
+                        uuid.parse()
+                            .map_err(|e| Error::Parse(format!("invalid uuid {uuid:?}: {e}")))
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            "recur" => {
+                self.recur = match value {
+                    "" => None,
+                    _ => Some(value.parse().map_err(|_| {
+                        Error::Parse(format!("invalid duration for recur: {value:?}"))
+                    })?),
+                }
+            }
+            "entry" => self.entry = date(value)?,
+            "modified" => {
+                self.modified = date(value)?;
+                return Ok(());
+            }
+            "start" => self.start = date_opt(value)?,
+            "end" => self.end = date_opt(value)?,
+            "scheduled" => self.scheduled = date_opt(value)?,
+            "until" => self.until = date_opt(value)?,
+            "wait" => self.wait = date_opt(value)?,
+            "due" => self.due = date_opt(value)?,
+            "id" | "uuid" | "urgency" | "imask" | "mask" | "parent" | "annotations" => {
+                return Err(Error::Validation(format!(
+                    "cannot set read-only field: {name}"
+                )))
+            }
+            _ if value.is_empty() => {
+                self.udas.remove(name);
+            }
+            _ => {
+                self.udas
+                    .insert(name.to_string(), UdaValue::String(value.to_string()));
+            }
+        }
+        self.touch();
+        Ok(())
+    }
+    /// Move trailing `+tag`, `project:` and `due:` tokens from the description into their fields.
+    ///
+    /// Mirrors how `task add` parses a one-line capture, e.g. `Call Bob +phone project:Work`.
+    /// Only tokens at the end of the description are taken, and the first word is always kept.
+    /// Dates for `due:` are `YYYY-MM-DD`, RFC 3339, or Taskwarrior's `YYYYMMDDTHHMMSSZ`.
+    pub fn extract_inline_metadata(&mut self) {
+        let mut description = self.description.trim_end();
+        let mut tokens = Vec::new();
+        while let Some(index) = description.rfind(char::is_whitespace) {
+            let token = match InlineToken::parse(&description[index + 1..]) {
+                Some(token) => token,
+                None => break,
+            };
+            tokens.push(token);
+            description = description[..index].trim_end();
+        }
+        if tokens.is_empty() {
+            return;
+        }
+        self.description = description.to_string();
+        // Apply in written order, so later tokens win.
+        for token in tokens.into_iter().rev() {
+            match token {
+                InlineToken::Tag(tag) => {
+                    if !self.tags.contains(&tag) {
+                        self.tags.push(tag);
+                    }
+                }
+                InlineToken::Project(project) => self.project = project,
+                InlineToken::Due(due) => self.due = Some(due),
+            }
+        }
     }
 }
 
-impl From<Task> for String {
-    fn from(task: Task) -> Self {
-        serde_json::to_string(&task).expect("task turned into string")
-    }
+/// Metadata written inline in a description.
+enum InlineToken {
+    Tag(String),
+    Project(String),
+    Due(DateTime<Utc>),
 }
 
-impl From<String> for Task {
-    fn from(s: String) -> Self {
-        Task::from_str(&s).expect("string turned into task")
+impl InlineToken {
+    fn parse(token: &str) -> Option<Self> {
+        if token.starts_with('+') {
+            return normalize_tag(token).ok().map(InlineToken::Tag);
+        }
+        let (key, value) = token.split_once(':')?;
+        if value.is_empty() {
+            return None;
+        }
+        match key {
+            "project" | "proj" => Some(InlineToken::Project(value.to_string())),
+            "due" => parse_inline_date(value).map(InlineToken::Due),
+            _ => None,
+        }
     }
 }
 
-impl From<&str> for Task {
-    fn from(s: &str) -> Self {
-        Task::from_str(s).expect("string turned into task")
+/// Parse a date given inline, e.g. `due:2022-01-31`.
+fn parse_inline_date(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = NaiveDateTime::parse_from_str(s, DATETIME_FORMAT) {
+        return Some(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
     }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    Some(DateTime::<Utc>::from_naive_utc_and_offset(
+        date.and_hms_opt(0, 0, 0)?,
+        Utc,
+    ))
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub struct Annotation {
-    #[serde(
-        serialize_with = "tw_dt_to_str_se",
-        deserialize_with = "tw_str_to_dt_de"
-    )]
-    entry: DateTime<Utc>,
-    description: String,
-}
-
-// #[derive(Debug, Serialize)]
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub enum Status {
-    #[serde(rename = "completed")]
-    Completed,
-    #[serde(rename = "pending")]
-    Pending,
-    #[serde(rename = "recurring")]
-    Recurring,
-    #[serde(rename = "deleted")]
-    Deleted,
-}
-
-#[derive(Debug, Default)]
-pub struct TaskBuilder {
-    id: Option<usize>,
-    uuid: Option<Uuid>,
-    description: Option<String>,
-    entry: Option<DateTime<Utc>>,
-    start: Option<DateTime<Utc>>,
-    end: Option<DateTime<Utc>>,
-    modified: Option<DateTime<Utc>>,
-    status: Option<Status>,
-    tags: Option<Vec<String>>,
-    annotations: Option<Vec<Annotation>>,
-    priority: Option<String>,
-    project: Option<String>,
-    wait: Option<DateTime<Utc>>,
-    due: Option<DateTime<Utc>>,
-    until: Option<DateTime<Utc>>,
-    scheduled: Option<DateTime<Utc>>,
-    recur: Option<Duration>,
-    mask: Option<String>,
-    imask: Option<f64>,
-    parent: Option<Uuid>,
-    depends: Option<Vec<Uuid>>,
-    urgency: Option<f64>,
-    udas: Option<HashMap<String, UdaValue>>,
-}
-
-impl TaskBuilder {
-    pub fn id(mut self, id: usize) -> Self {
-        self.id = Some(id);
-        self
-    }
-    pub fn uuid(mut self, uuid: &str) -> Self {
-        self.uuid = Some(Uuid::parse_str(uuid).expect("valid uuid"));
-        self
-    }
-    pub fn description<T: ToString>(mut self, description: T) -> Self {
-        self.description = Some(description.to_string());
-        self
-    }
-    pub fn entry(mut self, entry: DateTime<Utc>) -> Self {
-        self.entry = Some(entry);
-        self
-    }
-    pub fn start(mut self, start: DateTime<Utc>) -> Self {
-        self.start = Some(start);
+/// Chaining
+///
+/// For building a task inline, e.g. in tests. Unlike the other setters, these leave `modified` as
+/// it is.
+///
+/// ```rust
+/// use tasklib::prelude::*;
+///
+/// let task = Task::new("x").with_tag("home").with_project("P");
+/// assert_eq!(task.tags(), &["home"]);
+/// assert_eq!(task.project(), "P");
+/// ```
+impl Task {
+    /// Add a UDA, replacing any existing value.
+    pub fn with_uda(mut self, name: impl Into<String>, value: impl Into<UdaValue>) -> Self {
+        self.udas.insert(name.into(), value.into());
         self
     }
-    pub fn end(mut self, end: DateTime<Utc>) -> Self {
-        self.end = Some(end);
+    /// Add a tag, normalized as in `add_tag`, if the task doesn't have it already.
+    ///
+    /// An invalid tag is kept as given, and rejected by `validate`.
+    pub fn with_tag<T: ToString>(mut self, tag: T) -> Self {
+        let tag = tag.to_string();
+        let tag = normalize_tag(&tag).unwrap_or(tag);
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
         self
     }
-    pub fn modified(mut self, modified: DateTime<Utc>) -> Self {
-        self.modified = Some(modified);
+    pub fn with_project<T: ToString>(mut self, project: T) -> Self {
+        self.project = project.to_string();
         self
     }
-    pub fn status(mut self, status: Status) -> Self {
-        self.status = Some(status);
+    /// Set the due date, truncated to whole seconds as serialized.
+    pub fn with_due(mut self, due: DateTime<Utc>) -> Self {
+        self.due = Some(truncate_to_seconds(due));
         self
     }
-    pub fn tag(mut self, tag: String) -> Self {
-        if let Some(tags) = &mut self.tags {
-            tags.push(tag);
-        } else {
-            self.tags = Some(vec![tag]);
+}
+
+/// Tags
+impl Task {
+    /// Add a tag to the task.
+    ///
+    /// A leading `+` is stripped, and a leading `-` is rejected. Adding a tag that already exists
+    /// does nothing.
+    pub fn add_tag<T: ToString>(&mut self, tag: T) -> Result<(), Error> {
+        let tag = normalize_tag(&tag.to_string()).map_err(Error::Validation)?;
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
         }
-        self
+        Ok(())
     }
-    pub fn tags<T: ToString>(mut self, tags: Vec<T>) -> Self {
-        if let Some(existing_tags) = &mut self.tags {
-            existing_tags.extend(tags.into_iter().map(|t| t.to_string()));
-        } else {
-            self.tags = Some(tags.into_iter().map(|t| t.to_string()).collect());
+    /// Add several tags, stamping `modified` if any were added.
+    ///
+    /// Tags are normalized as in `add_tag`. If any tag is invalid, no tags are added.
+    pub fn add_tags(&mut self, tags: &[&str]) -> Result<(), Error> {
+        let tags = tags
+            .iter()
+            .map(|tag| normalize_tag(tag))
+            .collect::<Result<Vec<String>, String>>()
+            .map_err(Error::Validation)?;
+        let len = self.tags.len();
+        for tag in tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
         }
-        self
+        if self.tags.len() != len {
+            self.touch();
+        }
+        Ok(())
     }
-    pub fn annotations(mut self, annotations: Vec<Annotation>) -> Self {
-        self.annotations = Some(annotations);
-        self
+    /// Remove several tags, stamping `modified` if any were removed.
+    ///
+    /// A leading `+` is ignored, so `+urgent` removes `urgent`.
+    pub fn remove_tags(&mut self, tags: &[&str]) {
+        let tags: Vec<&str> = tags
+            .iter()
+            .map(|tag| tag.trim().strip_prefix('+').unwrap_or(tag.trim()))
+            .collect();
+        self.retain_tags_where(|tag| !tags.contains(&tag));
     }
-    pub fn priority(mut self, priority: String) -> Self {
-        self.priority = Some(priority);
-        self
+    /// Keep only the tags in `keep`, stamping `modified` if any were removed.
+    pub fn retain_tags(&mut self, keep: &HashSet<String>) {
+        self.retain_tags_where(|tag| keep.contains(tag));
     }
-    pub fn project<T: ToString>(mut self, project: T) -> Self {
-        self.project = Some(project.to_string());
-        self
+    /// Tags on both this task and `other`, in this task's order.
+    pub fn tags_in_common(&self, other: &Task) -> Vec<String> {
+        self.tags
+            .iter()
+            .filter(|tag| other.tags.contains(tag))
+            .cloned()
+            .collect()
     }
-    pub fn wait(mut self, wait: DateTime<Utc>) -> Self {
-        self.wait = Some(wait);
-        self
+    fn retain_tags_where<F: Fn(&str) -> bool>(&mut self, keep: F) {
+        let len = self.tags.len();
+        self.tags.retain(|tag| keep(tag));
+        if self.tags.len() != len {
+            self.touch();
+        }
     }
-    pub fn scheduled(mut self, scheduled: DateTime<Utc>) -> Self {
-        self.scheduled = Some(scheduled);
-        self
+}
+
+/// Shortest UUID prefix accepted as a reference to a task, as in Taskwarrior.
+const MIN_SHORT_UUID_LEN: usize = 8;
+
+/// Identity
+impl Task {
+    /// Whether `needle` refers to this task, as a full UUID or a prefix of one.
+    ///
+    /// Prefixes are case-insensitive, may include hyphens, and must be at least 8 characters,
+    /// e.g. `d67fce70`.
+    pub fn matches_uuid_or_short(&self, needle: &str) -> bool {
+        let needle = needle.trim();
+        if needle.len() < MIN_SHORT_UUID_LEN {
+            return false;
+        }
+        let uuid = self.uuid.hyphenated().to_string();
+        uuid.get(..needle.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(needle))
     }
-    pub fn recur(mut self, recur: Duration) -> Self {
-        self.recur = Some(recur);
-        self
+    /// Compare tasks by UUID, a stable, total order for identity, not urgency.
+    ///
+    /// e.g. `tasks.sort_by(Task::canonical_cmp)` before snapshotting. Tasks sharing a UUID
+    /// compare equal, even if their other fields differ.
+    pub fn canonical_cmp(&self, other: &Task) -> Ordering {
+        self.uuid().cmp(other.uuid())
     }
-    pub fn mask(mut self, mask: String) -> Self {
-        self.mask = Some(mask);
-        self
+    /// Whether the UUID was generated when deserializing, as the task didn't have one.
+    ///
+    /// Changing the UUID with `uuid_mut` clears this.
+    pub fn uuid_synthesized(&self) -> bool {
+        self.uuid.synthesized
     }
-    pub fn imask(mut self, imask: f64) -> Self {
-        self.imask = Some(imask);
-        self
+}
+
+/// Identity ordering, by UUID. See `Task::canonical_cmp`.
+///
+/// This isn't urgency. Tasks sharing a UUID but differing elsewhere aren't equal, so are
+/// unordered. `Ord` isn't implemented, as urgency may be NaN so a task may not equal itself, so
+/// sort with `sort_by(Task::canonical_cmp)` instead.
+impl PartialOrd for Task {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.canonical_cmp(other) {
+            Ordering::Equal if self != other => None,
+            ordering => Some(ordering),
+        }
     }
-    pub fn parent(mut self, parent: &str) -> Self {
-        self.parent = Some(Uuid::parse_str(parent).expect("valid uuid"));
-        self
+}
+
+/// Status
+impl Task {
+    /// Whether the task is still to be done. See `Status::is_open`.
+    ///
+    /// Waiting tasks, pending until their `wait` date, are open too, whatever the time, as are
+    /// recurrence templates. See `is_waiting` to tell waiting tasks apart.
+    pub fn is_open(&self) -> bool {
+        self.status.is_open()
     }
-    pub fn urgency(mut self, urgency: f64) -> Self {
-        self.urgency = Some(urgency);
-        self
+    /// Whether the task is pending, but hidden until its `wait` date, as of `now`.
+    pub fn is_waiting(&self, now: DateTime<Utc>) -> bool {
+        self.status == Status::Pending && self.wait.is_some_and(|wait| wait > now)
     }
-    pub fn uda(mut self, name: String, uda: UdaValue) -> Self {
-        self.udas.get_or_insert_with(HashMap::new).insert(name, uda);
-        self
+    /// Start working on the task at `at`, as `task start`, and stamp `modified`.
+    ///
+    /// Only a pending task that isn't already started can be started.
+    pub fn start_at(&mut self, at: DateTime<Utc>) -> Result<(), StateError> {
+        if self.status != Status::Pending {
+            return Err(StateError::InvalidStatus {
+                action: "start",
+                status: self.status.clone(),
+            });
+        }
+        if self.start.is_some() {
+            return Err(StateError::AlreadyStarted);
+        }
+        self.start = Some(truncate_to_seconds(at));
+        self.touch();
+        Ok(())
     }
-}
-
-impl TaskBuilder {
-    pub fn new() -> Self {
-        TaskBuilder {
-            ..Default::default()
+    /// Stop working on the task, as `task stop`, and stamp `modified`.
+    ///
+    /// Only a started task can be stopped.
+    pub fn stop(&mut self) -> Result<(), StateError> {
+        if self.start.take().is_none() {
+            return Err(StateError::NotStarted);
         }
+        self.touch();
+        Ok(())
     }
-    pub fn build(self) -> Task {
-        Task {
-            id: self.id,
-            uuid: self.uuid.unwrap(),
-            description: self.description.unwrap_or("".to_string()),
-            entry: self.entry.unwrap_or(Utc::now()),
-            start: self.start,
-            end: self.end,
-            modified: self.modified.unwrap(),
-            status: self.status.unwrap(),
-            tags: self.tags.unwrap_or(vec![]),
-            annotations: self.annotations.unwrap_or(vec![]),
-            project: self.project.unwrap_or("".to_string()),
-            scheduled: self.scheduled,
-            until: self.until,
-            recur: self.recur,
-            mask: self.mask,
-            imask: self.imask,
-            parent: self.parent,
-            depends: self.depends.unwrap_or(vec![]),
-            wait: self.wait,
-            due: self.due,
-            urgency: self.urgency,
-            udas: self.udas.unwrap_or(HashMap::new()),
+    /// Complete the task at `at`, as `task done`, and stamp `modified`.
+    ///
+    /// Only a pending task can be completed. A started task is stopped.
+    pub fn complete_at(&mut self, at: DateTime<Utc>) -> Result<(), StateError> {
+        if self.status != Status::Pending {
+            return Err(StateError::InvalidStatus {
+                action: "complete",
+                status: self.status.clone(),
+            });
         }
+        self.status = Status::Completed;
+        self.start = None;
+        self.end = Some(truncate_to_seconds(at));
+        self.touch();
+        Ok(())
     }
 }
 
-mod udas {
-
-    use std::any::Any;
-    use std::fmt;
-
-    use chrono::{self, offset::Utc, DateTime};
-    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
-
-    use super::tw_dt_to_str_opt_se;
-    use super::tw_dt_to_str_se;
-    use super::tw_str_to_dt_de;
-    use super::tw_str_to_dt_opt_de;
-    use super::Duration;
-    use super::DATETIME_FORMAT;
-
-    #[derive(Debug, Clone, PartialEq)]
-    pub enum UdaValue {
-        String(String),
-        Numeric(f64),
-        Date(DateTime<Utc>),
-        Duration(Duration),
+/// Validation
+impl Task {
+    /// Check that fields don't contradict each other.
+    ///
+    /// As `TaskBuilder::try_build`, and that timestamps are in order:
+    ///
+    /// * `modified` isn't before `entry`
+    /// * `end` isn't before `start`
+    ///
+    /// Hand-written JSON may get these wrong. See `Task::fix_timestamps`.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut problems = self.inconsistencies();
+        if self.modified < self.entry {
+            problems.push("modified is before entry".to_string());
+        }
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if end < start {
+                problems.push("end is before start".to_string());
+            }
+        }
+        if !problems.is_empty() {
+            return Err(Error::Validation(format!(
+                "inconsistent fields: {}",
+                problems.join(", ")
+            )));
+        }
+        Ok(())
     }
-
-    use std::error::Error;
-
-    #[derive(Debug)]
-    struct ParseError(String);
-
-    impl std::fmt::Display for ParseError {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "{}", self.0)
+    /// Put timestamps in order, moving `modified` up to `entry`, and `end` up to `start`.
+    ///
+    /// See `Task::validate`.
+    pub fn fix_timestamps(&mut self) {
+        self.modified = self.modified.max(self.entry);
+        if let (Some(start), Some(end)) = (self.start, self.end.as_mut()) {
+            *end = (*end).max(start);
         }
     }
-
-    impl Error for ParseError {}
-
-    /// Converters
-    impl UdaValue {
-        pub fn as_uda_string(&self) -> Result<Self, Box<dyn Error>> {
-            match self {
-                UdaValue::String(_) => Ok(self.clone()),
-                UdaValue::Numeric(n) => Ok(Self::String(n.to_string())),
-                UdaValue::Date(dt) => Ok(Self::String(dt.format(DATETIME_FORMAT).to_string())),
-                UdaValue::Duration(d) => Ok(Self::String(d.to_string())),
-            }
+    /// Fields that contradict each other, as checked when building.
+    fn inconsistencies(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.status == Status::Pending && self.end.is_some() {
+            problems.push("end is set on a pending task".to_string());
         }
-        pub fn as_uda_numeric(&self) -> Result<Self, Box<dyn Error>> {
-            match self {
-                UdaValue::String(s) => Ok(Self::Numeric(s.parse::<f64>()?)),
-                UdaValue::Numeric(_) => Ok(self.clone()),
-                UdaValue::Date(_) => Err(Box::new(ParseError(
-                    "cannot parse DateTime to a numeric value".to_string(),
-                ))),
-                UdaValue::Duration(_) => Err(Box::new(ParseError(
-                    "cannot parse Duration to a numeric value".to_string(),
-                ))),
+        for tag in &self.tags {
+            if normalize_tag(tag).as_deref() != Ok(tag.as_str()) {
+                problems.push(format!("tag {tag:?} is invalid"));
             }
         }
-        pub fn as_uda_date(&self) -> Result<Self, Box<dyn Error>> {
-            match self {
-                UdaValue::String(s) => Ok(Self::Date(DateTime::<Utc>::from_naive_utc_and_offset(
-                    chrono::NaiveDateTime::parse_from_str(s, DATETIME_FORMAT)
-                        .expect("string turned into datetime"),
-                    Utc,
-                ))),
-                UdaValue::Numeric(_) => Err(Box::new(ParseError(
-                    "cannot convert number to date".to_string(),
-                ))),
-                UdaValue::Date(_) => Ok(self.clone()),
-                UdaValue::Duration(_) => Err(Box::new(ParseError(
-                    "cannot convert duration to date".to_string(),
-                ))),
+        if let Some(due) = self.due {
+            if due < self.entry {
+                problems.push("due is before entry".to_string());
             }
-        }
-        pub fn as_uda_duration(&self) -> Result<Self, Box<dyn Error>> {
-            match self {
-                UdaValue::String(s) => Ok(Self::Duration(s.parse::<Duration>()?)),
-                UdaValue::Numeric(_) => Err(Box::new(ParseError(
-                    "cannot convert number to duration".to_string(),
-                ))),
-                UdaValue::Date(_) => Err(Box::new(ParseError(
-                    "cannot convert date to duration".to_string(),
-                ))),
-                UdaValue::Duration(_) => Ok(self.clone()),
+            if self.wait.is_some_and(|wait| wait > due) {
+                problems.push("wait is after due".to_string());
             }
         }
+        problems
     }
+}
 
-    impl Serialize for UdaValue {
-        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-            match self {
-                UdaValue::String(s) => serializer.serialize_str(s),
-                UdaValue::Numeric(n) => serializer.serialize_f64(*n),
-                UdaValue::Date(dt) => {
-                    serializer.serialize_str(&dt.format(DATETIME_FORMAT).to_string())
-                }
-                UdaValue::Duration(d) => serializer.serialize_str(&d.to_string()),
-            }
+/// Most periods counted when working out the instances of a recurrence template.
+///
+/// Keeps a short `recur` over a long time from running away, e.g. 100,000 hours is over 11 years.
+const MAX_OCCURRENCES: u32 = 100_000;
+
+/// Recurrence
+impl Task {
+    /// Whether this is the template that recurring instances are created from.
+    ///
+    /// Templates have a `recurring` status and a `recur` period, and no `parent`.
+    pub fn is_recurrence_template(&self) -> bool {
+        self.status == Status::Recurring && self.recur.is_some() && self.parent.is_none()
+    }
+    /// Whether this is an instance created from a recurrence template.
+    ///
+    /// Instances have a `parent`, the template's UUID, and an `imask`, their index in the
+    /// template's `mask`.
+    pub fn is_recurrence_instance(&self) -> bool {
+        self.status != Status::Recurring && self.parent.is_some() && self.imask.is_some()
+    }
+    /// The UUID of the recurrence template, for instances.
+    pub fn parent_uuid(&self) -> Option<&Uuid> {
+        self.parent.as_ref()
+    }
+    /// When the task is next due, as of `now`.
+    ///
+    /// For a recurrence template, this is the first due date of an instance at or after `now`,
+    /// counting in `recur` periods from the template's `due`, or `entry` without one. Returns
+    /// `None` if that's past `until`.
+    ///
+    /// At most `MAX_OCCURRENCES` (100,000) periods are counted, so this also returns `None` when
+    /// `now` is further out than that, e.g. for an hourly template entered 12 years ago.
+    ///
+    /// For any other task, this is `due`.
+    pub fn effective_due(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !self.is_recurrence_template() {
+            return self.due;
         }
+        self.occurrences().find(|due| *due >= now)
     }
-
-    /// Getters (Immutable)
-    impl UdaValue {
-        /// Retrieve the inner value.
-        pub fn inner(&self) -> &dyn Any {
-            match self {
-                UdaValue::String(s) => s,
-                UdaValue::Numeric(n) => n,
-                UdaValue::Date(dt) => dt,
-                UdaValue::Duration(d) => d,
-            }
+    /// Due dates of the instances of a recurrence template within `[from, to)`.
+    ///
+    /// Counts in `recur` periods from the template's `due`, or `entry` without one, up to `until`.
+    /// At most `MAX_OCCURRENCES` periods are counted.
+    ///
+    /// For any other task, this is `due`, if it's within `[from, to)`.
+    pub fn recurrence_dates(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        if !self.is_recurrence_template() {
+            return self
+                .due
+                .filter(|due| in_range(Some(due), &from, &to))
+                .into_iter()
+                .collect();
         }
+        self.occurrences()
+            .skip_while(|due| *due < from)
+            .take_while(|due| *due < to)
+            .collect()
     }
+    /// Due dates of the instances of a recurrence template, in order, up to `until`.
+    fn occurrences(&self) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        let base = self.due.unwrap_or(self.entry);
+        let recur = self.recur.as_ref();
+        let mut previous: Option<DateTime<Utc>> = None;
+        (0..MAX_OCCURRENCES)
+            .map_while(move |n| {
+                let recur = recur?;
+                let next = match previous {
+                    // Weekdays depend on the day before
+                    Some(previous) if recur.is_weekdays() => recur.add_to(previous)?,
+                    _ => recur.add_times_to(base, n)?,
+                };
+                // A zero period would repeat forever
+                if previous.is_some_and(|previous| next <= previous) {
+                    return None;
+                }
+                previous = Some(next);
+                Some(next)
+            })
+            .take_while(move |due| self.until.is_none_or(|until| *due <= until))
+    }
+}
 
-    impl fmt::Display for UdaValue {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let buffer: String = match self {
-                UdaValue::String(s) => s.clone(),
-                UdaValue::Numeric(n) => n.to_string(),
-                UdaValue::Date(dt) => dt.format(crate::DATETIME_FORMAT).to_string(),
-                UdaValue::Duration(d) => d.clone().into(),
-            };
-            write!(f, "{buffer}")
+/// Annotations
+impl Task {
+    /// Remove the first annotation matching `text`, and stamp `modified`.
+    ///
+    /// Mirrors `task denotate`: an exact match is preferred, otherwise the first annotation
+    /// containing `text` is removed. Returns whether an annotation was removed.
+    ///
+    /// Empty or whitespace-only text matches nothing.
+    pub fn denotate(&mut self, text: &str) -> bool {
+        if text.trim().is_empty() {
+            return false;
+        }
+        let index = self
+            .annotations
+            .iter()
+            .position(|a| a.description == text)
+            .or_else(|| {
+                self.annotations
+                    .iter()
+                    .position(|a| a.description.contains(text))
+            });
+        match index {
+            Some(index) => self.remove_annotation_at(index).is_some(),
+            None => false,
         }
     }
-
-    impl From<UdaValue> for String {
-        fn from(uda_value: UdaValue) -> Self {
-            match uda_value {
-                UdaValue::String(s) => s,
-                UdaValue::Numeric(n) => n.to_string(),
-                UdaValue::Date(dt) => dt.format(crate::DATETIME_FORMAT).to_string(),
-                UdaValue::Duration(d) => d.into(),
-            }
+    /// Remove the annotation at `index`, and stamp `modified`.
+    ///
+    /// Returns `None` if `index` is out of range.
+    pub fn remove_annotation_at(&mut self, index: usize) -> Option<Annotation> {
+        if index >= self.annotations.len() {
+            return None;
         }
+        self.touch();
+        Some(self.annotations.remove(index))
     }
+}
 
-    impl From<String> for UdaValue {
-        fn from(s: String) -> Self {
-            UdaValue::String(s)
+/// Projects
+impl Task {
+    /// The project split into its levels.
+    ///
+    /// e.g. `Work.ClientA.Phase1` -> `["Work", "ClientA", "Phase1"]`
+    ///
+    /// Returns an empty vec if there is no project.
+    pub fn project_hierarchy(&self) -> Vec<&str> {
+        if self.project.is_empty() {
+            return Vec::new();
         }
+        self.project.split('.').collect()
     }
-
-    impl From<&str> for UdaValue {
-        fn from(s: &str) -> Self {
-            UdaValue::String(s.to_string())
+    /// Move the task from project `from`, or one of its subprojects, to `to`.
+    ///
+    /// e.g. renaming `Work` to `Job` moves `Work.ClientA` to `Job.ClientA`, but leaves
+    /// `Workshop` alone. An empty `from` matches nothing, not tasks without a project. Returns
+    /// whether the project changed.
+    pub fn rename_project(&mut self, from: &str, to: &str) -> bool {
+        if from.is_empty() {
+            return false;
         }
+        let rest = match self.project.strip_prefix(from) {
+            Some(rest) if rest.is_empty() || rest.starts_with('.') => rest,
+            _ => return false,
+        };
+        let project = if to.is_empty() {
+            rest.trim_start_matches('.').to_string()
+        } else {
+            format!("{to}{rest}")
+        };
+        if project == self.project {
+            return false;
+        }
+        self.project = project;
+        self.touch();
+        true
     }
+}
 
-    impl<'de> serde::Deserialize<'de> for UdaValue {
-        fn deserialize<D>(deserializer: D) -> Result<UdaValue, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct UdaValueVisitor;
-
-            impl<'de> serde::de::Visitor<'de> for UdaValueVisitor {
-                type Value = UdaValue;
-
-                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                    formatter.write_str("a string or a number")
-                }
-
-                fn visit_str<E>(self, value: &str) -> Result<UdaValue, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(UdaValue::String(value.to_string()))
-                }
-
-                fn visit_i64<E>(self, value: i64) -> Result<UdaValue, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(UdaValue::Numeric(value as f64))
-                }
+/// Dependencies
+impl Task {
+    /// Whether the task depends directly on `uuid`.
+    pub fn depends_on(&self, uuid: &Uuid) -> bool {
+        self.depends.contains(uuid)
+    }
+    /// Remove a dependency, returning whether it was present.
+    pub fn remove_dependency(&mut self, uuid: &Uuid) -> bool {
+        let len = self.depends.len();
+        self.depends.as_mut_vec().retain(|depends| depends != uuid);
+        self.depends.len() != len
+    }
+}
 
-                fn visit_u64<E>(self, value: u64) -> Result<UdaValue, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(UdaValue::Numeric(value as f64))
+/// References
+impl Task {
+    /// HTTP(S) URLs in the description and annotations, in order of appearance.
+    ///
+    /// Duplicates are removed. Surrounding brackets, quotes, and trailing punctuation are not
+    /// taken as part of a URL.
+    pub fn urls(&self) -> Vec<String> {
+        let texts = std::iter::once(self.description.as_str())
+            .chain(self.annotations.iter().map(|a| a.description.as_str()));
+        let mut urls: Vec<String> = Vec::new();
+        for text in texts {
+            for url in find_urls(text) {
+                if !urls.iter().any(|u| u == url) {
+                    urls.push(url.to_string());
                 }
+            }
+        }
+        urls
+    }
+}
 
-                fn visit_f64<E>(self, value: f64) -> Result<UdaValue, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(UdaValue::Numeric(value))
-                }
+/// Find HTTP(S) URLs in text.
+///
+/// Trailing punctuation is trimmed, and so is a closing bracket unless it's balanced within the
+/// URL, e.g. `(see https://en.wikipedia.org/wiki/Rust_(programming_language))`.
+fn find_urls(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace().filter_map(|word| {
+        let start = word.find("http://").or_else(|| word.find("https://"))?;
+        let mut url = &word[start..];
+        while let Some(c) = url.chars().next_back() {
+            let unbalanced = |open: char| url.matches(open).count() < url.matches(c).count();
+            let trim = match c {
+                '.' | ',' | ';' | ':' | '!' | '?' | '>' | '"' | '\'' => true,
+                ')' => unbalanced('('),
+                ']' => unbalanced('['),
+                '}' => unbalanced('{'),
+                _ => false,
+            };
+            if !trim {
+                break;
             }
+            url = &url[..url.len() - c.len_utf8()];
+        }
+        // Only a scheme
+        if url.ends_with("//") {
+            return None;
+        }
+        Some(url)
+    })
+}
 
-            deserializer.deserialize_any(UdaValueVisitor)
+/// Attribute Paths
+impl Task {
+    /// The value at a dotted path, as Taskwarrior's `_get`, e.g. for report templates.
+    ///
+    /// * A field or UDA name gives its value as serialized, with lists such as `tags` joined by
+    ///   commas, e.g. `due` -> `20220131T083000Z`
+    /// * `<date>.year`, `.month`, `.day`, `.week` (ISO 8601), `.weekday` (0 is Sunday), `.julian`
+    ///   (day of the year), `.hour`, `.minute`, and `.second`, in UTC, for date fields and UDAs
+    /// * `tags.count`, `annotations.count`, and `depends.count`
+    ///
+    /// Returns `None` for an unknown path or a field that isn't set.
+    pub fn get_path(&self, path: &str) -> Option<String> {
+        use chrono::{Datelike, Timelike};
+
+        let Some((name, accessor)) = path.split_once('.') else {
+            return match self.to_json().get(path)? {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                serde_json::Value::Array(values) => values
+                    .iter()
+                    .map(|value| value.as_str())
+                    .collect::<Option<Vec<_>>>()
+                    .map(|values| values.join(",")),
+                _ => None,
+            };
+        };
+        let count = match (name, accessor) {
+            ("tags", "count") => Some(self.tags.len()),
+            ("annotations", "count") => Some(self.annotations.len()),
+            ("depends", "count") => Some(self.depends.len()),
+            _ => None,
+        };
+        if let Some(count) = count {
+            return Some(count.to_string());
         }
+        let date = match name {
+            "entry" => self.entry,
+            "modified" => self.modified,
+            "start" => self.start?,
+            "end" => self.end?,
+            "scheduled" => self.scheduled?,
+            "until" => self.until?,
+            "wait" => self.wait?,
+            "due" => self.due?,
+            _ => match self.udas.get(name)? {
+                UdaValue::Date(dt) => *dt,
+                _ => return None,
+            },
+        };
+        let value = match accessor {
+            "year" => return Some(date.year().to_string()),
+            "month" => date.month(),
+            "day" => date.day(),
+            "week" => date.iso_week().week(),
+            "weekday" => date.weekday().num_days_from_sunday(),
+            "julian" => date.ordinal(),
+            "hour" => date.hour(),
+            "minute" => date.minute(),
+            "second" => date.second(),
+            _ => return None,
+        };
+        Some(value.to_string())
     }
+}
 
-    /// Implement tasklib::Duration into UdaValue
-    impl From<Duration> for UdaValue {
-        fn from(d: Duration) -> Self {
-            UdaValue::Duration(d)
+/// UDAs
+impl Task {
+    /// Read a UDA as a duration.
+    ///
+    /// Strings are parsed as durations. Returns `None` if the UDA doesn't exist, can't be parsed,
+    /// or is of an incompatible type.
+    pub fn duration_uda(&self, name: &str) -> Option<Duration> {
+        match self.udas.get(name)? {
+            UdaValue::String(s) => s.parse::<Duration>().ok(),
+            UdaValue::Duration(d) => Some(d.clone()),
+            UdaValue::Numeric(_) | UdaValue::Date(_) => None,
         }
     }
-
-    /// Implement chrono::DateTime into UdaValue
-    impl From<DateTime<Utc>> for UdaValue {
-        fn from(d: DateTime<Utc>) -> Self {
-            UdaValue::Date(d)
+    /// The priority, stored as the `priority` UDA.
+    ///
+    /// Returns `None` if there's no priority or it isn't one of `H`, `M`, or `L`.
+    pub fn priority(&self) -> Option<Priority> {
+        match self.udas.get("priority")? {
+            UdaValue::String(s) => s.parse().ok(),
+            _ => None,
         }
     }
-
-    /// Implement == against string
-    impl PartialEq<String> for UdaValue {
-        fn eq(&self, other: &String) -> bool {
-            match self {
-                UdaValue::String(s) => s == other,
-                _ => false,
+    /// The task without any UDAs.
+    pub fn without_udas(mut self) -> Task {
+        self.udas.clear();
+        self
+    }
+    /// Keep only the UDAs named in `keep`.
+    pub fn retain_udas(&mut self, keep: &HashSet<String>) {
+        self.udas.retain(|name, _| keep.contains(name));
+    }
+    /// Rename a UDA, replacing any existing UDA named `to`.
+    ///
+    /// Returns whether a UDA named `from` existed.
+    pub fn rename_uda(&mut self, from: &str, to: &str) -> bool {
+        match self.udas.remove(from) {
+            Some(value) => {
+                self.udas.insert(to.to_string(), value);
+                true
             }
+            None => false,
         }
     }
+    /// Set a UDA to a duration, replacing any existing value.
+    pub fn set_duration_uda<T: ToString>(&mut self, name: T, duration: Duration) {
+        self.udas
+            .insert(name.to_string(), UdaValue::Duration(duration));
+    }
+}
 
-    /// Implement == against &str
-    impl PartialEq<str> for UdaValue {
-        fn eq(&self, other: &str) -> bool {
-            match self {
-                UdaValue::String(s) => s == other,
-                _ => false,
+/// Days ahead that a task counts as due, as Taskwarrior's `rc.due` default.
+const DUE_DAYS: u32 = 7;
+
+/// Virtual Tags
+impl Task {
+    /// Taskwarrior's virtual tags that apply to the task, as of `now`.
+    ///
+    /// Only those that can be worked out from the task alone are included, so `BLOCKED`,
+    /// `BLOCKING`, and `UNBLOCKED`, which need other tasks, and the calendar tags such as `TODAY`,
+    /// which need a time zone, are left out.
+    ///
+    /// e.g. `["OVERDUE", "PENDING", "PROJECT", "READY", "TAGGED"]`
+    ///
+    /// `DUE` applies to tasks due within 7 days, Taskwarrior's default. See
+    /// `Task::virtual_tags_with`.
+    pub fn virtual_tags(&self, now: DateTime<Utc>) -> Vec<&'static str> {
+        self.virtual_tags_with(now, &Duration::days(DUE_DAYS))
+    }
+    /// Taskwarrior's virtual tags that apply to the task, as of `now`, with `DUE` applying to
+    /// tasks due within `due_horizon`, as `rc.due`. See `Task::virtual_tags`.
+    pub fn virtual_tags_with(
+        &self,
+        now: DateTime<Utc>,
+        due_horizon: &Duration,
+    ) -> Vec<&'static str> {
+        let pending = self.status == Status::Pending;
+        let waiting = self.is_waiting(now);
+        let mut tags = Vec::new();
+        if pending && self.start.is_some() {
+            tags.push("ACTIVE");
+        }
+        if !self.annotations.is_empty() {
+            tags.push("ANNOTATED");
+        }
+        if self.parent.is_some() {
+            tags.push("CHILD");
+        }
+        if self.status == Status::Completed {
+            tags.push("COMPLETED");
+        }
+        if self.status == Status::Deleted {
+            tags.push("DELETED");
+        }
+        if pending {
+            match self.due_status(due_horizon, now) {
+                DueStatus::DueSoon => tags.push("DUE"),
+                DueStatus::Overdue => tags.push("OVERDUE"),
+                DueStatus::Later | DueStatus::None => {}
             }
         }
+        if self.is_recurrence_template() {
+            tags.push("PARENT");
+        }
+        if pending {
+            tags.push("PENDING");
+        }
+        if self.priority().is_some() {
+            tags.push("PRIORITY");
+        }
+        if !self.project.is_empty() {
+            tags.push("PROJECT");
+        }
+        if pending && !waiting && self.scheduled.is_none_or(|scheduled| scheduled <= now) {
+            tags.push("READY");
+        }
+        if self.status == Status::Recurring || self.recur.is_some() {
+            tags.push("RECURRING");
+        }
+        if self.scheduled.is_some() {
+            tags.push("SCHEDULED");
+        }
+        if !self.tags.is_empty() {
+            tags.push("TAGGED");
+        }
+        if !self.udas.is_empty() {
+            tags.push("UDA");
+        }
+        if self.until.is_some() {
+            tags.push("UNTIL");
+        }
+        if waiting {
+            tags.push("WAITING");
+        }
+        tags
     }
+}
 
-    /// Implement == against f64
-    impl PartialEq<f64> for UdaValue {
-        fn eq(&self, other: &f64) -> bool {
-            match self {
-                UdaValue::Numeric(n) => n == other,
-                _ => false,
-            }
+/// Date Range Queries
+///
+/// Ranges are half-open, `[start, end)`. Tasks without the relevant date never match.
+impl Task {
+    /// Whether the task is due within `[start, end)`.
+    pub fn due_between(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> bool {
+        in_range(self.due.as_ref(), start, end)
+    }
+    /// Whether the task is due within `[now, now + horizon)`, whatever its status.
+    ///
+    /// A horizon too long to add to `now` has no end.
+    pub fn is_due_within(&self, horizon: &Duration, now: DateTime<Utc>) -> bool {
+        let end = i64::try_from(horizon.num_seconds())
+            .ok()
+            .and_then(chrono::Duration::try_seconds)
+            .and_then(|horizon| now.checked_add_signed(horizon));
+        self.due
+            .is_some_and(|due| due >= now && end.is_none_or(|end| due < end))
+    }
+    /// When the task is due, as of `now`, whatever its status.
+    ///
+    /// `DueSoon` within `[now, now + horizon)`, as `Task::is_due_within`.
+    pub fn due_status(&self, horizon: &Duration, now: DateTime<Utc>) -> DueStatus {
+        match self.due {
+            None => DueStatus::None,
+            Some(due) if due < now => DueStatus::Overdue,
+            Some(_) if self.is_due_within(horizon, now) => DueStatus::DueSoon,
+            Some(_) => DueStatus::Later,
         }
     }
+    /// Whether the task was entered within `[start, end)`.
+    pub fn entered_between(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> bool {
+        in_range(Some(&self.entry), start, end)
+    }
+    /// Whether the task was completed within `[start, end)`.
+    ///
+    /// Uses `end`, and only matches tasks with a completed status.
+    pub fn completed_between(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> bool {
+        self.status == Status::Completed && in_range(self.end.as_ref(), start, end)
+    }
+}
 
-    /// Implement == against i64
-    impl PartialEq<i64> for UdaValue {
-        fn eq(&self, other: &i64) -> bool {
-            match self {
-                UdaValue::Numeric(n) => *n as i64 == *other,
-                _ => false,
-            }
-        }
+/// Whether `dt` is within `[start, end)`.
+fn in_range(dt: Option<&DateTime<Utc>>, start: &DateTime<Utc>, end: &DateTime<Utc>) -> bool {
+    match dt {
+        Some(dt) => start <= dt && dt < end,
+        None => false,
     }
+}
 
-    /// Implement == against DateTime<Utc>
-    impl PartialEq<DateTime<Utc>> for UdaValue {
-        fn eq(&self, other: &DateTime<Utc>) -> bool {
-            match self {
-                UdaValue::Date(d) => d == other,
-                _ => false,
-            }
+/// Constructors
+impl Task {
+    /// A new pending task, with a random UUID, entered and modified now.
+    pub fn new<T: ToString>(description: T) -> Self {
+        let now = now_to_seconds();
+        Task {
+            id: None,
+            uuid: Uuid::new_v4().into(),
+            description: description.to_string(),
+            start: None,
+            end: None,
+            entry: now,
+            scheduled: None,
+            until: None,
+            wait: None,
+            due: None,
+            depends: Depends::default(),
+            imask: None,
+            mask: None,
+            parent: None,
+            recur: None,
+            modified: now,
+            project: String::new(),
+            status: Status::Pending,
+            tags: Vec::new(),
+            urgency: None,
+            annotations: Vec::new(),
+            udas: BTreeMap::new(),
+            source: Source::default(),
         }
     }
-
-    /// Implement == against Duration
-    impl PartialEq<Duration> for UdaValue {
-        fn eq(&self, other: &Duration) -> bool {
-            match self {
-                UdaValue::Duration(d) => d == other,
-                _ => false,
+    pub fn from_reader(reader: impl Read) -> Result<Self, Error> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+    /// Build a task from column names and values, as in `task add key:value`.
+    ///
+    /// * Dates are `YYYYMMDDTHHMMSSZ`, `YYYY-MM-DD`, or RFC 3339
+    /// * `tags` are separated by spaces, and `depends` by commas
+    /// * Unknown keys are UDAs, kept as strings
+    ///
+    /// `uuid`, `description`, `status`, `entry`, and `modified` are required.
+    pub fn from_map(map: HashMap<String, String>) -> Result<Task, Error> {
+        for field in ["uuid", "description", "status", "entry", "modified"] {
+            if !map.contains_key(field) {
+                return Err(BuildError::Missing(field).into());
             }
         }
+        let mut object = serde_json::Map::new();
+        for (key, value) in map {
+            let json = match key.as_str() {
+                "start" | "end" | "entry" | "scheduled" | "until" | "wait" | "due" | "modified" => {
+                    match parse_inline_date(&value) {
+                        Some(dt) => serde_json::Value::from(dt.format(DATETIME_FORMAT).to_string()),
+                        None => {
+                            return Err(Error::Parse(format!("invalid date for {key}: {value:?}")))
+                        }
+                    }
+                }
+                "tags" => value
+                    .split_whitespace()
+                    .map(normalize_tag)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(Error::Validation)?
+                    .into(),
+                "id" => value
+                    .parse::<usize>()
+                    .map_err(|e| Error::Parse(format!("invalid id {value:?}: {e}")))?
+                    .into(),
+                "imask" | "urgency" => value
+                    .parse::<f64>()
+                    .map_err(|e| Error::Parse(format!("invalid {key} {value:?}: {e}")))?
+                    .into(),
+                "annotations" => {
+                    return Err(Error::Parse(
+                        "annotations can't be given as a string".to_string(),
+                    ))
+                }
+                _ => serde_json::Value::from(value),
+            };
+            object.insert(key, json);
+        }
+        Ok(serde_json::from_value(serde_json::Value::Object(object))?)
     }
-
-    /// Represents a Taskwarrior UDA
+    /// Parse a task, accepting built-in field names in any case.
     ///
-    /// <https://taskwarrior.org/docs/udas/>
+    /// e.g. `"UUID"` and `"Status"` are read as `uuid` and `status`. Other keys, such as UDAs,
+    /// are left as they are. Parsing with `FromStr` remains strict.
+    pub fn from_str_lenient(s: &str) -> Result<Self, Error> {
+        let mut value: serde_json::Value = serde_json::from_str(s)?;
+        if let Some(object) = value.as_object_mut() {
+            lowercase_keys(object, BUILT_IN_FIELDS);
+            if let Some(annotations) = object.get_mut("annotations").and_then(|a| a.as_array_mut())
+            {
+                for annotation in annotations.iter_mut().filter_map(|a| a.as_object_mut()) {
+                    lowercase_keys(annotation, &["entry", "description"]);
+                }
+            }
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+    /// Parse a task, keeping the JSON it was parsed from. See `Task::to_source_json`.
     ///
-    /// UDAs, at their core, have a name and a value. The name is a string and the value can be one
-    /// of 4 types: string, numeric, date, or duration.
+    /// Unlike `FromStr`, fields that can't be read as a UDA, such as objects, arrays, and
+    /// booleans, are accepted. They're kept only in the source.
+    pub fn from_str_preserving(s: &str) -> Result<Self, Error> {
+        let source: serde_json::Value = serde_json::from_str(s)?;
+        let mut value = source.clone();
+        if let Some(object) = value.as_object_mut() {
+            object.retain(|key, value| is_typed_field(key, value));
+        }
+        let mut task: Task = serde_json::from_value(value)?;
+        task.source = Source(Some(source));
+        Ok(task)
+    }
+    /// Parse a task, requiring a UUID rather than generating one.
     ///
-    /// Each can have a label, default, and/or coeeficient.
+    /// See `Task::uuid_synthesized`.
+    pub fn from_str_strict(s: &str) -> Result<Self, Error> {
+        let task: Task = s.parse()?;
+        if task.uuid_synthesized() {
+            return Err(Error::Parse("missing required field: uuid".to_string()));
+        }
+        Ok(task)
+    }
+    /// Reads JSON from stdin and parses it into a Task.
     ///
-    /// The label defaults to the capitalized form of the name.
+    /// Only takes the first line of input.
+    pub fn from_stdin() -> Result<Self, Error> {
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(serde_json::from_str(&input)?)
+    }
+}
+
+/// Whether a field is read into the task, as a built-in field or a UDA, rather than only kept in
+/// the source. See `Task::from_str_preserving`.
+fn is_typed_field(key: &str, value: &serde_json::Value) -> bool {
+    BUILT_IN_FIELDS.contains(&key) || value.is_string() || value.is_number()
+}
+
+/// Names of the fields of a task, as serialized.
+const BUILT_IN_FIELDS: &[&str] = &[
+    "id",
+    "uuid",
+    "description",
+    "start",
+    "end",
+    "entry",
+    "scheduled",
+    "until",
+    "wait",
+    "due",
+    "depends",
+    "imask",
+    "mask",
+    "parent",
+    "recur",
+    "modified",
+    "project",
+    "status",
+    "tags",
+    "urgency",
+    "annotations",
+];
+
+/// Rename keys matching one of `fields`, ignoring case, to the field name.
+///
+/// Keys already in the expected case are kept over differently cased duplicates.
+fn lowercase_keys(object: &mut serde_json::Map<String, serde_json::Value>, fields: &[&str]) {
+    let keys: Vec<String> = object.keys().cloned().collect();
+    for key in keys {
+        let field = match fields.iter().find(|field| field.eq_ignore_ascii_case(&key)) {
+            Some(field) if **field != key => field,
+            _ => continue,
+        };
+        if let Some(value) = object.remove(&key) {
+            if !object.contains_key(*field) {
+                object.insert(field.to_string(), value);
+            }
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash.
+///
+/// <http://www.isthe.com/chongo/tech/comp/fnv/>
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Whether two JSON values are the same, comparing numbers by value.
+///
+/// e.g. `3` equals `3.0`, as a numeric UDA read as `3` serializes as `3.0`.
+fn json_same(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_same(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| json_same(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+/// Replaces private text in `Task::redacted`.
+const REDACTED: &str = "[redacted]";
+
+/// Conversion Methods
+impl Task {
+    /// A copy safe to log, with the description and annotation texts replaced by `[redacted]`.
     ///
-    /// A string type can have a list of values.
-    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-    pub enum Uda {
-        String {
-            name: String,
-            value: String,
-            // defaults to ""
-            label: String,
-            default: String,
-            values: Vec<String>,
-            coefficient: Option<f32>,
-        },
-        Numeric {
-            name: String,
-            value: f64,
-            // defaults to ""
-            label: String,
-            default: f64,
-            coefficient: Option<f32>,
-        },
-        Date {
-            name: String,
-            #[serde(
-                serialize_with = "tw_dt_to_str_se",
-                deserialize_with = "tw_str_to_dt_de"
-            )]
-            value: DateTime<Utc>,
-            // defaults to ""
-            label: String,
-            #[serde(
-                serialize_with = "tw_dt_to_str_opt_se",
-                skip_serializing_if = "Option::is_none",
-                deserialize_with = "tw_str_to_dt_opt_de",
-                default
-            )]
-            default: Option<DateTime<Utc>>,
-            coefficient: Option<f32>,
-        },
-        Duration {
-            name: String,
-            value: Duration,
-            // defaults to ""
-            label: String,
-            default: Option<Duration>,
-            coefficient: Option<f32>,
-        },
+    /// Everything else is kept for debugging, including the UUID, status, dates, project, tags,
+    /// and UDAs.
+    pub fn redacted(&self) -> Task {
+        let mut task = self.clone();
+        task.description = REDACTED.to_string();
+        for annotation in &mut task.annotations {
+            annotation.description = REDACTED.to_string();
+        }
+        task
     }
-
-    /// Manually implement Deserialize for Uda
+    /// A builder with every field of the task, moved rather than cloned.
+    pub fn into_builder(self) -> TaskBuilder {
+        TaskBuilder {
+            id: self.id,
+            uuid: Some(*self.uuid),
+            description: Some(self.description),
+            entry: Some(self.entry),
+            start: self.start,
+            end: self.end,
+            modified: Some(self.modified),
+            status: Some(self.status),
+            tags: Some(self.tags),
+            annotations: Some(self.annotations),
+            priority: None,
+            project: Some(self.project),
+            wait: self.wait,
+            due: self.due,
+            until: self.until,
+            scheduled: self.scheduled,
+            recur: self.recur,
+            mask: self.mask,
+            imask: self.imask,
+            parent: self.parent,
+            depends: Some(self.depends.uuids),
+            urgency: self.urgency,
+            udas: Some(self.udas),
+        }
+    }
+    /// Convert Task to JSON object.
+    pub fn to_json(&self) -> serde_json::Value {
+        // Serialization can't fail. Every field serializes to a string, a number, or a sequence or
+        // map of those with string keys, and serde_json writes non-finite floats as `null`.
+        serde_json::to_value(self).expect("task serializes to JSON")
+    }
+    /// Convert Task to JSON, over the JSON it was parsed from by `Task::from_str_preserving`.
     ///
-    /// "elapsed": "2.0",   -> Uda::String { name: String::from("elapsed"), value: String::from("2.0"), .. }
-    /// "elapsed": 2.0,     -> Uda::Numeric { name: String::from("elapsed"), value: 2.0, .. }
-    /// "elapsed": "20220131T083000Z", -> Uda::Date { name: String::from("elapsed"), value: Utc.datetime_from_str("20220131T083000Z", "%Y%m%dT%H%M%SZ")
-    /// "elapsed": "PT2H",  -> Uda::Duration { name: String::from("elapsed"), value: Duration::hours(2), .. }
-
-    /// Allow Uda::String{ .. } to be compared to a string
+    /// Fields that were only kept in the source, e.g. an unknown object, are emitted unchanged, as
+    /// are fields whose value hasn't changed, e.g. a numeric UDA written `3` rather than `3.0`.
+    /// Everything else is as in `to_json`, so edits apply and removed fields stay removed. Without
+    /// a source, this is `to_json`.
     ///
-    /// Uses the `value` field of the UDA
-    impl PartialEq<String> for Uda {
-        fn eq(&self, other: &String) -> bool {
-            match self {
-                Uda::String { value, .. } => value == other,
-                _ => false,
+    /// An unedited task gives a value equal to its source. Key order and whitespace aren't kept,
+    /// so serializing it isn't byte-identical to the source text.
+    pub fn to_source_json(&self) -> serde_json::Value {
+        let mut json = self.to_json();
+        if let (Some(serde_json::Value::Object(source)), Some(object)) =
+            (&self.source.0, json.as_object_mut())
+        {
+            for (key, value) in source {
+                if !is_typed_field(key, value) {
+                    object.entry(key.clone()).or_insert_with(|| value.clone());
+                } else if let Some(typed) = object.get_mut(key) {
+                    if json_same(typed, value) {
+                        *typed = value.clone();
+                    }
+                }
             }
         }
+        json
     }
-
-    impl PartialEq<str> for Uda {
-        fn eq(&self, other: &str) -> bool {
-            match self {
-                Uda::String { value, .. } => value == other,
-                _ => false,
-            }
+    /// Convert Task to a JSON object of only the fields a user authors.
+    ///
+    /// Leaves out what Taskwarrior calculates, `id` and `urgency`, so logically identical tasks
+    /// give the same JSON, e.g. for hashing. Virtual tags are never serialized. See
+    /// `Task::sanitize_for_import`.
+    pub fn to_data_json(&self) -> serde_json::Value {
+        let mut json = self.to_json();
+        if let Some(map) = json.as_object_mut() {
+            map.remove("id");
+            map.remove("urgency");
         }
+        json
     }
-
-    impl Uda {
-        // This type isn't yet implemented and may be deprecated
-        #[allow(dead_code)]
-        /// Get the type of the UDA as a string
-        pub fn r#type(&self) -> String {
-            match self {
-                Uda::String { .. } => "string".to_string(),
-                Uda::Numeric { .. } => "numeric".to_string(),
-                Uda::Date { .. } => "date".to_string(),
-                Uda::Duration { .. } => "duration".to_string(),
-            }
+    /// A hash of the content a user authors, to detect real changes and duplicates.
+    ///
+    /// Hashes `to_data_json` without `modified`, with keys sorted, so tasks differing only in
+    /// `id`, `urgency`, or `modified` share a fingerprint. The UUID is included. The hash is
+    /// FNV-1a, which is stable across builds and platforms, so fingerprints may be stored. It isn't
+    /// cryptographic.
+    pub fn fingerprint(&self) -> u64 {
+        let mut json = self.to_data_json();
+        if let Some(map) = json.as_object_mut() {
+            map.remove("modified");
         }
+        let sorted: BTreeMap<String, serde_json::Value> = match json {
+            serde_json::Value::Object(map) => map.into_iter().collect(),
+            _ => BTreeMap::new(),
+        };
+        // Can't fail, see `to_json`.
+        let content = serde_json::to_string(&sorted).expect("task serializes to JSON");
+        fnv1a(content.as_bytes())
     }
-
-    impl From<Uda> for String {
-        fn from(uda: Uda) -> Self {
-            match uda {
-                Uda::String { value, .. } => value,
-                Uda::Numeric { value, .. } => value.to_string(),
-                Uda::Date { value, .. } => value.format(DATETIME_FORMAT).to_string(),
-                Uda::Duration { value, .. } => value.to_string(),
+    /// Whether the task serializes to the same JSON as `json`, whatever the order of keys.
+    ///
+    /// Numbers are compared by value, so a numeric UDA written `3` equals the `3.0` it serializes
+    /// as. e.g. for asserting serialized output in tests. Invalid JSON is never equal.
+    pub fn json_eq(&self, json: &str) -> bool {
+        serde_json::from_str::<serde_json::Value>(json)
+            .is_ok_and(|json| json_same(&self.to_json(), &json))
+    }
+    /// Convert Task to a JSON map, with UDAs inline.
+    ///
+    /// Has the same shape as `to_json`, so unset fields are absent.
+    pub fn as_object(&self) -> serde_json::Map<String, serde_json::Value> {
+        // As `to_json`, serialization can't fail
+        object::to_object(self).expect("task serializes to a JSON object")
+    }
+    /// Fields that importing this task would change on `existing`, as a JSON patch.
+    ///
+    /// Fields set here that differ from `existing` have their new value. Taskwarrior leaves fields
+    /// missing from an import untouched, so fields only set on `existing` are cleared explicitly:
+    /// lists such as `tags` are `[]`, and anything else is `""`. `id` and `urgency` are calculated
+    /// by Taskwarrior, so are never included.
+    pub fn import_changes(&self, existing: &Task) -> serde_json::Map<String, serde_json::Value> {
+        let new = self.as_object();
+        let old = existing.as_object();
+        let mut changes = serde_json::Map::new();
+        for (key, value) in &new {
+            if old.get(key) != Some(value) {
+                changes.insert(key.clone(), value.clone());
+            }
+        }
+        for (key, value) in &old {
+            if !new.contains_key(key) {
+                let cleared = match value {
+                    serde_json::Value::Array(_) => serde_json::Value::Array(Vec::new()),
+                    _ => serde_json::Value::String(String::new()),
+                };
+                changes.insert(key.clone(), cleared);
             }
         }
+        changes.remove("id");
+        changes.remove("urgency");
+        changes
+    }
+    /// Convert Task to JSON string.
+    pub fn to_json_string(&self) -> String {
+        // Can't fail, see `to_json`.
+        serde_json::to_string(self).expect("task serializes to JSON")
+    }
+    /// Convert Task to an indented JSON string, for reading.
+    ///
+    /// Hooks must write compact JSON, one task per line, so use `to_json_string` for those.
+    pub fn to_json_string_pretty(&self) -> String {
+        // Can't fail, see `to_json`.
+        serde_json::to_string_pretty(self).expect("task serializes to JSON")
+    }
+    /// Convert Task to a JSON string, with all keys sorted.
+    ///
+    /// `to_json_string` writes fields in declaration order, followed by UDAs in sorted order.
+    pub fn to_json_string_sorted(&self) -> String {
+        let sorted: BTreeMap<String, serde_json::Value> = self.as_object().into_iter().collect();
+        // Can't fail, see `to_json`.
+        serde_json::to_string(&sorted).expect("task serializes to JSON")
+    }
+    /// Append JSON representation of Task to `buf`.
+    ///
+    /// Lets a buffer be reused across many tasks. On error, `buf` is left as it was.
+    pub fn write_json_to(&self, buf: &mut String) -> Result<(), Error> {
+        buf.push_str(&serde_json::to_string(self)?);
+        Ok(())
+    }
+    /// Append JSON representation of Task to `buf`.
+    ///
+    /// Lets a buffer be reused across many tasks. On error, `buf` is left as it was.
+    pub fn write_json_bytes(&self, buf: &mut Vec<u8>) -> Result<(), Error> {
+        let len = buf.len();
+        serde_json::to_writer(&mut *buf, self).inspect_err(|_| buf.truncate(len))?;
+        Ok(())
+    }
+    /// Write JSON representation of Task to handle.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        Ok(writer.write_all(self.to_string().as_bytes())?)
     }
+    /// Write indented JSON representation of Task to handle. See `to_json_string_pretty`.
+    pub fn write_pretty<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        Ok(serde_json::to_writer_pretty(writer, self)?)
+    }
+    /// Write JSON representation of Task to stdout.
+    pub fn to_stdout(&self) -> Result<(), Error> {
+        self.to_writer(&mut io::stdout())
+    }
+}
 
-    #[derive(Debug, Clone)]
-    enum Type {
-        /// May be provided a list of acceptable values, using the `uda.my_uda.values` key, which
-        /// is set to a string of comma-separated values.
-        ///
-        /// e.g. `task config uda.size.values large,medium,small`
-        String,
-        /// Float
-        Numeric,
-        /// I'm using chrono's DateTime struct
-        Date,
-        Duration,
+/// Display (JSON)
+///
+/// Uses JSON as this is the most common use case for converting a Task to a string.
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_json_string())
     }
+}
 
-    impl fmt::Display for Type {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "{}", self.to_str())
-        }
+impl FromStr for Task {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let task: Task = serde_json::from_str(s)?;
+        Ok(task)
     }
+}
 
-    impl Type {
-        fn to_str(&self) -> &str {
-            match self {
-                Type::String => "string",
-                Type::Numeric => "numeric",
-                Type::Date => "date",
-                Type::Duration => "duration",
-            }
-        }
-        fn from_str(s: &str) -> Result<Type, String> {
-            match s {
-                "string" => Ok(Type::String),
-                "numeric" => Ok(Type::Numeric),
-                "date" => Ok(Type::Date),
-                "duration" => Ok(Type::Duration),
-                _ => Err(format!("invalid type: {s}")),
-            }
-        }
-        fn from_string(s: String) -> Result<Type, String> {
-            Type::from_str(&s)
-        }
+impl From<&Task> for serde_json::Map<String, serde_json::Value> {
+    fn from(task: &Task) -> Self {
+        task.as_object()
     }
+}
 
-    impl Serialize for Type {
-        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            S: Serializer,
-        {
-            serializer.serialize_str(&self.to_string())
-        }
+impl From<Task> for String {
+    fn from(task: Task) -> Self {
+        task.to_json_string()
     }
+}
 
-    impl<'de> Deserialize<'de> for Type {
-        fn deserialize<D>(deserializer: D) -> Result<Type, D::Error>
-        where
-            D: Deserializer<'de>,
-        {
-            let s = String::deserialize(deserializer)?;
-            Type::from_string(s).map_err(de::Error::custom)
+/// # Panics
+///
+/// Panics if the string isn't a valid task. Use `str::parse` to handle invalid input.
+impl From<String> for Task {
+    fn from(s: String) -> Self {
+        Task::from_str(&s).expect("string turned into task")
+    }
+}
+
+/// # Panics
+///
+/// Panics if the string isn't a valid task. Use `str::parse` to handle invalid input.
+impl From<&str> for Task {
+    fn from(s: &str) -> Self {
+        Task::from_str(s).expect("string turned into task")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Annotation {
+    #[serde(
+        serialize_with = "tw_dt_to_str_se",
+        deserialize_with = "tw_str_to_dt_de"
+    )]
+    entry: DateTime<Utc>,
+    description: String,
+}
+
+/// Constructors
+impl Annotation {
+    /// `entry` is truncated to whole seconds, as serialized.
+    pub fn new<T: ToString>(entry: DateTime<Utc>, description: T) -> Self {
+        Annotation {
+            entry: truncate_to_seconds(entry),
+            description: description.to_string(),
         }
     }
+}
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        #[test]
-        fn basic_uda() {
-            let uda_1 = Uda::String {
-                name: "my_uda".to_string(),
-                value: "my_value".to_string(),
-                label: "".to_string(),
-                default: "my_default".to_string(),
-                values: vec!["my_value".to_string(), "my_value_2".to_string()],
-                coefficient: Some(1.0),
-            };
-            let uda_2 = Uda::String {
-                name: "my_uda".to_string(),
-                value: "my_value".to_string(),
-                label: "".to_string(),
-                default: "my_default".to_string(),
-                values: vec!["my_value".to_string(), "my_value_2".to_string()],
-                coefficient: Some(1.0),
-            };
+/// Parse an annotation from `<entry> <description>`.
+///
+/// e.g. `20220131T083000Z Called the client`
+///
+/// If there is no leading timestamp, the whole string is the description and `entry` is now.
+impl FromStr for Annotation {
+    type Err = Error;
 
-            assert_eq!(uda_1, uda_2);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (entry, description) = match s.split_once(char::is_whitespace) {
+            Some((entry, description)) => {
+                match NaiveDateTime::parse_from_str(entry, DATETIME_FORMAT) {
+                    Ok(entry) => (
+                        DateTime::<Utc>::from_naive_utc_and_offset(entry, Utc),
+                        description.trim_start(),
+                    ),
+                    Err(_) => (Utc::now(), s),
+                }
+            }
+            None => (Utc::now(), s),
+        };
+        if description.is_empty() {
+            return Err(Error::Parse(
+                "invalid annotation, description is empty".to_string(),
+            ));
         }
-        #[test]
-        fn serialize() {
-            use chrono::TimeZone;
-            use chrono::Utc;
+        Ok(Annotation::new(entry, description))
+    }
+}
 
-            let uda_string = Uda::String {
-                name: "my_uda".to_string(),
-                value: "my_value".to_string(),
-                label: "".to_string(),
-                default: "my_default".to_string(),
-                values: vec!["my_value".to_string(), "my_value_2".to_string()],
-                coefficient: Some(1.0),
-            };
-            let expected = r#"my_value"#;
-            let actual: String = uda_string.into();
-            assert_eq!(actual, expected);
-
-            let uda_numeric = Uda::Numeric {
-                name: "my_uda".to_string(),
-                value: 1.0,
-                label: "".to_string(),
-                default: 1.0,
-                coefficient: Some(1.0),
-            };
-            let expected = r#"1"#;
-            let actual: String = uda_numeric.into();
-            assert_eq!(actual, expected);
+/// Getters (Immutable)
+impl Annotation {
+    pub fn entry(&self) -> &DateTime<Utc> {
+        &self.entry
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
 
-            let uda_date = Uda::Date {
-                name: "my_uda".to_string(),
-                //value: Utc.ymd(2020, 1, 1).and_hms(0, 0, 0),
-                value: Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap(),
-                label: "".to_string(),
-                default: None,
-                coefficient: Some(1.0),
-            };
-            let expected = r#"20200101T000000Z"#;
-            let actual: String = uda_date.into();
-            assert_eq!(actual, expected);
+// #[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum Status {
+    #[serde(rename = "completed")]
+    Completed,
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "recurring")]
+    Recurring,
+    #[serde(rename = "deleted")]
+    Deleted,
+}
 
-            let uda_duration = Uda::Duration {
-                name: "my_uda".to_string(),
-                value: Duration::days(3),
-                label: "".to_string(),
-                default: None,
-                coefficient: Some(1.0),
-            };
-            let expected = r#"P3D"#;
-            let actual: String = uda_duration.into();
-            assert_eq!(actual, expected);
+impl Status {
+    /// The status as serialized, without allocating, e.g. `pending`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Status::Completed => "completed",
+            Status::Pending => "pending",
+            Status::Recurring => "recurring",
+            Status::Deleted => "deleted",
         }
     }
+    /// Whether a task may move from this status to `to`.
+    ///
+    /// | From        | To                              |
+    /// |-------------|---------------------------------|
+    /// | `Pending`   | `Completed`, `Deleted`          |
+    /// | `Completed` | `Pending` (reopen), `Deleted`   |
+    /// | `Deleted`   | `Pending` (undelete)            |
+    /// | `Recurring` | `Pending` (instance), `Deleted` |
+    ///
+    /// Staying in the same status is always allowed.
+    pub fn can_transition_to(&self, to: &Status) -> bool {
+        use Status::*;
+        matches!(
+            (self, to),
+            (Pending, Pending)
+                | (Pending, Completed)
+                | (Pending, Deleted)
+                | (Completed, Completed)
+                | (Completed, Pending)
+                | (Completed, Deleted)
+                | (Deleted, Deleted)
+                | (Deleted, Pending)
+                | (Recurring, Recurring)
+                | (Recurring, Pending)
+                | (Recurring, Deleted)
+        )
+    }
+    /// Whether the status is still to be done, `Pending` or `Recurring`.
+    pub fn is_open(&self) -> bool {
+        matches!(self, Status::Pending | Status::Recurring)
+    }
+    /// Whether the status is done with, `Completed` or `Deleted`.
+    pub fn is_closed(&self) -> bool {
+        !self.is_open()
+    }
 }
 
-/// This module contains the logic for the CLI arguments given during a hook.
-mod cli {
-
-    use std::env;
-    use std::path::PathBuf;
-    use std::str::FromStr;
+/// Priority of a task, `H`, `M`, or `L`.
+///
+/// Ordered `High > Medium > Low`. No priority sorts lowest, see `Priority::cmp_option`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    #[serde(rename = "L")]
+    Low,
+    #[serde(rename = "M")]
+    Medium,
+    #[serde(rename = "H")]
+    High,
+}
 
-    /// // Get the command line arguments.
-    #[derive(Debug)]
-    pub struct CliArguments {
-        hook: PathBuf,
-        api: ApiVersion,
-        args: String,
-        command: Command,
-        rc_file: PathBuf,
-        data_location: PathBuf,
-        task_version: Version,
+impl Priority {
+    /// Compare optional priorities, where no priority is lowest.
+    pub fn cmp_option(a: Option<Priority>, b: Option<Priority>) -> std::cmp::Ordering {
+        // `None` is less than any `Some`
+        a.cmp(&b)
     }
-
-    /// Getters (Immutable)
-    impl CliArguments {
-        pub fn hook(&self) -> &PathBuf {
-            &self.hook
-        }
-        pub fn api_version(&self) -> &ApiVersion {
-            &self.api
-        }
-        pub fn arguments(&self) -> &String {
-            &self.args
-        }
-        pub fn command(&self) -> &Command {
-            &self.command
-        }
-        pub fn rc_file(&self) -> &PathBuf {
-            &self.rc_file
-        }
-        pub fn data_location(&self) -> &PathBuf {
-            &self.data_location
-        }
-        pub fn task_version(&self) -> &Version {
-            &self.task_version
+    /// The urgency coefficient for this priority, as in Taskwarrior's defaults.
+    ///
+    /// `urgency.uda.priority.{H,M,L}.coefficient`
+    pub fn urgency_value(&self) -> f64 {
+        match self {
+            Priority::High => 6.0,
+            Priority::Medium => 3.9,
+            Priority::Low => 1.8,
         }
     }
+}
 
-    impl CliArguments {
-        /// Get the command line arguments from the environemnt.
-        ///
-        /// This is given to the command line as arguments.
-        pub fn from_env() -> Result<Self, String> {
-            let args: Vec<String> = env::args().collect();
-            Self::from_vec(args)
-        }
-    }
+/// When a task is due, relative to now. See `Task::due_status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DueStatus {
+    /// Due before now.
+    Overdue,
+    /// Due within the horizon.
+    DueSoon,
+    /// Due beyond the horizon.
+    Later,
+    /// No due date.
+    None,
+}
 
-    impl From<Vec<String>> for CliArguments {
-        fn from(vec: Vec<String>) -> Self {
-            Self::from_vec(vec).unwrap()
-        }
-    }
+impl FromStr for Priority {
+    type Err = Error;
 
-    impl From<env::Args> for CliArguments {
-        fn from(args: env::Args) -> Self {
-            Self::from_vec(args.collect()).unwrap()
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "H" => Ok(Priority::High),
+            "M" => Ok(Priority::Medium),
+            "L" => Ok(Priority::Low),
+            _ => Err(Error::Parse(format!("invalid priority: {s}"))),
         }
     }
+}
 
-    impl CliArguments {
-        /// e.g. vec!["./.task/hooks/on-add_noop.py", "api:2", "args:task add My task", "command:add", "rc:./.taskrc", "data:./.task", "version:2.6.2"]
-        pub fn from_vec(vec: Vec<String>) -> Result<Self, String> {
-            let mut args = vec.into_iter();
-
-            let hook = args
-                .next()
-                .ok_or_else(|| "Missing hook argument".to_string())?;
-            let api = args
-                .next()
-                .ok_or_else(|| "Missing api argument".to_string())?
-                .split(':')
-                .nth(1)
-                .ok_or_else(|| "Missing api version".to_string())?
-                .parse::<ApiVersion>()?;
-            let task_args = args
-                .next()
-                .ok_or_else(|| "Missing args argument".to_string())?
-                .split(':')
-                .nth(1)
-                .ok_or_else(|| "Missing args".to_string())?
-                .to_string();
-            let command = args
-                .next()
-                .ok_or_else(|| "Missing command argument".to_string())?
-                .split(':')
-                .nth(1)
-                .ok_or_else(|| "Missing command".to_string())?
-                .parse::<Command>()?;
-            let rc_file = args
-                .next()
-                .ok_or_else(|| "Missing rc argument".to_string())?
-                .split(':')
-                .nth(1)
-                .ok_or_else(|| "Missing rc file".to_string())?
-                .parse::<PathBuf>()
-                .map_err(|e| format!("Invalid rc file: {}", e))?;
-            let data_location = args
-                .next()
-                .ok_or_else(|| "Missing data argument".to_string())?
-                .split(':')
-                .nth(1)
-                .ok_or_else(|| "Missing data location".to_string())?
-                .parse::<PathBuf>()
-                .map_err(|e| format!("Invalid data location: {}", e))?;
-            let task_version = args
-                .next()
-                .ok_or_else(|| "Missing version argument".to_string())?
-                .split(':')
-                .nth(1)
-                .ok_or_else(|| "Missing version".to_string())?
-                .parse::<Version>()
-                .map_err(|e| format!("Invalid version: {}", e))?;
+/// Dates are truncated to whole seconds, as serialized.
+#[derive(Debug, Default)]
+pub struct TaskBuilder {
+    id: Option<usize>,
+    uuid: Option<Uuid>,
+    description: Option<String>,
+    entry: Option<DateTime<Utc>>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    status: Option<Status>,
+    tags: Option<Vec<String>>,
+    annotations: Option<Vec<Annotation>>,
+    priority: Option<String>,
+    project: Option<String>,
+    wait: Option<DateTime<Utc>>,
+    due: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    scheduled: Option<DateTime<Utc>>,
+    recur: Option<Duration>,
+    mask: Option<String>,
+    imask: Option<f64>,
+    parent: Option<Uuid>,
+    depends: Option<Vec<Uuid>>,
+    urgency: Option<f64>,
+    udas: Option<BTreeMap<String, UdaValue>>,
+}
 
-            Ok(Self {
-                hook: PathBuf::from(hook),
-                api,
-                args: task_args,
-                command,
-                rc_file,
-                data_location,
-                task_version,
-            })
-        }
+impl TaskBuilder {
+    pub fn id(mut self, id: usize) -> Self {
+        self.id = Some(id);
+        self
     }
-
-    #[derive(Debug)]
-    pub enum ApiVersion {
-        V1,
-        V2,
-        /// An unknown API version.
-        ///
-        /// Can include a message.
-        Unknown(Option<String>),
+    /// # Panics
+    ///
+    /// Panics if `uuid` isn't a valid UUID. Use `try_uuid` to handle invalid input.
+    pub fn uuid(mut self, uuid: &str) -> Self {
+        self.uuid = Some(Uuid::parse_str(uuid).expect("valid uuid"));
+        self
     }
-
-    impl FromStr for ApiVersion {
-        type Err = String;
-
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            match s {
-                "1" => Ok(ApiVersion::V1),
-                "2" => Ok(ApiVersion::V2),
-                _ => Ok(ApiVersion::Unknown(Some(s.to_string()))),
-            }
+    pub fn try_uuid(mut self, uuid: &str) -> Result<Self, uuid::Error> {
+        self.uuid = Some(Uuid::parse_str(uuid)?);
+        Ok(self)
+    }
+    pub fn description<T: ToString>(mut self, description: T) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+    pub fn entry(mut self, entry: DateTime<Utc>) -> Self {
+        self.entry = Some(truncate_to_seconds(entry));
+        self
+    }
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(truncate_to_seconds(start));
+        self
+    }
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(truncate_to_seconds(end));
+        self
+    }
+    pub fn modified(mut self, modified: DateTime<Utc>) -> Self {
+        self.modified = Some(truncate_to_seconds(modified));
+        self
+    }
+    pub fn status(mut self, status: Status) -> Self {
+        self.status = Some(status);
+        self
+    }
+    /// Completed at `at`. Sets `status` and `end`.
+    pub fn done(self, at: DateTime<Utc>) -> Self {
+        self.status(Status::Completed).end(at)
+    }
+    /// Deleted at `at`. Sets `status` and `end`.
+    pub fn deleted(self, at: DateTime<Utc>) -> Self {
+        self.status(Status::Deleted).end(at)
+    }
+    /// Add a tag, normalized as in `Task::add_tag`. Duplicates are ignored.
+    ///
+    /// An invalid tag is kept as given, and rejected by `try_build`.
+    pub fn tag(mut self, tag: String) -> Self {
+        let tag = normalize_tag(&tag).unwrap_or(tag);
+        let tags = self.tags.get_or_insert_with(Vec::new);
+        if !tags.contains(&tag) {
+            tags.push(tag);
         }
+        self
     }
-
-    /// <https://taskwarrior.org/docs/commands/>
-    #[derive(Debug)]
-    pub enum Command {
-        /// Add a new task
-        Add,
-        /// Add an annotation to a task
-        Annotate,
-        /// Append words to a task description
-        Append,
-        /// 2.4.0 Expression calculator
-        Calc,
-        /// Modify configuration settings
-        Config,
-        /// Manage contexts
-        Context,
-        /// Count the tasks matching a filter
-        Count,
-        /// Mark a task as deleted
-        Delete,
-        /// Remove an annotation from a task
-        Denotate,
-        /// Complete a task
-        Done,
-        /// Clone an existing task
-        Duplicate,
-        /// Launch your text editor to modify a task
-        Edit,
-        /// Execute an external command
-        Execute,
-        /// Export tasks in JSON format
-        Export,
-        /// Show high-level help, a cheat-sheet
-        Help,
-        /// Import tasks in JSON form
-        Import,
-        /// Record an already-completed task
-        Log,
-        /// Show the Taskwarrior logo
-        Logo,
-        /// Modify one or more tasks
-        Modify,
-        /// Prepend words to a task description
-        Prepend,
-        /// 2.6.0 Completely removes tasks, rather than change status to deleted
-        Purge,
-        /// Start working on a task, make active
-        Start,
-        /// Stop working on a task, no longer active
-        Stop,
-        /// Syncs tasks with Taskserver
-        Synchronize,
-        /// Revert last change
-        Undo,
-        /// Version details and copyright
-        Version,
-        /// An unknown command.
-        ///
-        /// Can include a message.
-        Unknown(Option<String>),
+    /// Add several tags. See `tag`.
+    pub fn tags<T: ToString>(self, tags: Vec<T>) -> Self {
+        tags.into_iter()
+            .fold(self, |builder, tag| builder.tag(tag.to_string()))
+    }
+    pub fn annotations(mut self, annotations: Vec<Annotation>) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+    /// Append an annotation entered now, to the second.
+    pub fn annotate(self, description: impl Into<String>) -> Self {
+        self.annotation(Annotation::new(now_to_seconds(), description.into()))
+    }
+    /// Append an annotation.
+    pub fn annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations
+            .get_or_insert_with(Vec::new)
+            .push(annotation);
+        self
+    }
+    /// Set the `priority` UDA, read by `Task::priority`.
+    ///
+    /// Anything other than `H`, `M`, or `L` is kept as given, and rejected by `try_build`.
+    pub fn priority(mut self, priority: String) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+    pub fn project<T: ToString>(mut self, project: T) -> Self {
+        self.project = Some(project.to_string());
+        self
+    }
+    pub fn wait(mut self, wait: DateTime<Utc>) -> Self {
+        self.wait = Some(truncate_to_seconds(wait));
+        self
+    }
+    pub fn scheduled(mut self, scheduled: DateTime<Utc>) -> Self {
+        self.scheduled = Some(truncate_to_seconds(scheduled));
+        self
+    }
+    pub fn due(mut self, due: DateTime<Utc>) -> Self {
+        self.due = Some(truncate_to_seconds(due));
+        self
+    }
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(truncate_to_seconds(until));
+        self
+    }
+    pub fn recur(mut self, recur: Duration) -> Self {
+        self.recur = Some(recur);
+        self
+    }
+    pub fn mask(mut self, mask: String) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+    pub fn imask(mut self, imask: f64) -> Self {
+        self.imask = Some(imask);
+        self
+    }
+    /// # Panics
+    ///
+    /// Panics if `parent` isn't a valid UUID. Use `try_parent` to handle invalid input.
+    pub fn parent(mut self, parent: &str) -> Self {
+        self.parent = Some(Uuid::parse_str(parent).expect("valid uuid"));
+        self
+    }
+    pub fn try_parent(mut self, parent: &str) -> Result<Self, uuid::Error> {
+        self.parent = Some(Uuid::parse_str(parent)?);
+        Ok(self)
+    }
+    pub fn urgency(mut self, urgency: f64) -> Self {
+        self.urgency = Some(urgency);
+        self
+    }
+    pub fn uda(mut self, name: String, uda: UdaValue) -> Self {
+        self.udas
+            .get_or_insert_with(BTreeMap::new)
+            .insert(name, uda);
+        self
+    }
+    /// Check every UDA set against its definition in `defs`. See `Uda::validate`.
+    ///
+    /// UDAs without a definition are kept unchecked, as Taskwarrior keeps orphaned UDAs.
+    pub fn validate_udas(self, defs: &[Uda]) -> Result<TaskBuilder, Vec<UdaValidationError>> {
+        let errors: Vec<UdaValidationError> = self
+            .udas
+            .iter()
+            .flatten()
+            .filter_map(|(name, value)| {
+                let def = defs.iter().find(|def| def.name() == name)?;
+                def.validate(value).err()
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(self)
     }
+}
 
-    impl FromStr for Command {
-        type Err = String;
+impl TaskBuilder {
+    pub fn new() -> Self {
+        TaskBuilder {
+            ..Default::default()
+        }
+    }
+    /// A builder with every field of `task`, cloned.
+    pub fn from_task(task: &Task) -> Self {
+        task.clone().into_builder()
+    }
+    /// # Panics
+    ///
+    /// Panics if `uuid`, `modified`, or `status` weren't set.
+    pub fn build(self) -> Task {
+        let mut udas = self.udas.unwrap_or_default();
+        if let Some(priority) = self.priority {
+            udas.insert("priority".to_string(), UdaValue::String(priority));
+        }
+        Task {
+            id: self.id,
+            uuid: self.uuid.unwrap().into(),
+            description: self.description.unwrap_or("".to_string()),
+            entry: self.entry.unwrap_or_else(now_to_seconds),
+            start: self.start,
+            end: self.end,
+            modified: self.modified.unwrap(),
+            status: self.status.unwrap(),
+            tags: self.tags.unwrap_or(vec![]),
+            annotations: self.annotations.unwrap_or(vec![]),
+            project: self.project.unwrap_or("".to_string()),
+            scheduled: self.scheduled,
+            until: self.until,
+            recur: self.recur,
+            mask: self.mask,
+            imask: self.imask,
+            parent: self.parent,
+            depends: self.depends.unwrap_or_default().into(),
+            wait: self.wait,
+            due: self.due,
+            urgency: self.urgency,
+            udas,
+            source: Source::default(),
+        }
+    }
+    /// Build the task, checking that the fields are set and consistent.
+    ///
+    /// Inconsistent combinations are:
+    ///
+    /// * `end` set on a pending task
+    /// * `due` before `entry`
+    /// * `wait` after `due`
+    ///
+    /// Invalid tags, e.g. `two words`, and priorities are also reported.
+    pub fn try_build(self) -> Result<Task, BuildError> {
+        for (field, missing) in [
+            ("uuid", self.uuid.is_none()),
+            ("modified", self.modified.is_none()),
+            ("status", self.status.is_none()),
+        ] {
+            if missing {
+                return Err(BuildError::Missing(field));
+            }
+        }
 
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            match s {
-                "add" => Ok(Command::Add),
-                "annotate" => Ok(Command::Annotate),
-                "append" => Ok(Command::Append),
-                "calc" => Ok(Command::Calc),
-                "config" => Ok(Command::Config),
-                "context" => Ok(Command::Context),
-                "count" => Ok(Command::Count),
-                "delete" => Ok(Command::Delete),
-                "denotate" => Ok(Command::Denotate),
-                "done" => Ok(Command::Done),
-                "duplicate" => Ok(Command::Duplicate),
-                "edit" => Ok(Command::Edit),
-                "execute" => Ok(Command::Execute),
-                "export" => Ok(Command::Export),
-                "help" => Ok(Command::Help),
-                "import" => Ok(Command::Import),
-                "log" => Ok(Command::Log),
-                "logo" => Ok(Command::Logo),
-                "modify" => Ok(Command::Modify),
-                "prepend" => Ok(Command::Prepend),
-                "purge" => Ok(Command::Purge),
-                "start" => Ok(Command::Start),
-                "stop" => Ok(Command::Stop),
-                "sync" => Ok(Command::Synchronize),
-                "undo" => Ok(Command::Undo),
-                "version" => Ok(Command::Version),
-                _ => Ok(Command::Unknown(Some(s.to_string()))),
+        let invalid_priority = self
+            .priority
+            .clone()
+            .filter(|priority| priority.parse::<Priority>().is_err());
+        let task = self.build();
+        let mut problems = task.inconsistencies();
+        if let Some(priority) = invalid_priority {
+            problems.push(format!("priority {priority:?} is invalid"));
+        }
+        if !problems.is_empty() {
+            return Err(BuildError::Inconsistent(problems));
+        }
+        Ok(task)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// A required field wasn't set.
+    Missing(&'static str),
+    /// Fields were set that contradict each other.
+    Inconsistent(Vec<String>),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Missing(field) => write!(f, "missing required field: {field}"),
+            BuildError::Inconsistent(problems) => {
+                write!(f, "inconsistent fields: {}", problems.join(", "))
             }
         }
     }
+}
 
-    #[derive(Debug)]
-    pub struct Version {
-        major: u32,
-        minor: u32,
-        patch: u32,
+impl std::error::Error for BuildError {}
+
+/// Why a task couldn't be started, stopped, or completed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateError {
+    /// The task's status doesn't allow it, e.g. starting a completed task.
+    InvalidStatus {
+        action: &'static str,
+        status: Status,
+    },
+    /// The task is already started.
+    AlreadyStarted,
+    /// The task isn't started.
+    NotStarted,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidStatus { action, status } => {
+                write!(f, "cannot {action} a {} task", status.as_str())
+            }
+            StateError::AlreadyStarted => write!(f, "task is already started"),
+            StateError::NotStarted => write!(f, "task isn't started"),
+        }
     }
+}
 
-    /// Getters (Immutable)
-    impl Version {
-        pub fn major(&self) -> u32 {
-            self.major
+impl std::error::Error for StateError {}
+
+mod udas {
+
+    use std::any::Any;
+    use std::fmt;
+
+    use chrono::{self, offset::Utc, DateTime};
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::tw_dt_to_str_opt_se;
+    use super::tw_dt_to_str_se;
+    use super::tw_str_to_dt_de;
+    use super::tw_str_to_dt_opt_de;
+    use super::Duration;
+    use super::DATETIME_FORMAT;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum UdaValue {
+        String(String),
+        Numeric(f64),
+        Date(DateTime<Utc>),
+        Duration(Duration),
+    }
+
+    use std::error::Error;
+
+    /// The error for a string UDA that couldn't be parsed.
+    fn unparseable(value: &str, error: impl fmt::Display) -> crate::Error {
+        UdaConversionError::Unparseable {
+            value: value.to_string(),
+            error: error.to_string(),
         }
-        pub fn minor(&self) -> u32 {
-            self.minor
+        .into()
+    }
+
+    /// Why a UDA couldn't be converted to another type.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum UdaConversionError {
+        /// A string UDA couldn't be parsed.
+        Unparseable {
+            /// The string that couldn't be parsed.
+            value: String,
+            /// Why it couldn't be parsed.
+            error: String,
+        },
+        /// The UDA is of a type that can't be converted, e.g. `Date` to a duration.
+        IncompatibleVariant(&'static str),
+    }
+
+    impl fmt::Display for UdaConversionError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                UdaConversionError::Unparseable { value, error } => {
+                    write!(f, "cannot parse {value:?}: {error}")
+                }
+                UdaConversionError::IncompatibleVariant(variant) => {
+                    write!(f, "cannot convert a {variant} UDA")
+                }
+            }
         }
-        pub fn patch(&self) -> u32 {
-            self.patch
+    }
+
+    impl Error for UdaConversionError {}
+
+    /// Why a UDA doesn't match its definition. See `Uda::validate`.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum UdaValidationError {
+        /// The value isn't of the defined type, e.g. a `String` for a numeric UDA.
+        WrongType {
+            name: String,
+            expected: &'static str,
+            found: &'static str,
+        },
+        /// The value isn't one of the defined `values`.
+        NotAllowed {
+            name: String,
+            value: String,
+            allowed: Vec<String>,
+        },
+    }
+
+    impl fmt::Display for UdaValidationError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                UdaValidationError::WrongType {
+                    name,
+                    expected,
+                    found,
+                } => write!(f, "UDA {name} should be {expected}, not {found}"),
+                UdaValidationError::NotAllowed {
+                    name,
+                    value,
+                    allowed,
+                } => write!(
+                    f,
+                    "UDA {name} is {value:?}, not one of {}",
+                    allowed.join(", ")
+                ),
+            }
         }
     }
 
-    impl FromStr for Version {
-        type Err = String;
+    impl Error for UdaValidationError {}
 
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            let mut parts = s.split('.');
-            let major = parts
-                .next()
-                .ok_or_else(|| "missing major version".to_string())?
-                .parse::<u32>()
-                .map_err(|e| format!("invalid major version: {}", e))?;
-            let minor = parts
-                .next()
-                .ok_or_else(|| "missing minor version".to_string())?
-                .parse::<u32>()
-                .map_err(|e| format!("invalid minor version: {}", e))?;
-            let patch = parts
+    impl UdaValue {
+        /// The name of the variant, e.g. `Date`.
+        pub(crate) fn variant_name(&self) -> &'static str {
+            match self {
+                UdaValue::String(_) => "String",
+                UdaValue::Numeric(_) => "Numeric",
+                UdaValue::Date(_) => "Date",
+                UdaValue::Duration(_) => "Duration",
+            }
+        }
+    }
+
+    /// Parse a number written by hand, e.g. ` 1,234.5 `.
+    ///
+    /// Surrounding whitespace is ignored, as are commas grouping the whole part in thousands. Other
+    /// commas are rejected, so a decimal comma such as `1,5` isn't misread as `15`.
+    fn parse_numeric(s: &str) -> Result<f64, std::num::ParseFloatError> {
+        let s = s.trim();
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let mut groups = whole.trim_start_matches(['-', '+']).split(',');
+        let grouped = whole.contains(',')
+            && groups
                 .next()
-                .ok_or_else(|| "missing patch version".to_string())?
-                .parse::<u32>()
-                .map_err(|e| format!("invalid patch version: {}", e))?;
-            Ok(Version {
-                major,
-                minor,
-                patch,
+                .is_some_and(|group| (1..=3).contains(&group.len()))
+            && groups.all(|group| group.len() == 3);
+        if !grouped {
+            return s.parse();
+        }
+        let whole = whole.replace(',', "");
+        if s.contains('.') {
+            format!("{whole}.{fraction}").parse()
+        } else {
+            whole.parse()
+        }
+    }
+
+    /// Converters
+    impl UdaValue {
+        pub fn as_uda_string(&self) -> Result<Self, crate::Error> {
+            match self {
+                UdaValue::String(_) => Ok(self.clone()),
+                UdaValue::Numeric(n) => Ok(Self::String(n.to_string())),
+                UdaValue::Date(dt) => Ok(Self::String(dt.format(DATETIME_FORMAT).to_string())),
+                UdaValue::Duration(d) => Ok(Self::String(d.to_string())),
+            }
+        }
+        pub fn as_uda_numeric(&self) -> Result<Self, crate::Error> {
+            match self {
+                UdaValue::String(s) => parse_numeric(s)
+                    .map(Self::Numeric)
+                    .map_err(|e| unparseable(s, e)),
+                UdaValue::Numeric(_) => Ok(self.clone()),
+                UdaValue::Date(_) | UdaValue::Duration(_) => Err(self.incompatible()),
+            }
+        }
+        pub fn as_uda_date(&self) -> Result<Self, crate::Error> {
+            match self {
+                UdaValue::String(s) => {
+                    let dt = chrono::NaiveDateTime::parse_from_str(s, DATETIME_FORMAT)
+                        .map_err(|e| unparseable(s, e))?;
+                    Ok(Self::Date(DateTime::<Utc>::from_naive_utc_and_offset(
+                        dt, Utc,
+                    )))
+                }
+                UdaValue::Date(_) => Ok(self.clone()),
+                UdaValue::Numeric(_) | UdaValue::Duration(_) => Err(self.incompatible()),
+            }
+        }
+        pub fn as_uda_duration(&self) -> Result<Self, crate::Error> {
+            match self {
+                UdaValue::String(_) => Ok(Self::Duration(Duration::try_from(self.clone())?)),
+                UdaValue::Duration(_) => Ok(self.clone()),
+                UdaValue::Numeric(_) | UdaValue::Date(_) => Err(self.incompatible()),
+            }
+        }
+        /// The error for converting this variant to one it can't be.
+        fn incompatible(&self) -> crate::Error {
+            UdaConversionError::IncompatibleVariant(self.variant_name()).into()
+        }
+        /// Read the UDA as a boolean.
+        ///
+        /// * `true`: `yes`, `y`, `true`, `on`, `1`, and the number 1
+        /// * `false`: `no`, `n`, `false`, `off`, `0`, and the number 0
+        ///
+        /// Strings are compared ignoring case and surrounding whitespace. Anything else is `None`.
+        pub fn as_bool(&self) -> Option<bool> {
+            match self {
+                UdaValue::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+                    "yes" | "y" | "true" | "on" | "1" => Some(true),
+                    "no" | "n" | "false" | "off" | "0" => Some(false),
+                    _ => None,
+                },
+                UdaValue::Numeric(n) if *n == 1.0 => Some(true),
+                UdaValue::Numeric(n) if *n == 0.0 => Some(false),
+                UdaValue::Numeric(_) | UdaValue::Date(_) | UdaValue::Duration(_) => None,
+            }
+        }
+    }
+
+    impl Serialize for UdaValue {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                UdaValue::String(s) => serializer.serialize_str(s),
+                UdaValue::Numeric(n) => serializer.serialize_f64(*n),
+                UdaValue::Date(dt) => {
+                    serializer.serialize_str(&dt.format(DATETIME_FORMAT).to_string())
+                }
+                UdaValue::Duration(d) => serializer.serialize_str(&d.to_string()),
+            }
+        }
+    }
+
+    /// Getters (Immutable)
+    impl UdaValue {
+        /// Retrieve the inner value.
+        pub fn inner(&self) -> &dyn Any {
+            match self {
+                UdaValue::String(s) => s,
+                UdaValue::Numeric(n) => n,
+                UdaValue::Date(dt) => dt,
+                UdaValue::Duration(d) => d,
+            }
+        }
+    }
+
+    impl fmt::Display for UdaValue {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let buffer: String = match self {
+                UdaValue::String(s) => s.clone(),
+                UdaValue::Numeric(n) => n.to_string(),
+                UdaValue::Date(dt) => dt.format(crate::DATETIME_FORMAT).to_string(),
+                UdaValue::Duration(d) => d.clone().into(),
+            };
+            write!(f, "{buffer}")
+        }
+    }
+
+    impl From<UdaValue> for String {
+        fn from(uda_value: UdaValue) -> Self {
+            match uda_value {
+                UdaValue::String(s) => s,
+                UdaValue::Numeric(n) => n.to_string(),
+                UdaValue::Date(dt) => dt.format(crate::DATETIME_FORMAT).to_string(),
+                UdaValue::Duration(d) => d.into(),
+            }
+        }
+    }
+
+    impl From<String> for UdaValue {
+        fn from(s: String) -> Self {
+            UdaValue::String(s)
+        }
+    }
+
+    impl From<&str> for UdaValue {
+        fn from(s: &str) -> Self {
+            UdaValue::String(s.to_string())
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for UdaValue {
+        fn deserialize<D>(deserializer: D) -> Result<UdaValue, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct UdaValueVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for UdaValueVisitor {
+                type Value = UdaValue;
+
+                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    formatter.write_str("a string or a number")
+                }
+
+                fn visit_str<E>(self, value: &str) -> Result<UdaValue, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(UdaValue::String(value.to_string()))
+                }
+
+                fn visit_i64<E>(self, value: i64) -> Result<UdaValue, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(UdaValue::Numeric(value as f64))
+                }
+
+                fn visit_u64<E>(self, value: u64) -> Result<UdaValue, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(UdaValue::Numeric(value as f64))
+                }
+
+                fn visit_f64<E>(self, value: f64) -> Result<UdaValue, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(UdaValue::Numeric(value))
+                }
+            }
+
+            deserializer.deserialize_any(UdaValueVisitor)
+        }
+    }
+
+    /// Implement tasklib::Duration into UdaValue
+    impl From<Duration> for UdaValue {
+        fn from(d: Duration) -> Self {
+            UdaValue::Duration(d)
+        }
+    }
+
+    impl From<f64> for UdaValue {
+        fn from(n: f64) -> Self {
+            UdaValue::Numeric(n)
+        }
+    }
+
+    /// Implement chrono::DateTime into UdaValue
+    impl From<DateTime<Utc>> for UdaValue {
+        fn from(d: DateTime<Utc>) -> Self {
+            UdaValue::Date(d)
+        }
+    }
+
+    /// Implement == against string
+    impl PartialEq<String> for UdaValue {
+        fn eq(&self, other: &String) -> bool {
+            match self {
+                UdaValue::String(s) => s == other,
+                _ => false,
+            }
+        }
+    }
+
+    /// Implement == against &str
+    impl PartialEq<str> for UdaValue {
+        fn eq(&self, other: &str) -> bool {
+            match self {
+                UdaValue::String(s) => s == other,
+                _ => false,
+            }
+        }
+    }
+
+    /// Implement == against f64
+    impl PartialEq<f64> for UdaValue {
+        fn eq(&self, other: &f64) -> bool {
+            match self {
+                UdaValue::Numeric(n) => n == other,
+                _ => false,
+            }
+        }
+    }
+
+    /// Implement == against i64
+    impl PartialEq<i64> for UdaValue {
+        fn eq(&self, other: &i64) -> bool {
+            match self {
+                UdaValue::Numeric(n) => *n as i64 == *other,
+                _ => false,
+            }
+        }
+    }
+
+    /// Implement == against DateTime<Utc>
+    impl PartialEq<DateTime<Utc>> for UdaValue {
+        fn eq(&self, other: &DateTime<Utc>) -> bool {
+            match self {
+                UdaValue::Date(d) => d == other,
+                _ => false,
+            }
+        }
+    }
+
+    /// Implement == against Duration
+    impl PartialEq<Duration> for UdaValue {
+        fn eq(&self, other: &Duration) -> bool {
+            match self {
+                UdaValue::Duration(d) => d == other,
+                _ => false,
+            }
+        }
+    }
+
+    /// Represents a Taskwarrior UDA
+    ///
+    /// <https://taskwarrior.org/docs/udas/>
+    ///
+    /// UDAs, at their core, have a name and a value. The name is a string and the value can be one
+    /// of 4 types: string, numeric, date, or duration.
+    ///
+    /// Each can have a label, default, and/or coeeficient.
+    ///
+    /// The label defaults to the capitalized form of the name.
+    ///
+    /// A string type can have a list of values.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub enum Uda {
+        String {
+            name: String,
+            value: String,
+            // defaults to ""
+            label: String,
+            default: String,
+            values: Vec<String>,
+            coefficient: Option<f32>,
+        },
+        Numeric {
+            name: String,
+            value: f64,
+            // defaults to ""
+            label: String,
+            default: f64,
+            coefficient: Option<f32>,
+        },
+        Date {
+            name: String,
+            #[serde(
+                serialize_with = "tw_dt_to_str_se",
+                deserialize_with = "tw_str_to_dt_de"
+            )]
+            value: DateTime<Utc>,
+            // defaults to ""
+            label: String,
+            #[serde(
+                serialize_with = "tw_dt_to_str_opt_se",
+                skip_serializing_if = "Option::is_none",
+                deserialize_with = "tw_str_to_dt_opt_de",
+                default
+            )]
+            default: Option<DateTime<Utc>>,
+            coefficient: Option<f32>,
+        },
+        Duration {
+            name: String,
+            value: Duration,
+            // defaults to ""
+            label: String,
+            default: Option<Duration>,
+            coefficient: Option<f32>,
+        },
+    }
+
+    /// Manually implement Deserialize for Uda
+    ///
+    /// "elapsed": "2.0",   -> Uda::String { name: String::from("elapsed"), value: String::from("2.0"), .. }
+    /// "elapsed": 2.0,     -> Uda::Numeric { name: String::from("elapsed"), value: 2.0, .. }
+    /// "elapsed": "20220131T083000Z", -> Uda::Date { name: String::from("elapsed"), value: Utc.datetime_from_str("20220131T083000Z", "%Y%m%dT%H%M%SZ")
+    /// "elapsed": "PT2H",  -> Uda::Duration { name: String::from("elapsed"), value: Duration::hours(2), .. }
+    ///
+    /// Allow Uda::String{ .. } to be compared to a string
+    ///
+    /// Uses the `value` field of the UDA
+    impl PartialEq<String> for Uda {
+        fn eq(&self, other: &String) -> bool {
+            match self {
+                Uda::String { value, .. } => value == other,
+                _ => false,
+            }
+        }
+    }
+
+    impl PartialEq<str> for Uda {
+        fn eq(&self, other: &str) -> bool {
+            match self {
+                Uda::String { value, .. } => value == other,
+                _ => false,
+            }
+        }
+    }
+
+    impl Uda {
+        // This type isn't yet implemented and may be deprecated
+        #[allow(dead_code)]
+        /// Get the type of the UDA as a string
+        pub fn r#type(&self) -> String {
+            match self {
+                Uda::String { .. } => "string".to_string(),
+                Uda::Numeric { .. } => "numeric".to_string(),
+                Uda::Date { .. } => "date".to_string(),
+                Uda::Duration { .. } => "duration".to_string(),
+            }
+        }
+    }
+
+    /// Validation
+    impl Uda {
+        pub fn name(&self) -> &str {
+            match self {
+                Uda::String { name, .. }
+                | Uda::Numeric { name, .. }
+                | Uda::Date { name, .. }
+                | Uda::Duration { name, .. } => name,
+            }
+        }
+        /// Check a value against this definition.
+        ///
+        /// The value must be of the defined type, or a string that parses as it, as UDAs are
+        /// deserialized as strings. For a string UDA with `values`, it must be one of them.
+        pub fn validate(&self, value: &UdaValue) -> Result<(), UdaValidationError> {
+            let matches = match (self, value) {
+                (Uda::String { values, .. }, UdaValue::String(s)) => {
+                    if !values.is_empty() && !values.contains(s) {
+                        return Err(UdaValidationError::NotAllowed {
+                            name: self.name().to_string(),
+                            value: s.clone(),
+                            allowed: values.clone(),
+                        });
+                    }
+                    true
+                }
+                (Uda::Numeric { .. }, UdaValue::Numeric(_))
+                | (Uda::Date { .. }, UdaValue::Date(_))
+                | (Uda::Duration { .. }, UdaValue::Duration(_)) => true,
+                (Uda::Numeric { .. }, UdaValue::String(s)) => parse_numeric(s).is_ok(),
+                (Uda::Date { .. }, UdaValue::String(s)) => {
+                    chrono::NaiveDateTime::parse_from_str(s, DATETIME_FORMAT).is_ok()
+                }
+                (Uda::Duration { .. }, UdaValue::String(s)) => s.parse::<Duration>().is_ok(),
+                _ => false,
+            };
+            if !matches {
+                return Err(UdaValidationError::WrongType {
+                    name: self.name().to_string(),
+                    expected: self.variant_name(),
+                    found: value.variant_name(),
+                });
+            }
+            Ok(())
+        }
+        /// The name of the variant, e.g. `Date`.
+        fn variant_name(&self) -> &'static str {
+            match self {
+                Uda::String { .. } => "String",
+                Uda::Numeric { .. } => "Numeric",
+                Uda::Date { .. } => "Date",
+                Uda::Duration { .. } => "Duration",
+            }
+        }
+    }
+
+    impl From<Uda> for String {
+        fn from(uda: Uda) -> Self {
+            match uda {
+                Uda::String { value, .. } => value,
+                Uda::Numeric { value, .. } => value.to_string(),
+                Uda::Date { value, .. } => value.format(DATETIME_FORMAT).to_string(),
+                Uda::Duration { value, .. } => value.to_string(),
+            }
+        }
+    }
+
+    // This type isn't yet implemented and may be deprecated
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    enum Type {
+        /// May be provided a list of acceptable values, using the `uda.my_uda.values` key, which
+        /// is set to a string of comma-separated values.
+        ///
+        /// e.g. `task config uda.size.values large,medium,small`
+        String,
+        /// Float
+        Numeric,
+        /// I'm using chrono's DateTime struct
+        Date,
+        Duration,
+    }
+
+    impl fmt::Display for Type {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.to_str())
+        }
+    }
+
+    #[allow(dead_code)]
+    impl Type {
+        fn to_str(&self) -> &str {
+            match self {
+                Type::String => "string",
+                Type::Numeric => "numeric",
+                Type::Date => "date",
+                Type::Duration => "duration",
+            }
+        }
+        fn from_str(s: &str) -> Result<Type, String> {
+            match s {
+                "string" => Ok(Type::String),
+                "numeric" => Ok(Type::Numeric),
+                "date" => Ok(Type::Date),
+                "duration" => Ok(Type::Duration),
+                _ => Err(format!("invalid type: {s}")),
+            }
+        }
+        fn from_string(s: String) -> Result<Type, String> {
+            Type::from_str(&s)
+        }
+    }
+
+    impl Serialize for Type {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(&self.to_string())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Type {
+        fn deserialize<D>(deserializer: D) -> Result<Type, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            Type::from_string(s).map_err(de::Error::custom)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        #[test]
+        fn numeric_coercion() {
+            let numeric = |s: &str| UdaValue::String(s.to_string()).as_uda_numeric();
+
+            assert_eq!(numeric(" 1,234 ").unwrap(), UdaValue::Numeric(1234.0));
+            assert_eq!(
+                numeric("1,234,567.5").unwrap(),
+                UdaValue::Numeric(1234567.5)
+            );
+            assert_eq!(numeric("-1,234").unwrap(), UdaValue::Numeric(-1234.0));
+            assert_eq!(numeric("\t5.0\n").unwrap(), UdaValue::Numeric(5.0));
+            assert_eq!(numeric("1234").unwrap(), UdaValue::Numeric(1234.0));
+
+            assert!(numeric("twelve").is_err());
+            assert!(numeric("").is_err());
+            // Not thousands
+            assert!(numeric("1,5").is_err());
+            assert!(numeric("12,34").is_err());
+            assert!(numeric("1,234,").is_err());
+            assert!(numeric(",123").is_err());
+
+            let def = Uda::Numeric {
+                name: "estimate".to_string(),
+                value: 0.0,
+                label: "".to_string(),
+                default: 0.0,
+                coefficient: None,
+            };
+            assert!(def
+                .validate(&UdaValue::String(" 1,234 ".to_string()))
+                .is_ok());
+        }
+        #[test]
+        fn basic_uda() {
+            let uda_1 = Uda::String {
+                name: "my_uda".to_string(),
+                value: "my_value".to_string(),
+                label: "".to_string(),
+                default: "my_default".to_string(),
+                values: vec!["my_value".to_string(), "my_value_2".to_string()],
+                coefficient: Some(1.0),
+            };
+            let uda_2 = Uda::String {
+                name: "my_uda".to_string(),
+                value: "my_value".to_string(),
+                label: "".to_string(),
+                default: "my_default".to_string(),
+                values: vec!["my_value".to_string(), "my_value_2".to_string()],
+                coefficient: Some(1.0),
+            };
+
+            assert_eq!(uda_1, uda_2);
+        }
+        #[test]
+        fn serialize() {
+            use chrono::TimeZone;
+            use chrono::Utc;
+
+            let uda_string = Uda::String {
+                name: "my_uda".to_string(),
+                value: "my_value".to_string(),
+                label: "".to_string(),
+                default: "my_default".to_string(),
+                values: vec!["my_value".to_string(), "my_value_2".to_string()],
+                coefficient: Some(1.0),
+            };
+            let expected = r#"my_value"#;
+            let actual: String = uda_string.into();
+            assert_eq!(actual, expected);
+
+            let uda_numeric = Uda::Numeric {
+                name: "my_uda".to_string(),
+                value: 1.0,
+                label: "".to_string(),
+                default: 1.0,
+                coefficient: Some(1.0),
+            };
+            let expected = r#"1"#;
+            let actual: String = uda_numeric.into();
+            assert_eq!(actual, expected);
+
+            let uda_date = Uda::Date {
+                name: "my_uda".to_string(),
+                //value: Utc.ymd(2020, 1, 1).and_hms(0, 0, 0),
+                value: Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap(),
+                label: "".to_string(),
+                default: None,
+                coefficient: Some(1.0),
+            };
+            let expected = r#"20200101T000000Z"#;
+            let actual: String = uda_date.into();
+            assert_eq!(actual, expected);
+
+            let uda_duration = Uda::Duration {
+                name: "my_uda".to_string(),
+                value: Duration::days(3),
+                label: "".to_string(),
+                default: None,
+                coefficient: Some(1.0),
+            };
+            let expected = r#"P3D"#;
+            let actual: String = uda_duration.into();
+            assert_eq!(actual, expected);
+        }
+    }
+}
+
+/// This module contains the logic for the CLI arguments given during a hook.
+mod cli {
+
+    use std::env;
+    use std::fmt;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use crate::Error;
+
+    /// // Get the command line arguments.
+    #[derive(Debug, PartialEq)]
+    pub struct CliArguments {
+        hook: PathBuf,
+        api: ApiVersion,
+        args: String,
+        command: Command,
+        rc_file: PathBuf,
+        data_location: PathBuf,
+        task_version: Version,
+    }
+
+    /// Getters (Immutable)
+    impl CliArguments {
+        pub fn hook(&self) -> &PathBuf {
+            &self.hook
+        }
+        pub fn api_version(&self) -> &ApiVersion {
+            &self.api
+        }
+        pub fn arguments(&self) -> &String {
+            &self.args
+        }
+        pub fn command(&self) -> &Command {
+            &self.command
+        }
+        pub fn rc_file(&self) -> &PathBuf {
+            &self.rc_file
+        }
+        pub fn data_location(&self) -> &PathBuf {
+            &self.data_location
+        }
+        pub fn task_version(&self) -> &Version {
+            &self.task_version
+        }
+        /// The arguments split into words, as a shell would.
+        ///
+        /// Words are separated by whitespace, except within single or double quotes. A backslash
+        /// escapes the next character, except within single quotes.
+        ///
+        /// e.g. `task add "My task" +home` -> `["task", "add", "My task", "+home"]`
+        pub fn argument_tokens(&self) -> Vec<String> {
+            split_words(&self.args)
+        }
+    }
+
+    /// Split a string into words, as a shell would. See `CliArguments::argument_tokens`.
+    ///
+    /// An unterminated quote runs to the end of the string.
+    pub(crate) fn split_words(s: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut word: Option<String> = None;
+        let mut quote: Option<char> = None;
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match (quote, c) {
+                (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+                (Some('"') | None, '\\') => {
+                    let word = word.get_or_insert_with(String::new);
+                    word.extend(chars.next());
+                }
+                (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+                (None, '\'' | '"') => {
+                    quote = Some(c);
+                    // Quotes alone are still a word, e.g. `""`
+                    word.get_or_insert_with(String::new);
+                }
+                (None, c) if c.is_whitespace() => words.extend(word.take()),
+                (None, c) => word.get_or_insert_with(String::new).push(c),
+            }
+        }
+        words.extend(word);
+        words
+    }
+
+    impl CliArguments {
+        /// Get the command line arguments from the environemnt.
+        ///
+        /// This is given to the command line as arguments.
+        pub fn from_env() -> Result<Self, Error> {
+            let args: Vec<String> = env::args().collect();
+            Self::from_vec(args)
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the arguments aren't valid hook arguments. Use `CliArguments::from_vec` to
+    /// handle invalid input.
+    impl From<Vec<String>> for CliArguments {
+        fn from(vec: Vec<String>) -> Self {
+            Self::from_vec(vec).unwrap()
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the arguments aren't valid hook arguments. Use `CliArguments::from_env` to
+    /// handle invalid input.
+    impl From<env::Args> for CliArguments {
+        fn from(args: env::Args) -> Self {
+            Self::from_vec(args.collect()).unwrap()
+        }
+    }
+
+    impl CliArguments {
+        /// e.g. vec!["./.task/hooks/on-add_noop.py", "api:2", "args:task add My task", "command:add", "rc:./.taskrc", "data:./.task", "version:2.6.2"]
+        pub fn from_vec(vec: Vec<String>) -> Result<Self, Error> {
+            Self::parse(vec).map_err(Error::Parse)
+        }
+        fn parse(vec: Vec<String>) -> Result<Self, String> {
+            let mut args = vec.into_iter();
+
+            let hook = args
+                .next()
+                .ok_or_else(|| "Missing hook argument".to_string())?;
+            let api = args
+                .next()
+                .ok_or_else(|| "Missing api argument".to_string())?
+                .split_once(':')
+                .map(|(_, value)| value)
+                .ok_or_else(|| "Missing api version".to_string())?
+                .parse::<ApiVersion>()
+                .map_err(|e| e.to_string())?;
+            let task_args = args
+                .next()
+                .ok_or_else(|| "Missing args argument".to_string())?
+                .split_once(':')
+                .map(|(_, value)| value)
+                .ok_or_else(|| "Missing args".to_string())?
+                .to_string();
+            let command = args
+                .next()
+                .ok_or_else(|| "Missing command argument".to_string())?
+                .split_once(':')
+                .map(|(_, value)| value)
+                .ok_or_else(|| "Missing command".to_string())?
+                .parse::<Command>()
+                .map_err(|e| e.to_string())?;
+            let rc_file = args
+                .next()
+                .ok_or_else(|| "Missing rc argument".to_string())?
+                .split_once(':')
+                .map(|(_, value)| value)
+                .ok_or_else(|| "Missing rc file".to_string())?
+                .parse::<PathBuf>()
+                .map_err(|e| format!("Invalid rc file: {}", e))?;
+            let data_location = args
+                .next()
+                .ok_or_else(|| "Missing data argument".to_string())?
+                .split_once(':')
+                .map(|(_, value)| value)
+                .ok_or_else(|| "Missing data location".to_string())?
+                .parse::<PathBuf>()
+                .map_err(|e| format!("Invalid data location: {}", e))?;
+            let task_version = args
+                .next()
+                .ok_or_else(|| "Missing version argument".to_string())?
+                .split_once(':')
+                .map(|(_, value)| value)
+                .ok_or_else(|| "Missing version".to_string())?
+                .parse::<Version>()
+                .map_err(|e| match e {
+                    Error::Parse(message) => format!("Invalid version: {message}"),
+                    e => e.to_string(),
+                })?;
+
+            Ok(Self {
+                hook: PathBuf::from(hook),
+                api,
+                args: task_args,
+                command,
+                rc_file,
+                data_location,
+                task_version,
+            })
+        }
+        /// The arguments as Taskwarrior passes them to a hook, the inverse of `from_vec`.
+        ///
+        /// e.g. vec!["./.task/hooks/on-add_noop.py", "api:2", "args:task add My task", "command:add", "rc:./.taskrc", "data:./.task", "version:2.6.2"]
+        pub fn to_argv(&self) -> Vec<String> {
+            vec![
+                self.hook.display().to_string(),
+                format!("api:{}", self.api),
+                format!("args:{}", self.args),
+                format!("command:{}", self.command),
+                format!("rc:{}", self.rc_file.display()),
+                format!("data:{}", self.data_location.display()),
+                format!("version:{}", self.task_version),
+            ]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub enum ApiVersion {
+        V1,
+        V2,
+        /// An unknown API version.
+        ///
+        /// Can include a message.
+        Unknown(Option<String>),
+    }
+
+    impl FromStr for ApiVersion {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "1" => Ok(ApiVersion::V1),
+                "2" => Ok(ApiVersion::V2),
+                _ => Ok(ApiVersion::Unknown(Some(s.to_string()))),
+            }
+        }
+    }
+
+    impl fmt::Display for ApiVersion {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ApiVersion::V1 => write!(f, "1"),
+                ApiVersion::V2 => write!(f, "2"),
+                ApiVersion::Unknown(s) => write!(f, "{}", s.as_deref().unwrap_or_default()),
+            }
+        }
+    }
+
+    /// <https://taskwarrior.org/docs/commands/>
+    #[derive(Debug, PartialEq)]
+    pub enum Command {
+        /// Add a new task
+        Add,
+        /// Add an annotation to a task
+        Annotate,
+        /// Append words to a task description
+        Append,
+        /// 2.4.0 Expression calculator
+        Calc,
+        /// Modify configuration settings
+        Config,
+        /// Manage contexts
+        Context,
+        /// Count the tasks matching a filter
+        Count,
+        /// Mark a task as deleted
+        Delete,
+        /// Remove an annotation from a task
+        Denotate,
+        /// Complete a task
+        Done,
+        /// Clone an existing task
+        Duplicate,
+        /// Launch your text editor to modify a task
+        Edit,
+        /// Execute an external command
+        Execute,
+        /// Export tasks in JSON format
+        Export,
+        /// Show high-level help, a cheat-sheet
+        Help,
+        /// Import tasks in JSON form
+        Import,
+        /// Record an already-completed task
+        Log,
+        /// Show the Taskwarrior logo
+        Logo,
+        /// Modify one or more tasks
+        Modify,
+        /// Prepend words to a task description
+        Prepend,
+        /// 2.6.0 Completely removes tasks, rather than change status to deleted
+        Purge,
+        /// Start working on a task, make active
+        Start,
+        /// Stop working on a task, no longer active
+        Stop,
+        /// Syncs tasks with Taskserver
+        Synchronize,
+        /// Revert last change
+        Undo,
+        /// Version details and copyright
+        Version,
+        /// An unknown command.
+        ///
+        /// Can include a message.
+        Unknown(Option<String>),
+    }
+
+    impl FromStr for Command {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "add" => Ok(Command::Add),
+                "annotate" => Ok(Command::Annotate),
+                "append" => Ok(Command::Append),
+                "calc" => Ok(Command::Calc),
+                "config" => Ok(Command::Config),
+                "context" => Ok(Command::Context),
+                "count" => Ok(Command::Count),
+                "delete" => Ok(Command::Delete),
+                "denotate" => Ok(Command::Denotate),
+                "done" => Ok(Command::Done),
+                "duplicate" => Ok(Command::Duplicate),
+                "edit" => Ok(Command::Edit),
+                "execute" => Ok(Command::Execute),
+                "export" => Ok(Command::Export),
+                "help" => Ok(Command::Help),
+                "import" => Ok(Command::Import),
+                "log" => Ok(Command::Log),
+                "logo" => Ok(Command::Logo),
+                "modify" => Ok(Command::Modify),
+                "prepend" => Ok(Command::Prepend),
+                "purge" => Ok(Command::Purge),
+                "start" => Ok(Command::Start),
+                "stop" => Ok(Command::Stop),
+                "sync" => Ok(Command::Synchronize),
+                "undo" => Ok(Command::Undo),
+                "version" => Ok(Command::Version),
+                _ => Ok(Command::Unknown(Some(s.to_string()))),
+            }
+        }
+    }
+
+    impl fmt::Display for Command {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let command = match self {
+                Command::Unknown(s) => s.as_deref().unwrap_or_default(),
+                _ => self.as_str().unwrap_or_default(),
+            };
+            write!(f, "{command}")
+        }
+    }
+
+    impl Command {
+        /// The command's name, as given to `task`, without allocating.
+        ///
+        /// `None` for an unknown command, whose name isn't static. See `Display`.
+        pub const fn as_str(&self) -> Option<&'static str> {
+            let command = match self {
+                Command::Add => "add",
+                Command::Annotate => "annotate",
+                Command::Append => "append",
+                Command::Calc => "calc",
+                Command::Config => "config",
+                Command::Context => "context",
+                Command::Count => "count",
+                Command::Delete => "delete",
+                Command::Denotate => "denotate",
+                Command::Done => "done",
+                Command::Duplicate => "duplicate",
+                Command::Edit => "edit",
+                Command::Execute => "execute",
+                Command::Export => "export",
+                Command::Help => "help",
+                Command::Import => "import",
+                Command::Log => "log",
+                Command::Logo => "logo",
+                Command::Modify => "modify",
+                Command::Prepend => "prepend",
+                Command::Purge => "purge",
+                Command::Start => "start",
+                Command::Stop => "stop",
+                Command::Synchronize => "sync",
+                Command::Undo => "undo",
+                Command::Version => "version",
+                Command::Unknown(_) => return None,
+            };
+            Some(command)
+        }
+        /// Whether the command changes task data.
+        ///
+        /// * Writes: `add`, `annotate`, `append`, `delete`, `denotate`, `done`, `duplicate`,
+        ///   `edit`, `execute`, `import`, `log`, `modify`, `prepend`, `purge`, `start`, `stop`,
+        ///   `sync`, `undo`
+        /// * Read-only: `calc`, `config`, `context`, `count`, `export`, `help`, `logo`, `version`,
+        ///   and unknown commands, which are usually reports such as `list` or `next`
+        ///
+        /// `config` and `context` change the `.taskrc`, but not tasks. `execute` runs an arbitrary
+        /// command, so is assumed to write.
+        pub fn is_write(&self) -> bool {
+            match self {
+                Command::Add
+                | Command::Annotate
+                | Command::Append
+                | Command::Delete
+                | Command::Denotate
+                | Command::Done
+                | Command::Duplicate
+                | Command::Edit
+                | Command::Execute
+                | Command::Import
+                | Command::Log
+                | Command::Modify
+                | Command::Prepend
+                | Command::Purge
+                | Command::Start
+                | Command::Stop
+                | Command::Synchronize
+                | Command::Undo => true,
+                Command::Calc
+                | Command::Config
+                | Command::Context
+                | Command::Count
+                | Command::Export
+                | Command::Help
+                | Command::Logo
+                | Command::Version
+                | Command::Unknown(_) => false,
+            }
+        }
+        /// Whether the command leaves task data unchanged. See `Command::is_write`.
+        pub fn is_read_only(&self) -> bool {
+            !self.is_write()
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Version {
+        major: u32,
+        minor: u32,
+        patch: u32,
+    }
+
+    /// Getters (Immutable)
+    impl Version {
+        pub fn major(&self) -> u32 {
+            self.major
+        }
+        pub fn minor(&self) -> u32 {
+            self.minor
+        }
+        pub fn patch(&self) -> u32 {
+            self.patch
+        }
+    }
+
+    impl FromStr for Version {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut parts = s.split('.');
+            let major = parts
+                .next()
+                .ok_or_else(|| Error::Parse("missing major version".to_string()))?
+                .parse::<u32>()
+                .map_err(|e| Error::Parse(format!("invalid major version: {e}")))?;
+            let minor = parts
+                .next()
+                .ok_or_else(|| Error::Parse("missing minor version".to_string()))?
+                .parse::<u32>()
+                .map_err(|e| Error::Parse(format!("invalid minor version: {e}")))?;
+            let patch = parts
+                .next()
+                .ok_or_else(|| Error::Parse("missing patch version".to_string()))?
+                .parse::<u32>()
+                .map_err(|e| Error::Parse(format!("invalid patch version: {e}")))?;
+            Ok(Version {
+                major,
+                minor,
+                patch,
+            })
+        }
+    }
+
+    impl fmt::Display for Version {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        #[test]
+        #[allow(clippy::useless_vec)]
+        fn args_to_cliargs() {
+            let args = vec![
+                "./.task/hooks/on-add_noop.py",
+                "api:2",
+                "args:task add My task",
+                "command:add",
+                "rc:./.taskrc",
+                "data:./.task",
+                "version:2.6.2",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+            let _cli_args = CliArguments::from(args);
+        }
+        #[test]
+        fn cliargs_to_argv() {
+            let args = [
+                "./.task/hooks/on-modify_noop.py",
+                "api:2",
+                "args:task 1 modify due:2022-01-31T08:30:00",
+                "command:modify",
+                "rc:./.taskrc",
+                "data:./.task",
+                "version:2.6.2",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+            let cli_args = CliArguments::from(args.clone());
+            assert_eq!(
+                cli_args.arguments(),
+                "task 1 modify due:2022-01-31T08:30:00"
+            );
+            assert_eq!(cli_args.to_argv(), args);
+            assert_eq!(CliArguments::from(cli_args.to_argv()), cli_args);
+
+            let mut args = args;
+            args[3] = "command:next".to_string();
+            let cli_args = CliArguments::from(args.clone());
+            assert_eq!(cli_args.to_argv(), args);
+        }
+        #[test]
+        fn argument_tokens() {
+            let cli_args = |args: &str| {
+                CliArguments::from(vec![
+                    "./.task/hooks/on-add_noop.py".to_string(),
+                    "api:2".to_string(),
+                    format!("args:{args}"),
+                    "command:add".to_string(),
+                    "rc:./.taskrc".to_string(),
+                    "data:./.task".to_string(),
+                    "version:2.6.2".to_string(),
+                ])
+            };
+            assert_eq!(
+                cli_args("task add My task").argument_tokens(),
+                ["task", "add", "My", "task"]
+            );
+            assert_eq!(
+                cli_args(r#"task add "My task with spaces""#).argument_tokens(),
+                ["task", "add", "My task with spaces"]
+            );
+            assert_eq!(
+                cli_args("task add 'Say \"hi\"'  +home").argument_tokens(),
+                ["task", "add", "Say \"hi\"", "+home"]
+            );
+            assert_eq!(
+                cli_args(r#"task 1 modify project:"Home Garden" due:eom"#).argument_tokens(),
+                ["task", "1", "modify", "project:Home Garden", "due:eom"]
+            );
+            assert_eq!(
+                cli_args(r#"task add It\'s "a \"quoted\" \\ word" '' "#).argument_tokens(),
+                ["task", "add", "It's", r#"a "quoted" \ word"#, ""]
+            );
+            // Unterminated
+            assert_eq!(
+                cli_args(r#"task add "Open quote"#).argument_tokens(),
+                ["task", "add", "Open quote"]
+            );
+            assert!(split_words("   ").is_empty());
+        }
+        #[test]
+        fn command_as_str() {
+            let names = [
+                "add",
+                "annotate",
+                "append",
+                "calc",
+                "config",
+                "context",
+                "count",
+                "delete",
+                "denotate",
+                "done",
+                "duplicate",
+                "edit",
+                "execute",
+                "export",
+                "help",
+                "import",
+                "log",
+                "logo",
+                "modify",
+                "prepend",
+                "purge",
+                "start",
+                "stop",
+                "sync",
+                "undo",
+                "version",
+            ];
+            for name in names {
+                let command: Command = name.parse().unwrap();
+                let as_str: Option<&'static str> = command.as_str();
+                assert_eq!(as_str, Some(name));
+                assert_eq!(command.to_string(), name);
+            }
+            assert_eq!(Command::Synchronize.as_str(), Some("sync"));
+            let unknown: Command = "next".parse().unwrap();
+            assert_eq!(unknown.as_str(), None);
+            assert_eq!(unknown.to_string(), "next");
+        }
+        #[test]
+        fn command_is_write() {
+            for command in [
+                "add", "modify", "done", "delete", "start", "stop", "annotate",
+            ] {
+                let command: Command = command.parse().unwrap();
+                assert!(command.is_write(), "{command:?}");
+                assert!(!command.is_read_only(), "{command:?}");
+            }
+            for command in ["export", "count", "list", "next", "version", "help"] {
+                let command: Command = command.parse().unwrap();
+                assert!(command.is_read_only(), "{command:?}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+    #[test]
+    fn serialize_durations_iso_8601() {
+        assert_eq!("P3D".parse::<Duration>().unwrap(), Duration::days(3),);
+        assert_eq!("P1000D".parse::<Duration>().unwrap(), Duration::days(1000),);
+        assert_eq!("PT10M".parse::<Duration>().unwrap(), Duration::minutes(10),);
+        assert_eq!("P10M".parse::<Duration>().unwrap(), Duration::months(10),);
+        assert_eq!(
+            "P2M3D".parse::<Duration>().unwrap(),
+            Duration::months(2) + Duration::days(3)
+        );
+        assert_eq!("P1Y".parse::<Duration>().unwrap(), Duration::years(1),);
+        assert_eq!(
+            "P1Y3D".parse::<Duration>().unwrap(),
+            Duration::years(1) + Duration::days(3)
+        );
+        assert_eq!("PT50S".parse::<Duration>().unwrap(), Duration::seconds(50));
+        assert_eq!("PT40M".parse::<Duration>().unwrap(), Duration::minutes(40));
+        assert_eq!(
+            "PT40M50S".parse::<Duration>().unwrap(),
+            Duration::minutes(40) + Duration::seconds(50)
+        );
+        assert_eq!(
+            "PT12H40M50S".parse::<Duration>().unwrap(),
+            Duration::hours(12) + Duration::minutes(40) + Duration::seconds(50)
+        );
+        assert_eq!(
+            "P1Y2M3DT12H40M50S".parse::<Duration>().unwrap(),
+            Duration::years(1)
+                + Duration::months(2)
+                + Duration::days(3)
+                + Duration::hours(12)
+                + Duration::minutes(40)
+                + Duration::seconds(50)
+        );
+        assert_eq!(
+            {
+                let dur: Duration = "P1Y2M3DT12H40M50S".into();
+                dur
+            },
+            Duration::years(1)
+                + Duration::months(2)
+                + Duration::days(3)
+                + Duration::hours(12)
+                + Duration::minutes(40)
+                + Duration::seconds(50)
+        );
+        assert_eq!(
+            {
+                let dur: Duration = "P1Y2M3DT12H40M50S".to_string().into();
+                dur
+            },
+            Duration::years(1)
+                + Duration::months(2)
+                + Duration::days(3)
+                + Duration::hours(12)
+                + Duration::minutes(40)
+                + Duration::seconds(50)
+        );
+        assert_eq!(
+            {
+                let dur: Duration = Duration::from("P1Y2M3DT12H40M50S");
+                dur
+            },
+            Duration::years(1)
+                + Duration::months(2)
+                + Duration::days(3)
+                + Duration::hours(12)
+                + Duration::minutes(40)
+                + Duration::seconds(50)
+        );
+        assert_eq!(
+            {
+                let dur: Duration = Duration::from("P1Y2M3DT12H40M50S".to_string());
+                dur
+            },
+            Duration::years(1)
+                + Duration::months(2)
+                + Duration::days(3)
+                + Duration::hours(12)
+                + Duration::minutes(40)
+                + Duration::seconds(50)
+        );
+    }
+    #[test]
+    fn deserialize_durations() {
+        assert_eq!("P3D", Duration::days(3).to_string());
+        assert_eq!("P1000D", Duration::days(1000).to_string());
+        assert_eq!("PT10M", Duration::minutes(10).to_string());
+        assert_eq!("P10M", Duration::months(10).to_string());
+        assert_eq!(
+            "P2M3D",
+            (Duration::months(2) + Duration::days(3)).to_string()
+        );
+        assert_eq!("P1Y", Duration::years(1).to_string());
+        assert_eq!(
+            "P1Y3D",
+            (Duration::years(1) + Duration::days(3)).to_string()
+        );
+        assert_eq!("PT50S", Duration::seconds(50).to_string());
+        assert_eq!("PT40M", Duration::minutes(40).to_string());
+        assert_eq!(
+            "PT40M50S",
+            (Duration::minutes(40) + Duration::seconds(50)).to_string()
+        );
+        assert_eq!(
+            "PT12H40M50S",
+            (Duration::hours(12) + Duration::minutes(40) + Duration::seconds(50)).to_string()
+        );
+        assert_eq!(
+            "P1Y2M3DT12H40M50S",
+            (Duration::years(1)
+                + Duration::months(2)
+                + Duration::days(3)
+                + Duration::hours(12)
+                + Duration::minutes(40)
+                + Duration::seconds(50))
+            .to_string()
+        );
+    }
+
+    #[test]
+    /// Test parsing a task from a string using different methods
+    fn parse_task() {
+        let task_str = r#"
+        {
+            "id": 0,
+            "description": "Task to do.",
+            "end": "20220131T083000Z",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "project": "Daily",
+            "start": "20220131T083000Z",
+            "status": "pending",
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "tags": [
+                "WORK"
+            ],
+            "urgency": 9.91234
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.id, Some(0));
+
+        let task = Task::from(task_str);
+        assert_eq!(task.id, Some(0));
+
+        let task = Task::from(task_str.to_string());
+        assert_eq!(task.id, Some(0));
+
+        let task: Task = task_str.into();
+        assert_eq!(task.id, Some(0));
+
+        let task: Task = task_str.to_string().into();
+        assert_eq!(task.id, Some(0));
+    }
+    #[test]
+    fn convert_durations() {
+        use std::time;
+
+        let duration = time::Duration::from_secs(50);
+        let tasklib_duration: Duration = duration.into();
+        assert_eq!(tasklib_duration, Duration::seconds(50));
+
+        let duration = time::Duration::from_secs((40 * 60) + 50);
+        let tasklib_duration: Duration = duration.into();
+        // assert_eq!(tasklib_duration.to_string(), "PT2450S");
+        assert_eq!(tasklib_duration.to_string(), "PT40M50S"); // smoothing was added for addition
+        assert_eq!(
+            tasklib_duration,
+            Duration::minutes(40) + Duration::seconds(50)
+        );
+
+        let chrono_duration = chrono::Duration::seconds(50);
+        let tasklib_duration: Duration = chrono_duration.into();
+        assert_eq!(tasklib_duration, Duration::seconds(50));
+
+        let duration = time::Duration::from_secs(50);
+        let tasklib_duration = Duration::from(duration);
+        assert_eq!(tasklib_duration, Duration::seconds(50));
+
+        let chrono_duration = chrono::Duration::seconds(50);
+        let tasklib_duration = Duration::from(chrono_duration);
+        assert_eq!(tasklib_duration, Duration::seconds(50));
+    }
+    #[test]
+    fn test_udas() {
+        use chrono::TimeZone;
+
+        // Uses elapsed, a duration type UDA
+        let task_str = r#"
+        {
+            "id": 0,
+            "description": "Task to do.",
+            "end": "20220131T083000Z",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "elapsed": "PT2H",
+            "project": "Daily",
+            "start": "20220131T083000Z",
+            "status": "pending",
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "tags": [
+                "WORK"
+            ],
+            "urgency": 9.91234
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.udas().get("elapsed").unwrap(), "PT2H");
+
+        // Check adding and retreiving udas
+        let mut task = task_str.parse::<Task>().unwrap();
+        task.udas_mut()
+            .insert("elapsed".to_string(), Duration::hours(5).into());
+        assert_eq!(task.udas().get("elapsed").unwrap().to_string(), "PT5H");
+        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"PT5H"}"#.to_string());
+
+        // Check string type
+        task.udas_mut().insert("elapsed".to_string(), "5".into());
+        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"5"}"#.to_string());
+        assert_eq!(serde_json::to_string(&task).unwrap(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"5"}"#.to_string());
+
+        // Check date type
+        task.udas_mut().insert(
+            "elapsed".to_string(),
+            UdaValue::Date(Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap()),
+        );
+        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"20200101T000000Z"}"#.to_string());
+        assert_eq!(serde_json::to_string(&task).unwrap(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"20200101T000000Z"}"#.to_string());
+
+        // Check duration type
+        task.udas_mut().insert(
+            "elapsed".to_string(),
+            UdaValue::Duration(Duration::hours(5)),
+        );
+        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"PT5H"}"#.to_string());
+        assert_eq!(serde_json::to_string(&task).unwrap(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"PT5H"}"#.to_string());
+
+        // Check numeric type
+        task.udas_mut()
+            .insert("elapsed".to_string(), UdaValue::Numeric(5.0));
+        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":5.0}"#.to_string());
+        assert_eq!(serde_json::to_string(&task).unwrap(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":5.0}"#.to_string());
+    }
+
+    #[test]
+    /// Test the different types of UDA values
+    fn test_udas_types() {
+        // String
+        let task_str = r#"
+        {
+            "id": 0,
+            "description": "Task to do.",
+            "end": "20220131T083000Z",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "elapsed": "PT2H",
+            "project": "Daily",
+            "start": "20220131T083000Z",
+            "status": "pending",
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "tags": [
+                "WORK"
+            ],
+            "urgency": 9.91234
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.udas().get("elapsed").unwrap(), "PT2H");
+
+        // Number (integer)
+        let task_str = r#"
+        {
+            "id": 0,
+            "description": "Task to do.",
+            "end": "20220131T083000Z",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "elapsed": 2,
+            "project": "Daily",
+            "start": "20220131T083000Z",
+            "status": "pending",
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "tags": [
+                "WORK"
+            ],
+            "urgency": 9.91234
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.udas().get("elapsed").unwrap(), &2);
+
+        // Number (float)
+        let task_str = r#"
+        {
+            "id": 0,
+            "description": "Task to do.",
+            "end": "20220131T083000Z",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "elapsed": 2.5,
+            "project": "Daily",
+            "start": "20220131T083000Z",
+            "status": "pending",
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "tags": [
+                "WORK"
+            ],
+            "urgency": 9.91234
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.udas().get("elapsed").unwrap(), &2.5);
+    }
+
+    #[test]
+    #[allow(clippy::useless_conversion)]
+    fn builder() {
+        use chrono::ParseError;
+
+        /// e.g."20220131T083000Z" -> DateTime<Utc>
+        fn tw_str_to_dt(s: &str) -> Result<DateTime<Utc>, ParseError> {
+            NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ").map(|dt| dt.and_utc())
+        }
+
+        let task = TaskBuilder::new()
+            .description("Task to do.")
+            .end(tw_str_to_dt("20220131T083000Z").unwrap().into())
+            .entry(tw_str_to_dt("20220131T083000Z").unwrap().into())
+            .modified(tw_str_to_dt("20220131T083000Z").unwrap().into())
+            .project("Daily")
+            .start(tw_str_to_dt("20220131T083000Z").unwrap().into())
+            .status(Status::Pending)
+            .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
+            .tags(vec!["WORK"])
+            .urgency(9.91234)
+            .parent("d67fce70-c0b6-43c5-affc-a21e64567d40")
+            .build();
+        assert_eq!(task.id(), &None);
+    }
+
+    #[test]
+    fn deserialize_task() {
+        // Task should not include null or empty fields when deserialized to JSON
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let task_json = serde_json::to_string(&task).unwrap();
+        let expected_task_json = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","entry":"20220131T083000Z","modified":"20220131T083000Z","status":"pending"}"#;
+        assert_eq!(task_json, expected_task_json);
+    }
+    #[test]
+    fn uda_value_converters() {
+        let uda_value = UdaValue::String("5.0".to_string());
+        uda_value
+            .as_uda_string()
+            .expect("uda value string to string conversion");
+        uda_value
+            .as_uda_numeric()
+            .expect("uda value string to numeric conversion");
+
+        let uda_value = UdaValue::String("20220131T083000Z".to_string());
+        uda_value
+            .as_uda_date()
+            .expect("uda value string to date conversion");
+
+        let uda_value = UdaValue::String("PT2H".to_string());
+        uda_value
+            .as_uda_duration()
+            .expect("uda value string to duration conversion");
+
+        // Invalid
+        let error = UdaValue::String("garbage".to_string())
+            .as_uda_date()
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            crate::Error::UdaConversion(UdaConversionError::Unparseable { ref value, .. })
+                if value == "garbage"
+        ));
+        assert!(UdaValue::String("2022-01-31".to_string())
+            .as_uda_date()
+            .is_err());
+    }
+    #[test]
+    fn uda_value_as_bool() {
+        let string = |s: &str| UdaValue::String(s.to_string());
+        assert_eq!(string("yes").as_bool(), Some(true));
+        assert_eq!(string(" True ").as_bool(), Some(true));
+        assert_eq!(string("0").as_bool(), Some(false));
+        assert_eq!(string("no").as_bool(), Some(false));
+        assert_eq!(string("maybe").as_bool(), None);
+        assert_eq!(string("").as_bool(), None);
+        assert_eq!(UdaValue::Numeric(1.0).as_bool(), Some(true));
+        assert_eq!(UdaValue::Numeric(0.0).as_bool(), Some(false));
+        assert_eq!(UdaValue::Numeric(2.0).as_bool(), None);
+        assert_eq!(UdaValue::Duration(Duration::days(1)).as_bool(), None);
+    }
+    #[test]
+    fn duration_uda() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "elapsed": "PT2H",
+            "estimate": 5,
+            "note": "not a duration"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.duration_uda("elapsed"), Some(Duration::hours(2)));
+        assert_eq!(task.duration_uda("estimate"), None);
+        assert_eq!(task.duration_uda("note"), None);
+        assert_eq!(task.duration_uda("missing"), None);
+
+        task.set_duration_uda("estimate", Duration::minutes(30));
+        assert_eq!(task.duration_uda("estimate"), Some(Duration::minutes(30)));
+        assert_eq!(task.udas().get("estimate").unwrap().to_string(), "PT30M");
+    }
+    #[test]
+    fn filter_udas() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "estimate": "PT2H",
+            "priority": "H",
+            "size": 3
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.udas().len(), 3);
+
+        // Strip all
+        let stripped = task.clone().without_udas();
+        assert!(stripped.udas().is_empty());
+        assert!(stripped.to_json().get("estimate").is_none());
+
+        // Keep a subset
+        let mut subset = task.clone();
+        let keep: HashSet<String> = ["priority".to_string(), "missing".to_string()].into();
+        subset.retain_udas(&keep);
+        assert_eq!(subset.udas().keys().collect::<Vec<_>>(), vec!["priority"]);
+
+        // Rename
+        let mut renamed = task;
+        assert!(renamed.rename_uda("estimate", "effort"));
+        assert!(!renamed.rename_uda("estimate", "effort"));
+        assert!(!renamed.udas().contains_key("estimate"));
+        assert_eq!(renamed.udas().get("effort").unwrap(), "PT2H");
+    }
+    #[test]
+    fn add_tag() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+
+        task.add_tag("+x").unwrap();
+        assert_eq!(task.tags(), &["x".to_string()]);
+        // Duplicates are ignored
+        task.add_tag("x").unwrap();
+        assert_eq!(task.tags(), &["x".to_string()]);
+        // Removal syntax is rejected
+        assert!(task.add_tag("-x").is_err());
+        assert!(task.add_tag("+").is_err());
+        assert!(task.add_tag("two words").is_err());
+        assert_eq!(task.tags(), &["x".to_string()]);
+    }
+    #[test]
+    fn tag_set_operations() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "tags": ["work"]
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let modified = *task.modified();
+
+        // Add, ignoring duplicates
+        task.add_tags(&["+urgent", "work", "phone", "urgent"])
+            .unwrap();
+        assert_eq!(task.tags(), &["work", "urgent", "phone"]);
+        assert!(task.modified() > &modified);
+        // Invalid tags add nothing
+        assert!(task.add_tags(&["home", "-work"]).is_err());
+        assert_eq!(task.tags(), &["work", "urgent", "phone"]);
+
+        // Remove
+        *task.modified_mut() = modified;
+        task.remove_tags(&["missing"]);
+        assert_eq!(task.modified(), &modified);
+        task.remove_tags(&["+phone", "missing"]);
+        assert_eq!(task.tags(), &["work", "urgent"]);
+        assert!(task.modified() > &modified);
+
+        // Common
+        let mut other = task_str.parse::<Task>().unwrap();
+        other.add_tags(&["urgent", "home"]).unwrap();
+        assert_eq!(task.tags_in_common(&other), &["work", "urgent"]);
+        assert_eq!(other.tags_in_common(&task), &["work", "urgent"]);
+
+        // Retain
+        let keep: HashSet<String> = ["urgent".to_string(), "home".to_string()].into();
+        task.retain_tags(&keep);
+        assert_eq!(task.tags(), &["urgent"]);
+    }
+    #[test]
+    fn extract_inline_metadata() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Call Bob +phone project:Work",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        task.extract_inline_metadata();
+        assert_eq!(task.description(), "Call Bob");
+        assert_eq!(task.tags(), &["phone".to_string()]);
+        assert_eq!(task.project(), "Work");
+
+        // Dates, and later tokens win
+        *task.description_mut() = "Pay rent project:Home due:2022-02-01 project:Bills".to_string();
+        task.extract_inline_metadata();
+        assert_eq!(task.description(), "Pay rent");
+        assert_eq!(task.project(), "Bills");
+        assert_eq!(
+            task.due().unwrap().format(DATETIME_FORMAT).to_string(),
+            "20220201T000000Z"
+        );
+
+        // Only trailing tokens are taken
+        *task.description_mut() = "Use C+ +lang and +more words".to_string();
+        task.extract_inline_metadata();
+        assert_eq!(task.description(), "Use C+ +lang and +more words");
+        *task.description_mut() = "Read due:someday".to_string();
+        task.extract_inline_metadata();
+        assert_eq!(task.description(), "Read due:someday");
+        // The first word is kept
+        *task.description_mut() = "+solo".to_string();
+        task.extract_inline_metadata();
+        assert_eq!(task.description(), "+solo");
+    }
+    #[test]
+    fn from_str_lenient() {
+        let task_str = r#"
+        {
+            "UUID": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "Description": "Task to do.",
+            "Status": "pending",
+            "ENTRY": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "Annotations": [{"Entry": "20220131T083000Z", "Description": "A note"}],
+            "Estimate": "PT2H"
+        }
+        "#;
+        assert!(task_str.parse::<Task>().is_err());
+        let task = Task::from_str_lenient(task_str).unwrap();
+        assert_eq!(
+            task.uuid().to_string(),
+            "d67fce70-c0b6-43c5-affc-a21e64567d40"
+        );
+        assert_eq!(task.description(), "Task to do.");
+        assert_eq!(task.status(), &Status::Pending);
+        assert_eq!(task.annotations().len(), 1);
+        // UDAs keep their case
+        assert!(task.udas().contains_key("Estimate"));
+    }
+    #[test]
+    fn status_transitions() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "deleted",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let modified = *task.modified();
+
+        assert!(task.set_status_checked(Status::Completed).is_err());
+        assert_eq!(task.status(), &Status::Deleted);
+        assert_eq!(task.modified(), &modified);
+
+        task.set_status_checked(Status::Pending).unwrap();
+        assert_eq!(task.status(), &Status::Pending);
+        assert!(task.modified() > &modified);
+        assert_eq!(task.end(), None);
+        task.set_status_checked(Status::Completed).unwrap();
+        assert_eq!(task.end(), Some(task.modified()));
+        task.validate().unwrap();
+        task.set_status_checked(Status::Pending).unwrap();
+        assert_eq!(task.end(), None);
+        task.validate().unwrap();
+
+        assert!(Status::Recurring.can_transition_to(&Status::Pending));
+        assert!(!Status::Recurring.can_transition_to(&Status::Completed));
+        assert!(!Status::Pending.can_transition_to(&Status::Recurring));
+    }
+    #[test]
+    fn status_as_str() {
+        for (status, name) in [
+            (Status::Completed, "completed"),
+            (Status::Pending, "pending"),
+            (Status::Recurring, "recurring"),
+            (Status::Deleted, "deleted"),
+        ] {
+            let as_str: &'static str = status.as_str();
+            assert_eq!(as_str, name);
+            // As serialized
+            assert_eq!(serde_json::to_value(&status).unwrap(), name);
+        }
+    }
+    #[test]
+    fn is_due_within() {
+        use chrono::TimeZone;
+
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220203T083000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let now = Utc.with_ymd_and_hms(2022, 1, 31, 8, 30, 0).unwrap();
+        let week = Duration::days(7);
+
+        // In 3 days
+        assert!(task.is_due_within(&week, now));
+        assert!(task.virtual_tags(now).contains(&"DUE"));
+        assert!(!task.is_due_within(&Duration::days(3), now));
+        assert!(task.is_due_within(&Duration::years(u32::MAX), now));
+        // Overdue
+        let later = Utc.with_ymd_and_hms(2022, 2, 4, 0, 0, 0).unwrap();
+        assert!(!task.is_due_within(&week, later));
+
+        // In 10 days
+        *task.due_mut() = Some(Utc.with_ymd_and_hms(2022, 2, 10, 8, 30, 0).unwrap());
+        assert!(!task.is_due_within(&week, now));
+        assert!(!task.virtual_tags(now).contains(&"DUE"));
+        assert!(task
+            .virtual_tags_with(now, &Duration::days(14))
+            .contains(&"DUE"));
+
+        *task.due_mut() = None;
+        assert!(!task.is_due_within(&week, now));
+    }
+    #[test]
+    fn due_status() {
+        use chrono::TimeZone;
+
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let now = Utc.with_ymd_and_hms(2022, 2, 1, 0, 0, 0).unwrap();
+        let week = Duration::days(7);
+        let in_days = |days| Some(now + chrono::Duration::days(days));
+
+        assert_eq!(task.due_status(&week, now), DueStatus::None);
+        *task.due_mut() = in_days(-1);
+        assert_eq!(task.due_status(&week, now), DueStatus::Overdue);
+        *task.due_mut() = Some(now);
+        assert_eq!(task.due_status(&week, now), DueStatus::DueSoon);
+        *task.due_mut() = in_days(3);
+        assert_eq!(task.due_status(&week, now), DueStatus::DueSoon);
+        *task.due_mut() = in_days(7);
+        assert_eq!(task.due_status(&week, now), DueStatus::Later);
+        assert_eq!(task.due_status(&Duration::days(8), now), DueStatus::DueSoon);
+    }
+    #[test]
+    fn set_field() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let modified = *task.modified();
+
+        // Date
+        task.set_field("due", "2022-02-03").unwrap();
+        assert_eq!(
+            task.due().unwrap().format(DATETIME_FORMAT).to_string(),
+            "20220203T000000Z"
+        );
+        assert!(task.modified() > &modified);
+        assert!(matches!(
+            task.set_field("due", "tomorrow"),
+            Err(Error::Parse(_))
+        ));
+        assert!(task.due().is_some());
+
+        // Clear
+        task.set_field("due", "").unwrap();
+        assert!(task.due().is_none());
+        assert!(matches!(
+            task.set_field("entry", ""),
+            Err(Error::Validation(_))
+        ));
+
+        // UDA
+        task.set_field("estimate", "PT2H").unwrap();
+        assert_eq!(
+            task.udas().get("estimate"),
+            Some(&UdaValue::String("PT2H".to_string()))
+        );
+        task.set_field("priority", "H").unwrap();
+        assert_eq!(task.priority(), Some(Priority::High));
+        task.set_field("estimate", "").unwrap();
+        assert!(!task.udas().contains_key("estimate"));
+
+        // Others
+        task.set_field("tags", "+home garden").unwrap();
+        assert_eq!(task.tags(), &["home", "garden"]);
+        task.set_field("status", "completed").unwrap();
+        assert_eq!(task.status(), &Status::Completed);
+        task.set_field("recur", "weekly").unwrap();
+        assert_eq!(task.recur(), Some(&Duration::weeks(1)));
+        task.set_field("depends", "d67fce70-c0b6-43c5-affc-a21e64567d40")
+            .unwrap();
+        assert_eq!(task.depends().len(), 1);
+        assert!(task.set_field("uuid", "").is_err());
+        task.set_field("modified", "20220131T083000Z").unwrap();
+        assert_eq!(task.modified(), &modified);
+    }
+    #[test]
+    fn validate_timestamps() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "completed",
+            "entry": "20220131T083000Z",
+            "modified": "20220130T083000Z",
+            "start": "20220201T090000Z",
+            "end": "20220201T080000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let error = task.validate().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "validation failed: inconsistent fields: modified is before entry, end is before start"
+        );
+
+        task.fix_timestamps();
+        assert!(task.validate().is_ok());
+        assert_eq!(task.modified(), task.entry());
+        assert_eq!(task.end(), task.start());
+        // Already in order
+        let fixed = task.clone();
+        task.fix_timestamps();
+        assert_eq!(task, fixed);
+
+        // As when building
+        *task.status_mut() = Status::Pending;
+        assert_eq!(
+            task.validate().unwrap_err().to_string(),
+            "validation failed: inconsistent fields: end is set on a pending task"
+        );
+    }
+    #[test]
+    fn start_stop_complete() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let modified = *task.modified();
+        let at = *task.entry() + chrono::Duration::hours(1);
+
+        // Stopping a task that isn't started
+        assert_eq!(task.stop(), Err(StateError::NotStarted));
+        assert_eq!(task.modified(), &modified);
+
+        task.start_at(at).unwrap();
+        assert_eq!(task.start(), Some(&at));
+        assert!(task.modified() > &modified);
+        assert_eq!(task.start_at(at), Err(StateError::AlreadyStarted));
+        task.stop().unwrap();
+        assert!(task.start().is_none());
+
+        task.start_at(at).unwrap();
+        task.complete_at(at).unwrap();
+        assert_eq!(task.status(), &Status::Completed);
+        assert_eq!(task.end(), Some(&at));
+        assert!(task.start().is_none());
+
+        // Starting a completed task
+        let error = task.start_at(at).unwrap_err();
+        assert_eq!(
+            error,
+            StateError::InvalidStatus {
+                action: "start",
+                status: Status::Completed,
+            }
+        );
+        assert_eq!(error.to_string(), "cannot start a completed task");
+        assert!(task.start().is_none());
+        assert_eq!(
+            task.complete_at(at).unwrap_err().to_string(),
+            "cannot complete a completed task"
+        );
+
+        *task.status_mut() = Status::Deleted;
+        assert!(task.start_at(at).is_err());
+        assert!(task.complete_at(at).is_err());
+    }
+    #[test]
+    fn get_path() {
+        let task_str = r#"
+        {
+            "id": 3,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220203T160000Z",
+            "tags": ["home", "garden"],
+            "annotations": [{"entry": "20220131T090000Z", "description": "Use rainwater"}],
+            "estimate": "PT2H"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let get = |path| task.get_path(path);
+
+        assert_eq!(get("due.year").as_deref(), Some("2022"));
+        assert_eq!(get("due.month").as_deref(), Some("2"));
+        assert_eq!(get("due.day").as_deref(), Some("3"));
+        assert_eq!(get("due.week").as_deref(), Some("5"));
+        assert_eq!(get("due.weekday").as_deref(), Some("4"));
+        assert_eq!(get("due.hour").as_deref(), Some("16"));
+        assert_eq!(get("entry.julian").as_deref(), Some("31"));
+        assert_eq!(get("tags.count").as_deref(), Some("2"));
+        assert_eq!(get("annotations.count").as_deref(), Some("1"));
+        assert_eq!(get("depends.count").as_deref(), Some("0"));
+
+        // Plain
+        assert_eq!(get("description").as_deref(), Some("Task to do."));
+        assert_eq!(get("due").as_deref(), Some("20220203T160000Z"));
+        assert_eq!(get("id").as_deref(), Some("3"));
+        assert_eq!(get("tags").as_deref(), Some("home,garden"));
+        assert_eq!(get("estimate").as_deref(), Some("PT2H"));
+
+        // Unknown, or not set
+        assert_eq!(get("due.century"), None);
+        assert_eq!(get("nonexistent"), None);
+        assert_eq!(get("nonexistent.year"), None);
+        assert_eq!(get("description.year"), None);
+        assert_eq!(get("wait"), None);
+        assert_eq!(get("wait.year"), None);
+        assert_eq!(get("annotations"), None);
+        assert_eq!(get(""), None);
+    }
+    #[test]
+    fn open_and_closed() {
+        assert!(Status::Pending.is_open());
+        assert!(Status::Recurring.is_open());
+        assert!(!Status::Completed.is_open());
+        assert!(!Status::Deleted.is_open());
+        assert!(Status::Completed.is_closed());
+        assert!(Status::Deleted.is_closed());
+        assert!(!Status::Pending.is_closed());
+        assert!(!Status::Recurring.is_closed());
+
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "wait": "20991231T000000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        // Waiting
+        let now = Utc::now();
+        assert!(task.is_waiting(now));
+        assert!(task.is_open());
+        *task.wait_mut() = Some(now);
+        assert!(!task.is_waiting(now));
+        assert!(task.is_open());
+        *task.status_mut() = Status::Completed;
+        assert!(!task.is_open());
+        *task.status_mut() = Status::Deleted;
+        assert!(!task.is_open());
+        *task.status_mut() = Status::Recurring;
+        assert!(task.is_open());
+    }
+    #[test]
+    fn priority_ordering() {
+        assert!(Priority::High > Priority::Medium);
+        assert!(Priority::Medium > Priority::Low);
+        assert_eq!(
+            Priority::cmp_option(Some(Priority::Low), None),
+            std::cmp::Ordering::Greater
+        );
+        assert_eq!(Priority::cmp_option(None, None), std::cmp::Ordering::Equal);
+
+        let mut priorities = vec![
+            Some(Priority::Medium),
+            None,
+            Some(Priority::High),
+            Some(Priority::Low),
+        ];
+        priorities.sort_by(|a, b| Priority::cmp_option(*b, *a));
+        assert_eq!(
+            priorities,
+            vec![
+                Some(Priority::High),
+                Some(Priority::Medium),
+                Some(Priority::Low),
+                None
+            ]
+        );
+    }
+    #[test]
+    fn priority_urgency() {
+        assert_eq!(Priority::High.urgency_value(), 6.0);
+        assert_eq!(Priority::Medium.urgency_value(), 3.9);
+        assert_eq!(Priority::Low.urgency_value(), 1.8);
+
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "priority": "M"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.priority(), Some(Priority::Medium));
+        task.udas_mut().remove("priority");
+        assert_eq!(task.priority(), None);
+        assert_eq!(serde_json::to_string(&Priority::High).unwrap(), r#""H""#);
+    }
+    #[test]
+    fn as_object() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "estimate": "PT2H",
+            "priority": "H"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let map = task.as_object();
+        assert_eq!(map["uuid"], "d67fce70-c0b6-43c5-affc-a21e64567d40");
+        assert_eq!(map["estimate"], "PT2H");
+        assert_eq!(map["priority"], "H");
+        assert!(!map.contains_key("project"));
+        assert_eq!(serde_json::Value::Object(map.clone()), task.to_json());
+        assert_eq!(serde_json::Map::from(&task), map);
+    }
+    #[test]
+    fn uda_order() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "zone": "b",
+            "estimate": 3,
+            "area": "a"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        task.udas_mut()
+            .insert("owner".to_string(), UdaValue::String("me".to_string()));
+        assert_eq!(
+            task.udas().keys().collect::<Vec<_>>(),
+            vec!["area", "estimate", "owner", "zone"]
+        );
+        assert!(task
+            .to_json_string()
+            .ends_with(r#""area":"a","estimate":3.0,"owner":"me","zone":"b"}"#));
+    }
+    #[test]
+    fn to_json_string_sorted() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "zone": "b",
+            "estimate": 3,
+            "area": "a",
+            "owner": "me"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let json = task.to_json_string_sorted();
+        assert_eq!(json, task.clone().to_json_string_sorted());
+        assert_eq!(
+            json,
+            task_str.parse::<Task>().unwrap().to_json_string_sorted()
+        );
+        assert_eq!(
+            json,
+            r#"{"area":"a","description":"Task to do.","entry":"20220131T083000Z","estimate":3.0,"modified":"20220131T083000Z","owner":"me","status":"pending","uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","zone":"b"}"#
+        );
+    }
+    #[test]
+    fn project_hierarchy() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "project": "Work.ClientA.Phase1"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.project_hierarchy(), vec!["Work", "ClientA", "Phase1"]);
+        *task.project_mut() = "Home".to_string();
+        assert_eq!(task.project_hierarchy(), vec!["Home"]);
+        task.project_mut().clear();
+        assert!(task.project_hierarchy().is_empty());
+    }
+    #[test]
+    fn remove_annotations() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "annotations": [
+                {"entry": "20220131T083000Z", "description": "Call back later"},
+                {"entry": "20220131T083000Z", "description": "Call"},
+                {"entry": "20220131T083000Z", "description": "Left a message"}
+            ]
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let modified = *task.modified();
+
+        // Non-matching text
+        assert!(!task.denotate("Email"));
+        assert_eq!(task.annotations().len(), 3);
+        assert_eq!(task.modified(), &modified);
+        // Empty text
+        assert!(!task.denotate(""));
+        assert!(!task.denotate("  "));
+        assert_eq!(task.annotations().len(), 3);
+        // Exact text is preferred
+        assert!(task.denotate("Call"));
+        assert!(task.modified() > &modified);
+        let descriptions: Vec<&str> = task.annotations().iter().map(|a| a.description()).collect();
+        assert_eq!(descriptions, vec!["Call back later", "Left a message"]);
+        // Partial text
+        assert!(task.denotate("message"));
+        assert_eq!(task.annotations().len(), 1);
+
+        // By index
+        assert!(task.remove_annotation_at(1).is_none());
+        let annotation = task.remove_annotation_at(0).unwrap();
+        assert_eq!(annotation.description(), "Call back later");
+        assert!(task.annotations().is_empty());
+    }
+    #[test]
+    fn urgency_as_string() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "urgency": URGENCY
+        }
+        "#;
+        let number = task_str.replace("URGENCY", "9.9").parse::<Task>().unwrap();
+        let string = task_str
+            .replace("URGENCY", r#""9.9""#)
+            .parse::<Task>()
+            .unwrap();
+        assert_eq!(number.urgency(), &Some(9.9));
+        assert_eq!(string.urgency(), &Some(9.9));
+        // Serialized as a number
+        assert_eq!(string.to_json()["urgency"], 9.9);
+
+        let integer = task_str.replace("URGENCY", "9").parse::<Task>().unwrap();
+        assert_eq!(integer.urgency(), &Some(9.0));
+        let null = task_str.replace("URGENCY", "null").parse::<Task>().unwrap();
+        assert_eq!(null.urgency(), &None);
+        assert!(task_str
+            .replace("URGENCY", r#""high""#)
+            .parse::<Task>()
+            .is_err());
+    }
+    #[test]
+    fn non_finite_urgency() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "urgency": 1.5
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        assert_eq!(task.to_json()["urgency"], 1.5);
+
+        for urgency in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            *task.urgency_mut() = Some(urgency);
+            let json = task.to_json_string();
+            assert!(!json.contains("urgency"), "{json}");
+            assert_eq!(json.parse::<Task>().unwrap().urgency(), &None);
+        }
+    }
+    #[test]
+    fn parse_annotation() {
+        let annotation: Annotation = "20220131T083000Z Called the client".parse().unwrap();
+        assert_eq!(
+            annotation.entry().format(DATETIME_FORMAT).to_string(),
+            "20220131T083000Z"
+        );
+        assert_eq!(annotation.description(), "Called the client");
+
+        let before = truncate_to_seconds(Utc::now());
+        let annotation: Annotation = " Called the client ".parse().unwrap();
+        assert!(annotation.entry() >= &before);
+        assert_eq!(annotation.description(), "Called the client");
+        // A timestamp alone is a description
+        let annotation: Annotation = "20220131T083000Z".parse().unwrap();
+        assert_eq!(annotation.description(), "20220131T083000Z");
+
+        assert!("".parse::<Annotation>().is_err());
+    }
+    #[test]
+    fn matches_uuid_or_short() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert!(task.matches_uuid_or_short("d67fce70-c0b6-43c5-affc-a21e64567d40"));
+        assert!(task.matches_uuid_or_short("D67FCE70-C0B6-43C5-AFFC-A21E64567D40"));
+        assert!(task.matches_uuid_or_short("d67fce70"));
+        assert!(task.matches_uuid_or_short("D67FCE70-c0"));
+        // Too short to be unambiguous
+        assert!(!task.matches_uuid_or_short("d67fce7"));
+        assert!(!task.matches_uuid_or_short(""));
+        // Not a prefix
+        assert!(!task.matches_uuid_or_short("67fce70-c0"));
+        assert!(!task.matches_uuid_or_short("d67fce70-c0b6-43c5-affc-a21e64567d40x"));
+    }
+    #[test]
+    fn canonical_cmp() {
+        let uuids = [
+            "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f",
+            "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b",
+            "11111111-2222-4333-8444-555555555555",
+        ];
+        let mut tasks: Vec<Task> = uuids
+            .iter()
+            .map(|uuid| {
+                let mut task = Task::new("Task to do.");
+                *task.uuid_mut() = Uuid::parse_str(uuid).unwrap();
+                task
             })
+            .collect();
+        tasks.sort_by(Task::canonical_cmp);
+        let sorted: Vec<String> = tasks.iter().map(|task| task.uuid().to_string()).collect();
+        assert_eq!(
+            sorted,
+            [
+                "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f",
+                "11111111-2222-4333-8444-555555555555",
+                "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b",
+                "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            ]
+        );
+        assert!(tasks[0] < tasks[1]);
+        assert_eq!(tasks[0].partial_cmp(&tasks[0]), Some(Ordering::Equal));
+
+        // Same UUID, but a different task
+        let mut other = tasks[0].clone();
+        *other.description_mut() = "Something else.".to_string();
+        assert_eq!(tasks[0].canonical_cmp(&other), Ordering::Equal);
+        assert_eq!(tasks[0].partial_cmp(&other), None);
+    }
+    #[test]
+    fn new_and_chaining() {
+        use chrono::TimeZone;
+
+        let due = Utc.with_ymd_and_hms(2022, 1, 31, 8, 30, 0).unwrap();
+        let task = Task::new("Water the plants")
+            .with_tag("home")
+            .with_tag("+home")
+            .with_project("Garden")
+            .with_due(due)
+            .with_uda("estimate", 2.0)
+            .with_uda("area", "back");
+
+        assert_eq!(task.description(), "Water the plants");
+        assert_eq!(task.status(), &Status::Pending);
+        assert_eq!(task.entry(), task.modified());
+        assert!(!task.uuid_synthesized());
+        assert_eq!(task.tags(), &["home"]);
+        assert_eq!(task.due(), Some(&due));
+        assert_eq!(task.udas()["estimate"], 2.0);
+        assert_eq!(&task.udas()["area"], "back");
+        assert_eq!(task.to_json_string().parse::<Task>().unwrap(), task);
+
+        let task = Task::new("x").with_tag("two words");
+        assert_eq!(task.tags(), &["two words"]);
+        assert!(task.validate().is_err());
+    }
+    #[test]
+    fn missing_entry() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let before = truncate_to_seconds(Utc::now());
+        let task = task_str.parse::<Task>().unwrap();
+        assert!(task.entry() >= &before && task.entry() <= &Utc::now());
+        assert_eq!(task.entry().nanosecond(), 0);
+        assert!(task.to_json_string().contains(r#""entry":"#));
+    }
+    #[test]
+    fn missing_uuid() {
+        let task_str = r#"
+        {
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert!(task.uuid_synthesized());
+        assert_eq!(task.uuid().get_version(), Some(uuid::Version::Random));
+        assert_ne!(task.uuid(), task_str.parse::<Task>().unwrap().uuid());
+        assert!(matches!(
+            Task::from_str_strict(task_str),
+            Err(Error::Parse(e)) if e == "missing required field: uuid"
+        ));
+
+        // The generated UUID is kept
+        let json = task.to_json_string();
+        let parsed = Task::from_str_strict(&json).unwrap();
+        assert!(!parsed.uuid_synthesized());
+        assert_eq!(parsed, task);
+
+        let mut task = task;
+        *task.uuid_mut() = Uuid::parse_str("d67fce70-c0b6-43c5-affc-a21e64567d40").unwrap();
+        assert!(!task.uuid_synthesized());
+    }
+    #[test]
+    fn recurrence() {
+        let template_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Water the plants",
+            "status": "recurring",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220131T083000Z",
+            "recur": "weekly",
+            "mask": "-+"
+        }
+        "#;
+        let instance_str = r#"
+        {
+            "uuid": "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b",
+            "description": "Water the plants",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220207T083000Z",
+            "recur": "weekly",
+            "parent": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "imask": 1
+        }
+        "#;
+        let task_str = r#"
+        {
+            "uuid": "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z"
         }
+        "#;
+        let template = template_str.parse::<Task>().unwrap();
+        let instance = instance_str.parse::<Task>().unwrap();
+        let task = task_str.parse::<Task>().unwrap();
+
+        assert!(template.is_recurrence_template());
+        assert!(!template.is_recurrence_instance());
+        assert_eq!(template.parent_uuid(), None);
+
+        assert!(!instance.is_recurrence_template());
+        assert!(instance.is_recurrence_instance());
+        assert_eq!(instance.parent_uuid(), Some(template.uuid()));
+
+        assert!(!task.is_recurrence_template());
+        assert!(!task.is_recurrence_instance());
+
+        // Instances have an imask as well as a parent
+        let mut orphan = instance.clone();
+        *orphan.imask_mut() = None;
+        assert!(!orphan.is_recurrence_instance());
     }
+    #[test]
+    fn effective_due() {
+        use chrono::TimeZone;
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        #[test]
-        fn args_to_cliargs() {
-            let args = vec![
-                "./.task/hooks/on-add_noop.py",
-                "api:2",
-                "args:task add My task",
-                "command:add",
-                "rc:./.taskrc",
-                "data:./.task",
-                "version:2.6.2",
-            ]
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
-            let _cli_args = CliArguments::from(args);
+        let template_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Water the plants",
+            "status": "recurring",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220131T083000Z",
+            "recur": "weekly",
+            "until": "20220301T000000Z"
         }
-    }
-}
+        "#;
+        let task_str = r#"
+        {
+            "uuid": "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220105T083000Z"
+        }
+        "#;
+        let mut template = template_str.parse::<Task>().unwrap();
+        let task = task_str.parse::<Task>().unwrap();
+        let now = Utc.with_ymd_and_hms(2022, 2, 10, 0, 0, 0).unwrap();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[test]
-    fn serialize_durations_iso_8601() {
-        assert_eq!("P3D".parse::<Duration>().unwrap(), Duration::days(3),);
-        assert_eq!("P1000D".parse::<Duration>().unwrap(), Duration::days(1000),);
-        assert_eq!("PT10M".parse::<Duration>().unwrap(), Duration::minutes(10),);
-        assert_eq!("P10M".parse::<Duration>().unwrap(), Duration::months(10),);
-        assert_eq!(
-            "P2M3D".parse::<Duration>().unwrap(),
-            Duration::months(2) + Duration::days(3)
-        );
-        assert_eq!("P1Y".parse::<Duration>().unwrap(), Duration::years(1),);
-        assert_eq!(
-            "P1Y3D".parse::<Duration>().unwrap(),
-            Duration::years(1) + Duration::days(3)
-        );
-        assert_eq!("PT50S".parse::<Duration>().unwrap(), Duration::seconds(50));
-        assert_eq!("PT40M".parse::<Duration>().unwrap(), Duration::minutes(40));
-        assert_eq!(
-            "PT40M50S".parse::<Duration>().unwrap(),
-            Duration::minutes(40) + Duration::seconds(50)
-        );
-        assert_eq!(
-            "PT12H40M50S".parse::<Duration>().unwrap(),
-            Duration::hours(12) + Duration::minutes(40) + Duration::seconds(50)
-        );
-        assert_eq!(
-            "P1Y2M3DT12H40M50S".parse::<Duration>().unwrap(),
-            Duration::years(1)
-                + Duration::months(2)
-                + Duration::days(3)
-                + Duration::hours(12)
-                + Duration::minutes(40)
-                + Duration::seconds(50)
-        );
-        assert_eq!(
-            {
-                let dur: Duration = "P1Y2M3DT12H40M50S".into();
-                dur
-            },
-            Duration::years(1)
-                + Duration::months(2)
-                + Duration::days(3)
-                + Duration::hours(12)
-                + Duration::minutes(40)
-                + Duration::seconds(50)
-        );
+        // Concrete due, even if past
+        assert_eq!(task.effective_due(now), task.due().copied());
+        // Weekly from the 31st
         assert_eq!(
-            {
-                let dur: Duration = "P1Y2M3DT12H40M50S".to_string().into();
-                dur
-            },
-            Duration::years(1)
-                + Duration::months(2)
-                + Duration::days(3)
-                + Duration::hours(12)
-                + Duration::minutes(40)
-                + Duration::seconds(50)
+            template.effective_due(now),
+            Some(Utc.with_ymd_and_hms(2022, 2, 14, 8, 30, 0).unwrap())
         );
         assert_eq!(
-            {
-                let dur: Duration = Duration::from("P1Y2M3DT12H40M50S");
-                dur
-            },
-            Duration::years(1)
-                + Duration::months(2)
-                + Duration::days(3)
-                + Duration::hours(12)
-                + Duration::minutes(40)
-                + Duration::seconds(50)
+            template.effective_due(*template.entry()),
+            template.due().copied()
         );
+        // Past `until`
+        let later = Utc.with_ymd_and_hms(2022, 2, 28, 9, 0, 0).unwrap();
+        assert_eq!(template.effective_due(later), None);
+        // From `entry`, without `due`
+        *template.due_mut() = None;
+        *template.entry_mut() = Utc.with_ymd_and_hms(2022, 2, 1, 12, 0, 0).unwrap();
         assert_eq!(
-            {
-                let dur: Duration = Duration::from("P1Y2M3DT12H40M50S".to_string());
-                dur
-            },
-            Duration::years(1)
-                + Duration::months(2)
-                + Duration::days(3)
-                + Duration::hours(12)
-                + Duration::minutes(40)
-                + Duration::seconds(50)
+            template.effective_due(now),
+            Some(Utc.with_ymd_and_hms(2022, 2, 15, 12, 0, 0).unwrap())
         );
+        // Beyond `MAX_OCCURRENCES` periods
+        *template.recur_mut() = Some("PT1H".parse().unwrap());
+        *template.until_mut() = None;
+        let far = Utc.with_ymd_and_hms(2034, 2, 1, 0, 0, 0).unwrap();
+        assert_eq!(template.effective_due(far), None);
     }
     #[test]
-    fn deserialize_durations() {
-        assert_eq!("P3D", Duration::days(3).to_string());
-        assert_eq!("P1000D", Duration::days(1000).to_string());
-        assert_eq!("PT10M", Duration::minutes(10).to_string());
-        assert_eq!("P10M", Duration::months(10).to_string());
-        assert_eq!(
-            "P2M3D",
-            (Duration::months(2) + Duration::days(3)).to_string()
-        );
-        assert_eq!("P1Y", Duration::years(1).to_string());
+    fn recurrence_dates() {
+        use chrono::TimeZone;
+
+        let template_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Pay rent",
+            "status": "recurring",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220131T083000Z",
+            "recur": "P1M"
+        }
+        "#;
+        let mut template = template_str.parse::<Task>().unwrap();
+        // Parsed as 30 days, as Taskwarrior does
+        *template.recur_mut() = Some(Duration::months(1));
+        let date = |month, day| Utc.with_ymd_and_hms(2022, month, day, 8, 30, 0).unwrap();
+        let from = Utc.with_ymd_and_hms(2022, 2, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2022, 8, 1, 0, 0, 0).unwrap();
+
+        // Clamped to the end of shorter months, without drifting
         assert_eq!(
-            "P1Y3D",
-            (Duration::years(1) + Duration::days(3)).to_string()
+            template.recurrence_dates(from, to),
+            vec![
+                date(2, 28),
+                date(3, 31),
+                date(4, 30),
+                date(5, 31),
+                date(6, 30),
+                date(7, 31)
+            ]
         );
-        assert_eq!("PT50S", Duration::seconds(50).to_string());
-        assert_eq!("PT40M", Duration::minutes(40).to_string());
+        // Half-open
         assert_eq!(
-            "PT40M50S",
-            (Duration::minutes(40) + Duration::seconds(50)).to_string()
+            template.recurrence_dates(date(3, 31), date(4, 30)),
+            vec![date(3, 31)]
         );
+        // Stops at `until`
+        *template.until_mut() = Some(date(5, 1));
         assert_eq!(
-            "PT12H40M50S",
-            (Duration::hours(12) + Duration::minutes(40) + Duration::seconds(50)).to_string()
+            template.recurrence_dates(from, to),
+            vec![date(2, 28), date(3, 31), date(4, 30)]
         );
+        // A zero period stops after the first
+        *template.recur_mut() = Some(Duration::default());
+        assert_eq!(template.recurrence_dates(date(1, 1), to), vec![date(1, 31)]);
+
+        // Not a template
+        *template.status_mut() = Status::Pending;
+        assert!(template.recurrence_dates(from, to).is_empty());
         assert_eq!(
-            "P1Y2M3DT12H40M50S",
-            (Duration::years(1)
-                + Duration::months(2)
-                + Duration::days(3)
-                + Duration::hours(12)
-                + Duration::minutes(40)
-                + Duration::seconds(50))
-            .to_string()
+            template.recurrence_dates(date(1, 31), to),
+            vec![date(1, 31)]
         );
     }
+    #[test]
+    fn clear_dates() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "start": "20220131T090000Z",
+            "scheduled": "20220201T083000Z",
+            "until": "20220301T083000Z",
+            "wait": "20220201T083000Z",
+            "due": "20220202T083000Z"
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        let before = *task.modified();
+
+        task.clear_due();
+        assert!(task.due().is_none());
+        assert!(task.modified() > &before);
+        // Nothing to clear
+        *task.modified_mut() = before;
+        task.clear_due();
+        assert_eq!(task.modified(), &before);
+
+        task.clear_wait();
+        assert!(task.wait().is_none());
+        assert!(task.modified() > &before);
+
+        *task.modified_mut() = before;
+        task.clear_scheduled();
+        assert!(task.scheduled().is_none());
+        assert!(task.modified() > &before);
+
+        *task.modified_mut() = before;
+        task.clear_until();
+        assert!(task.until().is_none());
+        assert!(task.modified() > &before);
 
+        let mut task = task_str.parse::<Task>().unwrap();
+        task.clear_all_dates();
+        assert!(task.start().is_none());
+        assert!(task.scheduled().is_none());
+        assert!(task.until().is_none());
+        assert!(task.wait().is_none());
+        assert!(task.due().is_none());
+        assert_eq!(task.entry(), &before);
+        assert!(task.modified() > &before);
+    }
     #[test]
-    /// Test parsing a task from a string using different methods
-    fn parse_task() {
+    fn from_str_preserving() {
         let task_str = r#"
         {
-            "id": 0,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
             "description": "Task to do.",
-            "end": "20220131T083000Z",
+            "status": "pending",
             "entry": "20220131T083000Z",
             "modified": "20220131T083000Z",
-            "project": "Daily",
-            "start": "20220131T083000Z",
+            "due": "20220202T083000Z",
+            "estimate": "PT2H",
+            "size": 3,
+            "urgency": 4,
+            "sync": {"remote": "github", "ids": [42, 43], "open": true},
+            "flagged": false
+        }
+        "#;
+        // Not readable as UDAs
+        assert!(task_str.parse::<Task>().is_err());
+
+        let mut task = Task::from_str_preserving(task_str).unwrap();
+        assert_eq!(&task.udas()["estimate"], "PT2H");
+        assert!(!task.udas().contains_key("sync"));
+
+        let source: serde_json::Value = serde_json::from_str(task_str).unwrap();
+        assert_eq!(task.to_source_json(), source);
+        // Numbers as written
+        assert_eq!(task.to_json()["size"].to_string(), "3.0");
+        assert_eq!(task.to_source_json()["size"].to_string(), "3");
+        assert_eq!(task.to_source_json()["urgency"].to_string(), "4");
+        task.udas_mut()
+            .insert("size".to_string(), UdaValue::Numeric(4.5));
+        assert_eq!(task.to_source_json()["size"].to_string(), "4.5");
+
+        // Edits apply over the source
+        *task.description_mut() = "Something else.".to_string();
+        task.clear_due();
+        task.udas_mut().remove("estimate");
+        let json = task.to_source_json();
+        assert_eq!(json["description"], "Something else.");
+        assert!(json.get("due").is_none());
+        assert!(json.get("estimate").is_none());
+        assert_eq!(json["sync"], source["sync"]);
+        assert_eq!(json["flagged"], false);
+        // Only kept in the source
+        assert!(task.to_json().get("sync").is_none());
+
+        // Unaffected by the source
+        let plain = Task::from_str_preserving(&json.to_string()).unwrap();
+        assert_eq!(plain, task);
+        let task = Task::new("Task to do.");
+        assert_eq!(task.to_source_json(), task.to_json());
+    }
+    #[test]
+    fn to_data_json() {
+        let task_str = r#"
+        {
+            "id": 3,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220202T083000Z",
+            "urgency": 4.2
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let mut other = task.clone();
+        *other.urgency_mut() = Some(9.9);
+        *other.id_mut() = None;
+
+        assert_ne!(task.to_json(), other.to_json());
+        assert_eq!(task.to_data_json(), other.to_data_json());
+        let json = task.to_data_json();
+        assert!(json.get("id").is_none());
+        assert!(json.get("urgency").is_none());
+        assert_eq!(json["due"], "20220202T083000Z");
+
+        *other.description_mut() = "Something else.".to_string();
+        assert_ne!(task.to_data_json(), other.to_data_json());
+    }
+    #[test]
+    fn fingerprint() {
+        let task_str = r#"
+        {
+            "id": 3,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
             "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "tags": ["home"],
+            "urgency": 4.2,
+            "estimate": "PT2H"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let mut other = task.clone();
+        *other.urgency_mut() = Some(9.9);
+        *other.id_mut() = None;
+        other.touch();
+        assert_eq!(task.fingerprint(), other.fingerprint());
+
+        *other.description_mut() = "Something else.".to_string();
+        assert_ne!(task.fingerprint(), other.fingerprint());
+        let mut other = task.clone();
+        other
+            .udas_mut()
+            .insert("estimate".to_string(), "PT3H".into());
+        assert_ne!(task.fingerprint(), other.fingerprint());
+
+        // Stable
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+    #[test]
+    fn json_eq() {
+        let task_str = r#"
+        {
             "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
-            "tags": [
-                "WORK"
-            ],
-            "urgency": 9.91234
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "tags": ["home", "garden"],
+            "estimate": "PT2H",
+            "client": "Acme"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert!(task.json_eq(task_str));
+        // Reordered
+        assert!(task.json_eq(
+            r#"{"client":"Acme","estimate":"PT2H","tags":["home","garden"],"status":"pending","modified":"20220131T083000Z","entry":"20220131T083000Z","description":"Task to do.","uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40"}"#
+        ));
+
+        // Tag order matters
+        assert!(!task.json_eq(
+            r#"{"client":"Acme","estimate":"PT2H","tags":["garden","home"],"status":"pending","modified":"20220131T083000Z","entry":"20220131T083000Z","description":"Task to do.","uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40"}"#
+        ));
+        assert!(!task.json_eq(
+            r#"{"client":"Acme","tags":["home","garden"],"status":"pending","modified":"20220131T083000Z","entry":"20220131T083000Z","description":"Task to do.","uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40"}"#
+        ));
+        assert!(!task.json_eq("{"));
+
+        // Numbers by value
+        let task_str = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","status":"pending","entry":"20220131T083000Z","modified":"20220131T083000Z","size":3}"#;
+        let task = task_str.parse::<Task>().unwrap();
+        assert!(task.json_eq(task_str));
+        assert!(!task.json_eq(&task_str.replace("3}", "4}")));
+    }
+    #[test]
+    fn redacted() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Call Dr. Smith about results",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "tags": ["health"],
+            "annotations": [{"entry": "20220131T090000Z", "description": "Ask about dosage"}]
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let redacted = task.redacted();
+
+        assert_eq!(redacted.description(), "[redacted]");
+        assert_eq!(redacted.annotations()[0].description(), "[redacted]");
+        assert_eq!(
+            redacted.annotations()[0].entry(),
+            task.annotations()[0].entry()
+        );
+        assert_eq!(redacted.uuid(), task.uuid());
+        assert_eq!(redacted.status(), task.status());
+        assert_eq!(redacted.entry(), task.entry());
+        assert_eq!(redacted.tags(), task.tags());
+        assert!(!redacted.to_json_string().contains("Smith"));
+        // The original is unchanged
+        assert_eq!(task.description(), "Call Dr. Smith about results");
+    }
+    #[test]
+    fn sanitize_for_import() {
+        let task_str = r#"
+        {
+            "id": 3,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "urgency": 4.2
+        }
+        "#;
+        let mut task = task_str.parse::<Task>().unwrap();
+        assert!(task.to_json().get("id").is_some());
+
+        task.sanitize_for_import();
+        let json = task.to_json();
+        assert!(json.get("id").is_none());
+        assert!(json.get("urgency").is_none());
+        assert_eq!(json["modified"], "20220131T083000Z");
+        assert_eq!(json["uuid"], "d67fce70-c0b6-43c5-affc-a21e64567d40");
+    }
+    #[test]
+    fn import_changes() {
+        let task_str = r#"
+        {
+            "id": 1,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "project": "Home",
+            "urgency": 1.0
+        }
+        "#;
+        let existing = task_str.parse::<Task>().unwrap();
+        let mut task = existing.clone();
+        assert!(task.import_changes(&existing).is_empty());
+
+        *task.project_mut() = "Work".to_string();
+        *task.id_mut() = Some(2);
+        *task.urgency_mut() = Some(2.0);
+        let changes = task.import_changes(&existing);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes["project"], "Work");
+
+        // Removed fields are cleared, as leaving them out wouldn't remove them
+        task.project_mut().clear();
+        task.add_tag("work").unwrap();
+        let changes = task.import_changes(&existing);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes["project"], "");
+        assert_eq!(changes["tags"], serde_json::json!(["work"]));
+
+        let changes = existing.import_changes(&task);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes["project"], "Home");
+        assert_eq!(changes["tags"], serde_json::json!([]));
+    }
+    #[test]
+    fn try_build() {
+        let dt = |day| {
+            DateTime::<Utc>::from_naive_utc_and_offset(
+                chrono::NaiveDate::from_ymd_opt(2022, 1, day)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap(),
+                Utc,
+            )
+        };
+        let builder = || {
+            TaskBuilder::new()
+                .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
+                .description("Task to do.")
+                .status(Status::Pending)
+                .entry(dt(10))
+                .modified(dt(10))
+        };
+
+        // Valid
+        let task = builder().wait(dt(12)).due(dt(15)).try_build().unwrap();
+        assert_eq!(task.due(), Some(&dt(15)));
+        let task = builder()
+            .status(Status::Completed)
+            .end(dt(11))
+            .try_build()
+            .unwrap();
+        assert_eq!(task.end(), Some(&dt(11)));
+
+        // Inconsistent
+        assert_eq!(
+            builder().end(dt(11)).try_build(),
+            Err(BuildError::Inconsistent(vec![
+                "end is set on a pending task".to_string()
+            ]))
+        );
+        assert_eq!(
+            builder().due(dt(5)).try_build(),
+            Err(BuildError::Inconsistent(vec![
+                "due is before entry".to_string()
+            ]))
+        );
+        assert_eq!(
+            builder().wait(dt(20)).due(dt(15)).try_build(),
+            Err(BuildError::Inconsistent(vec![
+                "wait is after due".to_string()
+            ]))
+        );
+        match builder().end(dt(11)).due(dt(5)).try_build() {
+            Err(BuildError::Inconsistent(problems)) => assert_eq!(problems.len(), 2),
+            other => panic!("expected inconsistent, got {other:?}"),
         }
-        "#;
-        let task = task_str.parse::<Task>().unwrap();
-        assert_eq!(task.id, Some(0));
-
-        let task = Task::from(task_str);
-        assert_eq!(task.id, Some(0));
 
-        let task = Task::from(task_str.to_string());
-        assert_eq!(task.id, Some(0));
+        // Tags are normalized, and invalid tags rejected
+        let task = builder()
+            .tag("+home".to_string())
+            .tags(vec!["home", " work "])
+            .try_build()
+            .unwrap();
+        assert_eq!(task.tags(), &["home", "work"]);
+        assert_eq!(
+            builder().tag("two words".to_string()).try_build(),
+            Err(BuildError::Inconsistent(vec![
+                "tag \"two words\" is invalid".to_string()
+            ]))
+        );
 
-        let task: Task = task_str.into();
-        assert_eq!(task.id, Some(0));
+        // Priority is the priority UDA
+        let task = builder().priority("H".to_string()).try_build().unwrap();
+        assert_eq!(task.priority(), Some(Priority::High));
+        assert_eq!(
+            builder().priority("M".to_string()).build().priority(),
+            Some(Priority::Medium)
+        );
+        assert_eq!(
+            builder().priority("urgent".to_string()).try_build(),
+            Err(BuildError::Inconsistent(vec![
+                "priority \"urgent\" is invalid".to_string()
+            ]))
+        );
 
-        let task: Task = task_str.to_string().into();
-        assert_eq!(task.id, Some(0));
+        // Missing
+        assert_eq!(
+            TaskBuilder::new().try_build(),
+            Err(BuildError::Missing("uuid"))
+        );
     }
     #[test]
-    fn convert_durations() {
-        use std::time;
+    fn builder_done_deleted() {
+        use chrono::TimeZone;
 
-        let duration = time::Duration::from_secs(50);
-        let tasklib_duration: Duration = duration.into();
-        assert_eq!(tasklib_duration, Duration::seconds(50));
+        let at = Utc.with_ymd_and_hms(2022, 1, 31, 8, 30, 0).unwrap();
+        let builder = || {
+            TaskBuilder::new()
+                .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
+                .description("Task to do.")
+                .status(Status::Pending)
+                .entry(at)
+                .modified(at)
+        };
+
+        let task = builder().done(at).try_build().unwrap();
+        assert_eq!(task.status(), &Status::Completed);
+        assert_eq!(task.end(), Some(&at));
+
+        let task = builder().deleted(at).try_build().unwrap();
+        assert_eq!(task.status(), &Status::Deleted);
+        assert_eq!(task.end(), Some(&at));
+    }
+    #[test]
+    fn builder_annotate() {
+        use chrono::TimeZone;
 
-        let duration = time::Duration::from_secs((40 * 60) + 50);
-        let tasklib_duration: Duration = duration.into();
-        // assert_eq!(tasklib_duration.to_string(), "PT2450S");
-        assert_eq!(tasklib_duration.to_string(), "PT40M50S"); // smoothing was added for addition
+        let at = Utc.with_ymd_and_hms(2022, 1, 31, 8, 30, 0).unwrap();
+        let before = now_to_seconds();
+        let task = TaskBuilder::new()
+            .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
+            .description("Call Bob")
+            .status(Status::Pending)
+            .entry(at)
+            .modified(at)
+            .annotation(Annotation::new(at, "Left a message"))
+            .annotate("Called back")
+            .annotate(String::from("Sent the contract"))
+            .try_build()
+            .unwrap();
+
+        let annotations = task.annotations();
+        assert_eq!(annotations.len(), 3);
+        assert_eq!(annotations[0].entry(), &at);
+        assert_eq!(annotations[1].description(), "Called back");
+        assert_eq!(annotations[2].description(), "Sent the contract");
+        assert!(annotations[1].entry() >= &before);
+        assert_eq!(annotations[1].entry().timestamp_subsec_nanos(), 0);
+    }
+    #[test]
+    fn builder_validate_udas() {
+        let defs = [
+            Uda::String {
+                name: "size".to_string(),
+                value: String::new(),
+                label: "Size".to_string(),
+                default: String::new(),
+                values: vec![
+                    "small".to_string(),
+                    "medium".to_string(),
+                    "large".to_string(),
+                ],
+                coefficient: None,
+            },
+            Uda::Numeric {
+                name: "points".to_string(),
+                value: 0.0,
+                label: "Points".to_string(),
+                default: 0.0,
+                coefficient: None,
+            },
+            Uda::Duration {
+                name: "estimate".to_string(),
+                value: Duration::default(),
+                label: "Estimate".to_string(),
+                default: None,
+                coefficient: None,
+            },
+        ];
+        let builder = |size: &str| {
+            TaskBuilder::new()
+                .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
+                .description("Task to do.")
+                .status(Status::Pending)
+                .entry(now_to_seconds())
+                .modified(now_to_seconds())
+                .uda("size".to_string(), UdaValue::String(size.to_string()))
+                .uda("points".to_string(), UdaValue::Numeric(3.0))
+                .uda("estimate".to_string(), UdaValue::String("PT2H".to_string()))
+                .uda("orphan".to_string(), UdaValue::Numeric(1.0))
+        };
+
+        let task = builder("medium")
+            .validate_udas(&defs)
+            .unwrap()
+            .try_build()
+            .unwrap();
+        assert_eq!(&task.udas()["size"], "medium");
+
+        let errors = builder("huge").validate_udas(&defs).unwrap_err();
         assert_eq!(
-            tasklib_duration,
-            Duration::minutes(40) + Duration::seconds(50)
+            errors,
+            vec![UdaValidationError::NotAllowed {
+                name: "size".to_string(),
+                value: "huge".to_string(),
+                allowed: vec![
+                    "small".to_string(),
+                    "medium".to_string(),
+                    "large".to_string()
+                ],
+            }]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "UDA size is \"huge\", not one of small, medium, large"
         );
 
-        let chrono_duration = chrono::Duration::seconds(50);
-        let tasklib_duration: Duration = chrono_duration.into();
-        assert_eq!(tasklib_duration, Duration::seconds(50));
-
-        let duration = time::Duration::from_secs(50);
-        let tasklib_duration = Duration::from(duration);
-        assert_eq!(tasklib_duration, Duration::seconds(50));
-
-        let chrono_duration = chrono::Duration::seconds(50);
-        let tasklib_duration = Duration::from(chrono_duration);
-        assert_eq!(tasklib_duration, Duration::seconds(50));
+        let errors = builder("small")
+            .uda("points".to_string(), UdaValue::String("many".to_string()))
+            .validate_udas(&defs)
+            .unwrap_err();
+        assert_eq!(
+            errors[0].to_string(),
+            "UDA points should be Numeric, not String"
+        );
     }
     #[test]
-    fn test_udas() {
-        use chrono::TimeZone;
-
-        // Uses elapsed, a duration type UDA
+    fn tags_as_string() {
         let task_str = r#"
         {
-            "id": 0,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
             "description": "Task to do.",
-            "end": "20220131T083000Z",
+            "status": "pending",
             "entry": "20220131T083000Z",
             "modified": "20220131T083000Z",
-            "elapsed": "PT2H",
-            "project": "Daily",
-            "start": "20220131T083000Z",
-            "status": "pending",
-            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
-            "tags": [
-                "WORK"
-            ],
-            "urgency": 9.91234
+            "tags": TAGS
         }
         "#;
-        let task = task_str.parse::<Task>().unwrap();
-        assert_eq!(task.udas().get("elapsed").unwrap(), "PT2H");
-
-        // Check adding and retreiving udas
-        let mut task = task_str.parse::<Task>().unwrap();
-        task.udas_mut()
-            .insert("elapsed".to_string(), Duration::hours(5).into());
-        assert_eq!(task.udas().get("elapsed").unwrap().to_string(), "PT5H");
-        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"PT5H"}"#.to_string());
-
-        // Check string type
-        task.udas_mut().insert("elapsed".to_string(), "5".into());
-        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"5"}"#.to_string());
-        assert_eq!(serde_json::to_string(&task).unwrap(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"5"}"#.to_string());
-
-        // Check date type
-        task.udas_mut().insert(
-            "elapsed".to_string(),
-            UdaValue::Date(Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap()),
-        );
-        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"20200101T000000Z"}"#.to_string());
-        assert_eq!(serde_json::to_string(&task).unwrap(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"20200101T000000Z"}"#.to_string());
-
-        // Check duration type
-        task.udas_mut().insert(
-            "elapsed".to_string(),
-            UdaValue::Duration(Duration::hours(5)),
+        let tags = |tags: &str| {
+            task_str
+                .replace("TAGS", tags)
+                .parse::<Task>()
+                .unwrap()
+                .tags()
+                .to_vec()
+        };
+        assert_eq!(tags(r#"["home", "garden"]"#), ["home", "garden"]);
+        assert_eq!(tags(r#""home,garden""#), ["home", "garden"]);
+        assert_eq!(tags(r#"" home , garden,""#), ["home", "garden"]);
+        assert_eq!(tags(r#""work""#), ["work"]);
+        assert!(tags(r#""""#).is_empty());
+        assert!(task_str.replace("TAGS", "3").parse::<Task>().is_err());
+
+        // Serialized as an array
+        let task = task_str
+            .replace("TAGS", r#""home,garden""#)
+            .parse::<Task>()
+            .unwrap();
+        assert_eq!(
+            task.to_json()["tags"],
+            serde_json::json!(["home", "garden"])
         );
-        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"PT5H"}"#.to_string());
-        assert_eq!(serde_json::to_string(&task).unwrap(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":"PT5H"}"#.to_string());
-
-        // Check numeric type
-        task.udas_mut()
-            .insert("elapsed".to_string(), UdaValue::Numeric(5.0));
-        assert_eq!(task.to_string(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":5.0}"#.to_string());
-        assert_eq!(serde_json::to_string(&task).unwrap(), r#"{"id":0,"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","start":"20220131T083000Z","end":"20220131T083000Z","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","status":"pending","tags":["WORK"],"urgency":9.91234,"elapsed":5.0}"#.to_string());
     }
-
     #[test]
-    /// Test the different types of UDA values
-    fn test_udas_types() {
-        // String
+    fn depends_as_string() {
         let task_str = r#"
         {
-            "id": 0,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
             "description": "Task to do.",
-            "end": "20220131T083000Z",
+            "status": "pending",
             "entry": "20220131T083000Z",
             "modified": "20220131T083000Z",
-            "elapsed": "PT2H",
-            "project": "Daily",
-            "start": "20220131T083000Z",
-            "status": "pending",
-            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
-            "tags": [
-                "WORK"
-            ],
-            "urgency": 9.91234
+            "depends": DEPENDS
         }
         "#;
-        let task = task_str.parse::<Task>().unwrap();
-        assert_eq!(task.udas().get("elapsed").unwrap(), "PT2H");
+        let array = task_str
+            .replace(
+                "DEPENDS",
+                r#"["8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b", "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f"]"#,
+            )
+            .parse::<Task>()
+            .unwrap();
+        let string = task_str
+            .replace(
+                "DEPENDS",
+                r#""8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b,0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f""#,
+            )
+            .parse::<Task>()
+            .unwrap();
+        assert_eq!(array.depends().len(), 2);
+        assert_eq!(array.depends(), string.depends());
+        // Serialized as an array
+        assert_eq!(
+            string.to_json()["depends"],
+            serde_json::json!([
+                "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b",
+                "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f"
+            ])
+        );
 
-        // Number (integer)
+        let empty = task_str
+            .replace("DEPENDS", r#""""#)
+            .parse::<Task>()
+            .unwrap();
+        assert!(empty.depends().is_empty());
+        assert!(task_str
+            .replace("DEPENDS", r#""not-a-uuid""#)
+            .parse::<Task>()
+            .is_err());
+    }
+    #[test]
+    fn cleared_depends() {
         let task_str = r#"
         {
-            "id": 0,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
             "description": "Task to do.",
-            "end": "20220131T083000Z",
+            "status": "pending",
             "entry": "20220131T083000Z",
             "modified": "20220131T083000Z",
-            "elapsed": 2,
-            "project": "Daily",
-            "start": "20220131T083000Z",
-            "status": "pending",
-            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
-            "tags": [
-                "WORK"
-            ],
-            "urgency": 9.91234
+            "depends": ["8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b"]
         }
         "#;
-        let task = task_str.parse::<Task>().unwrap();
-        assert_eq!(task.udas().get("elapsed").unwrap(), &2);
+        let mut task = task_str.parse::<Task>().unwrap();
+        task.depends_mut().clear();
+        // Emitted, so importing clears the existing dependencies
+        assert_eq!(task.to_json()["depends"], serde_json::json!([]));
+        // Equal to a task that never had dependencies
+        let mut never = task.clone();
+        never.depends = Depends::default();
+        assert_eq!(task, never);
+        assert!(never.to_json().get("depends").is_none());
 
-        // Number (float)
+        let mut task = task_str.parse::<Task>().unwrap();
+        let uuid = task.depends()[0];
+        assert!(task.remove_dependency(&uuid));
+        assert_eq!(task.to_json()["depends"], serde_json::json!([]));
+
+        // Never had dependencies
+        let mut task = task_str.parse::<Task>().unwrap();
+        task.depends = Depends::default();
+        task.depends_mut().clear();
+        assert!(task.to_json().get("depends").is_none());
+    }
+    #[test]
+    fn into_builder() {
         let task_str = r#"
         {
-            "id": 0,
+            "id": 3,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
             "description": "Task to do.",
-            "end": "20220131T083000Z",
+            "status": "pending",
             "entry": "20220131T083000Z",
-            "modified": "20220131T083000Z",
-            "elapsed": 2.5,
+            "modified": "20220201T083000Z",
+            "start": "20220201T083000Z",
+            "due": "20220301T083000Z",
+            "wait": "20220215T083000Z",
+            "scheduled": "20220210T083000Z",
+            "until": "20220401T083000Z",
+            "recur": "weekly",
+            "mask": "-",
+            "imask": 1,
+            "parent": "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f",
+            "depends": ["8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b"],
             "project": "Daily",
-            "start": "20220131T083000Z",
-            "status": "pending",
-            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
-            "tags": [
-                "WORK"
-            ],
-            "urgency": 9.91234
+            "tags": ["work"],
+            "urgency": 9.9,
+            "annotations": [{"entry": "20220131T083000Z", "description": "A note"}],
+            "estimate": "PT2H"
         }
         "#;
         let task = task_str.parse::<Task>().unwrap();
-        assert_eq!(task.udas().get("elapsed").unwrap(), &2.5);
+        assert_eq!(task.clone().into_builder().build(), task);
+        assert_eq!(TaskBuilder::from_task(&task).build(), task);
+        assert_eq!(
+            task.clone().into_builder().build().to_json(),
+            task.to_json()
+        );
     }
-
     #[test]
-    fn builder() {
-        use chrono::ParseError;
-
-        /// e.g."20220131T083000Z" -> DateTime<Utc>
-        fn tw_str_to_dt(s: &str) -> Result<DateTime<Utc>, ParseError> {
-            NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ").map(|dt| dt.and_utc())
-        }
+    fn from_map() {
+        let map: HashMap<String, String> = [
+            ("uuid", "d67fce70-c0b6-43c5-affc-a21e64567d40"),
+            ("description", "Task to do."),
+            ("status", "pending"),
+            ("entry", "20220131T083000Z"),
+            ("modified", "2022-01-31T08:30:00Z"),
+            ("due", "2022-02-01"),
+            ("tags", "+work  urgent"),
+            ("depends", "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b"),
+            ("estimate", "PT2H"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let task = Task::from_map(map.clone()).unwrap();
+        assert_eq!(task.description(), "Task to do.");
+        assert_eq!(task.modified(), task.entry());
+        assert_eq!(
+            task.due().unwrap().format(DATETIME_FORMAT).to_string(),
+            "20220201T000000Z"
+        );
+        assert_eq!(task.tags(), &["work", "urgent"]);
+        assert_eq!(task.depends().len(), 1);
+        assert_eq!(task.udas().get("estimate").unwrap(), "PT2H");
+
+        let mut missing = map.clone();
+        missing.remove("status");
+        assert!(matches!(
+            Task::from_map(missing),
+            Err(Error::Build(BuildError::Missing("status")))
+        ));
+        let mut invalid = map.clone();
+        invalid.insert("due".to_string(), "tomorrow".to_string());
+        assert!(Task::from_map(invalid).is_err());
+        let mut invalid = map;
+        invalid.insert("tags".to_string(), "work -bad".to_string());
+        assert!(matches!(Task::from_map(invalid), Err(Error::Validation(_))));
+    }
+    #[test]
+    fn sub_second_annotations() {
+        let now = Utc::now().with_nanosecond(123_456_789).unwrap();
+        let annotation = Annotation::new(now, "Called the client");
+        assert_eq!(annotation.entry().nanosecond(), 0);
+        let json = serde_json::to_string(&annotation).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Annotation>(&json).unwrap(),
+            annotation
+        );
 
         let task = TaskBuilder::new()
+            .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
             .description("Task to do.")
-            .end(tw_str_to_dt("20220131T083000Z").unwrap().into())
-            .entry(tw_str_to_dt("20220131T083000Z").unwrap().into())
-            .modified(tw_str_to_dt("20220131T083000Z").unwrap().into())
-            .project("Daily")
-            .start(tw_str_to_dt("20220131T083000Z").unwrap().into())
             .status(Status::Pending)
+            .entry(now)
+            .modified(now)
+            .due(now)
+            .annotations(vec![annotation])
+            .build();
+        assert_eq!(task.to_string().parse::<Task>().unwrap(), task);
+    }
+    #[test]
+    fn round_trip_fresh_task() {
+        let now = Utc::now().with_nanosecond(123_456_789).unwrap();
+        let mut task = TaskBuilder::new()
             .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
-            .tags(vec!["WORK"])
-            .urgency(9.91234)
-            .parent("d67fce70-c0b6-43c5-affc-a21e64567d40")
+            .description("Task to do.")
+            .status(Status::Pending)
+            .modified(now)
             .build();
-        assert_eq!(task.id(), &None);
+        assert_eq!(task.to_string().parse::<Task>().unwrap(), task);
+        task.touch();
+        assert_eq!(task.to_string().parse::<Task>().unwrap(), task);
+
+        // Set directly
+        *task.due_mut() = Some(now);
+        task.annotations_mut().push(Annotation {
+            entry: now,
+            description: "A note".to_string(),
+        });
+        assert_ne!(task.to_string().parse::<Task>().unwrap(), task);
+        task.normalize();
+        assert_eq!(task.due().unwrap().nanosecond(), 0);
+        assert_eq!(task.to_string().parse::<Task>().unwrap(), task);
     }
-
     #[test]
-    fn deserialize_task() {
-        // Task should not include null or empty fields when deserialized to JSON
+    fn urls() {
         let task_str = r#"
         {
             "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
-            "description": "Task to do.",
+            "description": "Read https://taskwarrior.org/docs/ and reply",
             "status": "pending",
             "entry": "20220131T083000Z",
-            "modified": "20220131T083000Z"
+            "modified": "20220131T083000Z",
+            "annotations": [
+                {"entry": "20220131T083000Z", "description": "See (http://example.com/a?b=c)."},
+                {"entry": "20220131T083000Z", "description": "Again: https://taskwarrior.org/docs/"},
+                {"entry": "20220131T083000Z", "description": "No links, just http:// here"},
+                {"entry": "20220131T083000Z", "description": "(https://en.wikipedia.org/wiki/Rust_(programming_language))."},
+                {"entry": "20220131T083000Z", "description": "https://en.wikipedia.org/wiki/Fork_(software_development)"}
+            ]
         }
         "#;
         let task = task_str.parse::<Task>().unwrap();
-        let task_json = serde_json::to_string(&task).unwrap();
-        let expected_task_json = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","entry":"20220131T083000Z","modified":"20220131T083000Z","status":"pending"}"#;
-        assert_eq!(task_json, expected_task_json);
+        assert_eq!(
+            task.urls(),
+            vec![
+                "https://taskwarrior.org/docs/".to_string(),
+                "http://example.com/a?b=c".to_string(),
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)".to_string(),
+                "https://en.wikipedia.org/wiki/Fork_(software_development)".to_string(),
+            ]
+        );
     }
     #[test]
-    fn uda_value_converters() {
-        let uda_value = UdaValue::String("5.0".to_string());
-        uda_value
-            .as_uda_string()
-            .expect("uda value string to string conversion");
-        uda_value
-            .as_uda_numeric()
-            .expect("uda value string to numeric conversion");
+    fn pretty_json() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "tags": ["home"]
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+        let pretty = task.to_json_string_pretty();
+        assert!(pretty.contains("\n  \"uuid\": \"d67fce70-c0b6-43c5-affc-a21e64567d40\",\n"));
+        assert!(pretty.contains("\n    \"home\"\n"));
+        assert!(!task.to_json_string().contains('\n'));
+        assert_eq!(pretty.parse::<Task>().unwrap(), task);
+        assert_eq!(
+            pretty.parse::<Task>().unwrap().to_json_string(),
+            task.to_json_string()
+        );
 
-        let uda_value = UdaValue::String("20220131T083000Z".to_string());
-        uda_value
-            .as_uda_date()
-            .expect("uda value string to date conversion");
+        let mut buffer: Vec<u8> = Vec::new();
+        task.write_pretty(&mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), pretty);
+    }
+    #[test]
+    fn write_json_to_buffer() {
+        let now = DateTime::<Utc>::from_naive_utc_and_offset(
+            NaiveDateTime::parse_from_str("20220131T083000Z", DATETIME_FORMAT).unwrap(),
+            Utc,
+        );
+        let tasks: Vec<Task> = (0..100)
+            .map(|i| {
+                TaskBuilder::new()
+                    .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
+                    .description(format!("Task \"{i}\" ✓"))
+                    .status(Status::Pending)
+                    .entry(now)
+                    .modified(now)
+                    .project("Daily")
+                    .build()
+            })
+            .collect();
+
+        let mut string = String::new();
+        let mut bytes = Vec::new();
+        for task in &tasks {
+            string.clear();
+            bytes.clear();
+            task.write_json_to(&mut string).unwrap();
+            task.write_json_bytes(&mut bytes).unwrap();
+            assert_eq!(string, task.to_json_string());
+            assert_eq!(bytes, task.to_json_string().into_bytes());
+        }
 
-        let uda_value = UdaValue::String("PT2H".to_string());
-        uda_value
-            .as_uda_duration()
-            .expect("uda value string to duration conversion");
+        // Appends, rather than overwriting
+        let mut string = String::from("[");
+        tasks[0].write_json_to(&mut string).unwrap();
+        string.push(',');
+        tasks[1].write_json_to(&mut string).unwrap();
+        string.push(']');
+        let parsed: Vec<Task> = serde_json::from_str(&string).unwrap();
+        assert_eq!(parsed, &tasks[..2]);
     }
 }
 
 pub mod prelude {
     pub use crate::cli::CliArguments;
-    pub use crate::duration::Duration;
-    pub use crate::udas::UdaValue;
+    pub use crate::duration::{
+        Duration, DurationComponents, DurationParseError, DurationUnit, DurationUnits,
+    };
+    #[cfg(feature = "exec")]
+    pub use crate::exec::{ExecError, TaskCommand};
+    pub use crate::ff4::{parse_ff4_line, read_ff4_file};
+    pub use crate::fieldmask::FieldMask;
+    pub use crate::hooks::{on_add, on_modify, run_on_add, run_on_modify};
+    #[cfg(feature = "taskchampion-db")]
+    pub use crate::taskchampion::{read_all_from_sqlite, DbError};
+    pub use crate::taskrc::Taskrc;
+    pub use crate::taskref::{AnnotationRef, TaskRef};
+    pub use crate::tasks::{
+        completed_between, due_between, entered_between, group_by, group_by_multi,
+        group_by_project, prune_dangling_depends, rename_project_in_all, tasks_blocking, touch_all,
+        touch_where, TaskIteratorExt, TaskSliceExt,
+    };
+    pub use crate::taskset::{Change, TaskSet};
+    pub use crate::udas::{Uda, UdaConversionError, UdaValidationError, UdaValue};
+    pub use crate::writer::{ImportBatch, TaskArrayWriter};
+    pub use crate::BuildError;
+    pub use crate::DueStatus;
+    pub use crate::Priority;
+    pub use crate::StateError;
     pub use crate::Task;
     pub use crate::TaskBuilder;
 }
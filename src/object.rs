@@ -0,0 +1,193 @@
+//! Serialize straight to a JSON map, without wrapping it in a `serde_json::Value`.
+use serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeStruct, Serializer};
+use serde_json::{Map, Value};
+
+/// Serialize `value`, which must serialize as a map or struct, to a JSON map.
+///
+/// Fields are serialized as `serde_json::to_value` would, so the map equals the object it gives.
+pub(crate) fn to_object<T: Serialize + ?Sized>(value: &T) -> Result<Map<String, Value>, Error> {
+    value.serialize(ObjectSerializer)
+}
+
+type Error = serde_json::Error;
+
+/// Serializes maps and structs to a JSON map, and rejects anything else.
+struct ObjectSerializer;
+
+/// Collects the entries of a map or the fields of a struct.
+#[derive(Default)]
+struct Entries {
+    map: Map<String, Value>,
+    key: Option<String>,
+}
+
+fn not_an_object<T>() -> Result<T, Error> {
+    Err(ser::Error::custom("expected a map or struct"))
+}
+
+/// Reject a value that isn't a map or struct.
+macro_rules! reject {
+    ($($method:ident($($ty:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<Self::Ok, Error> {
+                not_an_object()
+            }
+        )*
+    };
+}
+
+impl Serializer for ObjectSerializer {
+    type Ok = Map<String, Value>;
+    type Error = Error;
+    type SerializeSeq = Impossible<Self::Ok, Error>;
+    type SerializeTuple = Impossible<Self::Ok, Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Error>;
+    type SerializeMap = Entries;
+    type SerializeStruct = Entries;
+    type SerializeStructVariant = Impossible<Self::Ok, Error>;
+
+    reject! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Error> {
+        not_an_object()
+    }
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        not_an_object()
+    }
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Error> {
+        not_an_object()
+    }
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        not_an_object()
+    }
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        not_an_object()
+    }
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(Entries::default())
+    }
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Error> {
+        Ok(Entries::default())
+    }
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        not_an_object()
+    }
+}
+
+impl SerializeMap for Entries {
+    type Ok = Map<String, Value>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        // As serde_json, numbers and booleans are written as strings
+        self.key = Some(match serde_json::to_value(key)? {
+            Value::String(key) => key,
+            key @ (Value::Number(_) | Value::Bool(_)) => key.to_string(),
+            _ => return Err(ser::Error::custom("key must be a string")),
+        });
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ser::Error::custom("value without a key"))?;
+        self.map.insert(key, serde_json::to_value(value)?);
+        Ok(())
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(self.map)
+    }
+}
+
+impl SerializeStruct for Entries {
+    type Ok = Map<String, Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.map
+            .insert(key.to_string(), serde_json::to_value(value)?);
+        Ok(())
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(self.map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn maps_only() {
+        let map: BTreeMap<&str, u32> = [("a", 1), ("b", 2)].into_iter().collect();
+        assert_eq!(
+            Value::Object(to_object(&map).unwrap()),
+            serde_json::to_value(&map).unwrap()
+        );
+        assert!(to_object(&1).is_err());
+        assert!(to_object(&vec![1]).is_err());
+        let numbered = BTreeMap::from([(1, "one")]);
+        assert_eq!(
+            Value::Object(to_object(&numbered).unwrap()),
+            serde_json::to_value(&numbered).unwrap()
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Read tasks from a TaskChampion database, `taskchampion.sqlite3`.
+//!
+//! Taskwarrior 3.x stores its tasks in a SQLite database managed by TaskChampion. Each task is a
+//! row in the `tasks` table, keyed by UUID, with its attributes stored as a JSON object of string
+//! keys to string values.
+//!
+//! * Dates are stored as Unix timestamps, e.g. `"1643617800"`
+//! * Tags are stored as `tag_<name>` keys
+//! * Annotations are stored as `annotation_<timestamp>` keys, with the description as the value
+//! * Dependencies are stored as `dep_<uuid>` keys
+//!
+//! Everything else is treated as a UDA.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::ff4::task_from_attributes;
+use crate::Task;
+
+#[derive(Debug)]
+pub enum DbError {
+    /// Error reading from the database.
+    Sqlite(rusqlite::Error),
+    /// Error reading the stored attributes.
+    Json(serde_json::Error),
+    /// Error turning the stored attributes into a task.
+    Parse(String),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Sqlite(e) => write!(f, "sqlite error: {e}"),
+            DbError::Json(e) => write!(f, "invalid task data: {e}"),
+            DbError::Parse(s) => write!(f, "invalid task attribute: {s}"),
+        }
+    }
+}
+
+impl Error for DbError {}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(e: rusqlite::Error) -> Self {
+        DbError::Sqlite(e)
+    }
+}
+
+impl From<serde_json::Error> for DbError {
+    fn from(e: serde_json::Error) -> Self {
+        DbError::Json(e)
+    }
+}
+
+/// Read every task from a TaskChampion database.
+///
+/// The database is opened read-only.
+pub fn read_all_from_sqlite(path: &Path) -> Result<Vec<Task>, DbError> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut statement = connection.prepare("SELECT uuid, data FROM tasks")?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut tasks = Vec::new();
+    for row in rows {
+        let (uuid, data) = row?;
+        let attributes: HashMap<String, String> = serde_json::from_str(&data)?;
+        tasks.push(task_from_attributes(Some(&uuid), attributes).map_err(DbError::Parse)?);
+    }
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Status, DATETIME_FORMAT};
+    use std::fs;
+
+    #[test]
+    fn read_fixture() {
+        let path = std::env::temp_dir().join(format!(
+            "tasklib-taskchampion-{}.sqlite3",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        {
+            let connection = Connection::open(&path).unwrap();
+            connection
+                .execute(
+                    "CREATE TABLE tasks (uuid STRING PRIMARY KEY, data STRING)",
+                    [],
+                )
+                .unwrap();
+            connection
+                .execute(
+                    "INSERT INTO tasks (uuid, data) VALUES (?1, ?2)",
+                    [
+                        "d67fce70-c0b6-43c5-affc-a21e64567d40",
+                        r#"{
+                            "description": "Task to do.",
+                            "status": "pending",
+                            "entry": "1643617800",
+                            "modified": "1643617800",
+                            "due": "1643704200",
+                            "project": "Daily",
+                            "tag_WORK": "",
+                            "annotation_1643617800": "A note",
+                            "dep_8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b": "",
+                            "estimate": "PT2H"
+                        }"#,
+                    ],
+                )
+                .unwrap();
+        }
+
+        let tasks = read_all_from_sqlite(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        let task = &tasks[0];
+        assert_eq!(
+            task.uuid().to_string(),
+            "d67fce70-c0b6-43c5-affc-a21e64567d40"
+        );
+        assert_eq!(task.description(), "Task to do.");
+        assert_eq!(task.status(), &Status::Pending);
+        assert_eq!(
+            task.entry().format(DATETIME_FORMAT).to_string(),
+            "20220131T083000Z"
+        );
+        assert_eq!(
+            task.due().unwrap().format(DATETIME_FORMAT).to_string(),
+            "20220201T083000Z"
+        );
+        assert_eq!(task.project(), "Daily");
+        assert_eq!(task.tags(), &["WORK".to_string()]);
+        assert_eq!(task.annotations().len(), 1);
+        assert_eq!(
+            task.depends()[0].to_string(),
+            "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b"
+        );
+        assert_eq!(task.udas().get("estimate").unwrap(), "PT2H");
+    }
+    #[test]
+    fn missing_database() {
+        let path = std::env::temp_dir().join("tasklib-does-not-exist.sqlite3");
+        assert!(matches!(
+            read_all_from_sqlite(&path),
+            Err(DbError::Sqlite(_))
+        ));
+    }
+}
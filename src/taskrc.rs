@@ -0,0 +1,123 @@
+//! Read Taskwarrior's configuration, `.taskrc`.
+//!
+//! ```rust
+//! use tasklib::prelude::*;
+//!
+//! let taskrc = Taskrc::parse("# Estimates\nuda.estimate.type=duration\ninclude holidays.en-US.rc\n")
+//!     .expect("parse taskrc");
+//! assert_eq!(taskrc.get("uda.estimate.type"), Some("duration"));
+//! assert_eq!(taskrc.includes(), &["holidays.en-US.rc"]);
+//! ```
+use std::collections::BTreeMap;
+
+use crate::Error;
+
+/// Settings from a `.taskrc`, as `name=value` lines.
+///
+/// `include` lines are recorded, but the files aren't read. Callers may read and parse them in
+/// turn, e.g. relative to the `.taskrc`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Taskrc {
+    settings: BTreeMap<String, String>,
+    /// Paths of `include` lines, as written, in order.
+    includes: Vec<String>,
+}
+
+/// Parsing
+impl Taskrc {
+    /// Parse the contents of a `.taskrc`.
+    ///
+    /// * Blank lines are skipped
+    /// * Comments, from `#` to the end of the line, are removed
+    /// * `include <path>` lines are recorded in `includes`
+    /// * Later settings override earlier ones
+    ///
+    /// Any other line without a `=` is an error.
+    pub fn parse(s: &str) -> Result<Taskrc, Error> {
+        let mut taskrc = Taskrc::default();
+        for (n, line) in s.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(path) = line.strip_prefix("include") {
+                if path.starts_with(char::is_whitespace) {
+                    taskrc.includes.push(path.trim().to_string());
+                    continue;
+                }
+            }
+            let (name, value) = line.split_once('=').ok_or_else(|| {
+                Error::Parse(format!("line {}: expected name=value: {line:?}", n + 1))
+            })?;
+            taskrc
+                .settings
+                .insert(name.trim().to_string(), value.trim().to_string());
+        }
+        Ok(taskrc)
+    }
+}
+
+/// Getters (Immutable)
+impl Taskrc {
+    /// The value of a setting, e.g. `uda.estimate.type`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.settings.get(name).map(String::as_str)
+    }
+    pub fn settings(&self) -> &BTreeMap<String, String> {
+        &self.settings
+    }
+    pub fn includes(&self) -> &[String] {
+        &self.includes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let s = "
+# Taskwarrior configuration
+
+data.location=~/.task   # Where tasks live
+include /usr/share/taskwarrior/dark-256.theme
+
+  # UDAs
+uda.estimate.type = duration
+uda.estimate.label=Estimate
+
+include holidays.en-US.rc
+uda.estimate.label=Est.
+";
+        let taskrc = Taskrc::parse(s).unwrap();
+        assert_eq!(taskrc.get("data.location"), Some("~/.task"));
+        assert_eq!(taskrc.get("uda.estimate.type"), Some("duration"));
+        // Later settings override earlier ones
+        assert_eq!(taskrc.get("uda.estimate.label"), Some("Est."));
+        assert_eq!(taskrc.settings().len(), 3);
+        assert_eq!(
+            taskrc.includes(),
+            &["/usr/share/taskwarrior/dark-256.theme", "holidays.en-US.rc"]
+        );
+
+        assert_eq!(Taskrc::parse("").unwrap(), Taskrc::default());
+        assert_eq!(
+            Taskrc::parse("# Only\n\n# comments\n").unwrap(),
+            Taskrc::default()
+        );
+    }
+    #[test]
+    fn invalid_lines() {
+        assert!(matches!(
+            Taskrc::parse("color=on\nnot a setting\n"),
+            Err(Error::Parse(e)) if e == "line 2: expected name=value: \"not a setting\""
+        ));
+        // Not an include
+        assert!(Taskrc::parse("included").is_err());
+        assert_eq!(
+            Taskrc::parse("include.me=yes").unwrap().get("include.me"),
+            Some("yes")
+        );
+    }
+}
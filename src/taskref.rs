@@ -0,0 +1,243 @@
+//! Borrowed views of tasks, for reading large exports without copying.
+//!
+//! ```rust
+//! use tasklib::prelude::*;
+//!
+//! let json = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","status":"pending","entry":"20220131T083000Z","modified":"20220131T083000Z"}"#;
+//! let task_ref: TaskRef = Task::parse_borrowed(json).expect("parse task");
+//! assert_eq!(task_ref.description(), "Task to do.");
+//! let task: Task = task_ref.to_task().expect("parse task");
+//! ```
+use std::{borrow::Cow, fmt, marker::PhantomData};
+
+use chrono::{offset::Utc, DateTime};
+use serde::{de, Deserialize, Deserializer};
+use uuid::Uuid;
+
+use crate::{tw_str_to_dt_de, tw_str_to_dt_opt_de, Error, Status, Task};
+
+/// A task borrowing its text from the JSON it was parsed from.
+///
+/// Only the common fields are kept. Text without escapes is borrowed rather than copied, so large
+/// descriptions, annotations, and tags aren't allocated twice. Use `to_task` for the full, owned
+/// task.
+///
+/// As with `Task`, `uuid` and `entry` may be missing. They're left unset here rather than
+/// generated, as `to_task` would generate different ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskRef<'a> {
+    /// The JSON the task was parsed from.
+    source: &'a str,
+    fields: Fields<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Fields<'a> {
+    #[serde(default)]
+    uuid: Option<Uuid>,
+    #[serde(borrow)]
+    description: Cow<'a, str>,
+    status: Status,
+    #[serde(deserialize_with = "tw_str_to_dt_opt_de", default)]
+    entry: Option<DateTime<Utc>>,
+    #[serde(deserialize_with = "tw_str_to_dt_de")]
+    modified: DateTime<Utc>,
+    #[serde(borrow, deserialize_with = "borrow_option", default)]
+    project: Option<Cow<'a, str>>,
+    #[serde(borrow, deserialize_with = "borrow_tags", default)]
+    tags: Vec<Cow<'a, str>>,
+    #[serde(borrow, default)]
+    annotations: Vec<AnnotationRef<'a>>,
+}
+
+/// Text borrowed where possible.
+///
+/// Serde only borrows a `Cow<str>` that is itself a field, not one inside an `Option` or `Vec`, so
+/// those go through this.
+#[derive(Deserialize)]
+struct Text<'a>(#[serde(borrow)] Cow<'a, str>);
+
+fn borrow_option<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Cow<'a, str>>, D::Error> {
+    Ok(Option::<Text>::deserialize(deserializer)?.map(|text| text.0))
+}
+
+/// Tags borrowed where possible, from an array or a comma-separated string as `Task` accepts.
+fn borrow_tags<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Cow<'a, str>>, D::Error> {
+    struct TagsVisitor<'a>(PhantomData<&'a ()>);
+
+    impl<'de: 'a, 'a> de::Visitor<'de> for TagsVisitor<'a> {
+        type Value = Vec<Cow<'a, str>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an array of tags or a comma-separated string of tags")
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut tags = Vec::new();
+            while let Some(Text(tag)) = seq.next_element()? {
+                tags.push(tag);
+            }
+            Ok(tags)
+        }
+
+        fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+            Ok(split_tags(v).map(Cow::Borrowed).collect())
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(split_tags(v)
+                .map(|tag| Cow::Owned(tag.to_string()))
+                .collect())
+        }
+    }
+    deserializer.deserialize_any(TagsVisitor(PhantomData))
+}
+
+/// See `tags_de`.
+fn split_tags(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').map(str::trim).filter(|tag| !tag.is_empty())
+}
+
+/// An annotation borrowing its description. See `TaskRef`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AnnotationRef<'a> {
+    #[serde(deserialize_with = "tw_str_to_dt_de")]
+    entry: DateTime<Utc>,
+    #[serde(borrow)]
+    description: Cow<'a, str>,
+}
+
+/// Getters (Immutable)
+impl<'a> AnnotationRef<'a> {
+    pub fn entry(&self) -> &DateTime<Utc> {
+        &self.entry
+    }
+    pub fn description(&self) -> &Cow<'a, str> {
+        &self.description
+    }
+}
+
+/// Getters (Immutable)
+impl<'a> TaskRef<'a> {
+    /// The UUID, if the JSON has one.
+    pub fn uuid(&self) -> Option<&Uuid> {
+        self.fields.uuid.as_ref()
+    }
+    pub fn description(&self) -> &Cow<'a, str> {
+        &self.fields.description
+    }
+    pub fn status(&self) -> &Status {
+        &self.fields.status
+    }
+    /// The entry date, if the JSON has one.
+    pub fn entry(&self) -> Option<&DateTime<Utc>> {
+        self.fields.entry.as_ref()
+    }
+    pub fn modified(&self) -> &DateTime<Utc> {
+        &self.fields.modified
+    }
+    /// The project, or an empty string if there is none.
+    pub fn project(&self) -> &str {
+        self.fields.project.as_deref().unwrap_or_default()
+    }
+    pub fn tags(&self) -> &[Cow<'a, str>] {
+        &self.fields.tags
+    }
+    pub fn annotations(&self) -> &[AnnotationRef<'a>] {
+        &self.fields.annotations
+    }
+}
+
+/// Conversion Methods
+impl TaskRef<'_> {
+    /// Parse the full, owned task.
+    ///
+    /// This parses the source again, so fields that aren't kept, such as UDAs, aren't lost.
+    pub fn to_task(&self) -> Result<Task, Error> {
+        Ok(serde_json::from_str(self.source)?)
+    }
+}
+
+/// Constructors
+impl Task {
+    /// Parse a task, borrowing its text from `s`. See `TaskRef`.
+    pub fn parse_borrowed(s: &str) -> Result<TaskRef<'_>, Error> {
+        Ok(TaskRef {
+            source: s,
+            fields: serde_json::from_str(s)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrow_large_description() {
+        let description = "Task to do. ".repeat(100_000);
+        let json = format!(
+            r#"{{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"{description}","status":"pending","entry":"20220131T083000Z","modified":"20220131T083000Z","project":"Daily","tags":["work"],"annotations":[{{"entry":"20220131T083000Z","description":"{description}"}}],"estimate":"PT2H"}}"#
+        );
+        let task_ref = Task::parse_borrowed(&json).unwrap();
+
+        assert!(matches!(task_ref.description(), Cow::Borrowed(_)));
+        assert_eq!(task_ref.description(), &description);
+        // Points into the source
+        assert!(json
+            .as_bytes()
+            .as_ptr_range()
+            .contains(&task_ref.description().as_ptr()));
+        assert!(matches!(
+            task_ref.annotations()[0].description(),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(task_ref.project(), "Daily");
+        assert_eq!(task_ref.tags(), &["work"]);
+        assert!(matches!(task_ref.fields.project, Some(Cow::Borrowed(_))));
+        assert!(matches!(task_ref.tags()[0], Cow::Borrowed(_)));
+        assert_eq!(task_ref.status(), &Status::Pending);
+
+        let task = task_ref.to_task().unwrap();
+        assert_eq!(task.description(), &description);
+        assert!(task.udas().contains_key("estimate"));
+    }
+    #[test]
+    fn escaped_text_is_owned() {
+        let json = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Say \"hi\"","status":"pending","entry":"20220131T083000Z","modified":"20220131T083000Z"}"#;
+        let task_ref = Task::parse_borrowed(json).unwrap();
+        assert!(matches!(task_ref.description(), Cow::Owned(_)));
+        assert_eq!(task_ref.description(), "Say \"hi\"");
+        assert_eq!(task_ref.project(), "");
+
+        assert!(Task::parse_borrowed("{}").is_err());
+    }
+    #[test]
+    fn missing_uuid_and_entry() {
+        let json =
+            r#"{"description":"Task to do.","status":"pending","modified":"20220131T083000Z"}"#;
+        let task_ref = Task::parse_borrowed(json).unwrap();
+        assert_eq!(task_ref.uuid(), None);
+        assert_eq!(task_ref.entry(), None);
+
+        let task = task_ref.to_task().unwrap();
+        assert!(task.uuid_synthesized());
+    }
+    #[test]
+    fn comma_separated_tags() {
+        let json = r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","status":"pending","entry":"20220131T083000Z","modified":"20220131T083000Z","tags":"work, home,"}"#;
+        let task_ref = Task::parse_borrowed(json).unwrap();
+        assert_eq!(task_ref.tags(), &["work", "home"]);
+        assert!(matches!(task_ref.tags()[0], Cow::Borrowed(_)));
+        assert_eq!(task_ref.tags(), task_ref.to_task().unwrap().tags());
+
+        let json = json.replace("work, ", "w\\u006frk,");
+        let task_ref = Task::parse_borrowed(&json).unwrap();
+        assert_eq!(task_ref.tags(), &["work", "home"]);
+        assert!(matches!(task_ref.tags()[0], Cow::Owned(_)));
+    }
+}
@@ -0,0 +1,460 @@
+//! Helpers operating on collections of tasks.
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{offset::Utc, DateTime};
+use uuid::Uuid;
+
+use crate::{Status, Task};
+
+/// Tasks due within `[start, end)`.
+pub fn due_between<'a>(
+    tasks: &'a [Task],
+    start: &DateTime<Utc>,
+    end: &DateTime<Utc>,
+) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|task| task.due_between(start, end))
+        .collect()
+}
+
+/// Tasks entered within `[start, end)`.
+pub fn entered_between<'a>(
+    tasks: &'a [Task],
+    start: &DateTime<Utc>,
+    end: &DateTime<Utc>,
+) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|task| task.entered_between(start, end))
+        .collect()
+}
+
+/// Tasks completed within `[start, end)`.
+pub fn completed_between<'a>(
+    tasks: &'a [Task],
+    start: &DateTime<Utc>,
+    end: &DateTime<Utc>,
+) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|task| task.completed_between(start, end))
+        .collect()
+}
+
+/// Stamp `modified` on every task.
+pub fn touch_all(tasks: &mut [Task], at: DateTime<Utc>) {
+    for task in tasks {
+        *task.modified_mut() = at;
+    }
+}
+
+/// Stamp `modified` on every task matching `predicate`, returning how many were stamped.
+pub fn touch_where<F>(tasks: &mut [Task], predicate: F, at: DateTime<Utc>) -> usize
+where
+    F: Fn(&Task) -> bool,
+{
+    let mut count = 0;
+    for task in tasks.iter_mut().filter(|task| predicate(task)) {
+        *task.modified_mut() = at;
+        count += 1;
+    }
+    count
+}
+
+/// Remove dependencies on tasks not in `tasks`, returning how many were removed.
+pub fn prune_dangling_depends(tasks: &mut [Task]) -> usize {
+    let uuids: HashSet<Uuid> = tasks.iter().map(|task| *task.uuid()).collect();
+    let mut count = 0;
+    for task in tasks {
+        let len = task.depends().len();
+        task.depends_mut().retain(|uuid| uuids.contains(uuid));
+        count += len - task.depends().len();
+    }
+    count
+}
+
+/// Tasks depending directly on `target`, i.e. those completing it would help unblock.
+pub fn tasks_blocking<'a>(target: &Uuid, all: &'a [Task]) -> Vec<&'a Task> {
+    all.iter().filter(|task| task.depends_on(target)).collect()
+}
+
+/// Group tasks by the top level of their project.
+///
+/// e.g. `Work.ClientA` and `Work.ClientB` are both grouped under `Work`. Tasks without a
+/// project are grouped under an empty string.
+pub fn group_by_project(tasks: &[Task]) -> BTreeMap<String, Vec<&Task>> {
+    let mut groups: BTreeMap<String, Vec<&Task>> = BTreeMap::new();
+    for task in tasks {
+        let top = task.project_hierarchy().first().copied().unwrap_or("");
+        groups.entry(top.to_string()).or_default().push(task);
+    }
+    groups
+}
+
+/// Group tasks by the key `f` returns for each, in order within each group.
+///
+/// e.g. `group_by(&tasks, |task| task.status().as_str())`. See `group_by_multi` for keys such as
+/// tags, where a task may belong to several groups.
+pub fn group_by<K, F>(tasks: &[Task], f: F) -> BTreeMap<K, Vec<&Task>>
+where
+    K: Ord,
+    F: Fn(&Task) -> K,
+{
+    let mut groups: BTreeMap<K, Vec<&Task>> = BTreeMap::new();
+    for task in tasks {
+        groups.entry(f(task)).or_default().push(task);
+    }
+    groups
+}
+
+/// Group tasks under each of the keys `f` returns for each, in order within each group.
+///
+/// e.g. `group_by_multi(&tasks, |task| task.tags().to_vec())`. A task is grouped once per distinct
+/// key, and not at all if it has none. See `group_by`.
+pub fn group_by_multi<K, I, F>(tasks: &[Task], f: F) -> BTreeMap<K, Vec<&Task>>
+where
+    K: Ord,
+    I: IntoIterator<Item = K>,
+    F: Fn(&Task) -> I,
+{
+    let mut groups: BTreeMap<K, Vec<&Task>> = BTreeMap::new();
+    for task in tasks {
+        for key in f(task) {
+            let group = groups.entry(key).or_default();
+            if !group.last().is_some_and(|last| std::ptr::eq(*last, task)) {
+                group.push(task);
+            }
+        }
+    }
+    groups
+}
+
+/// Move every task in project `from`, or one of its subprojects, to `to`, returning how many were
+/// moved.
+///
+/// e.g. renaming `Work` to `Job` moves `Work.ClientA` to `Job.ClientA`. See `Task::rename_project`.
+pub fn rename_project_in_all(tasks: &mut [Task], from: &str, to: &str) -> usize {
+    let mut count = 0;
+    for task in tasks {
+        if task.rename_project(from, to) {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Filters over an iterator of tasks, for chaining.
+///
+/// e.g. `tasks.iter().pending().tagged("work")`
+pub trait TaskIteratorExt<'a>: Iterator<Item = &'a Task> + Sized {
+    /// Tasks with a pending status.
+    fn pending(self) -> impl Iterator<Item = &'a Task> {
+        self.filter(|task| task.status() == &Status::Pending)
+    }
+    /// Tasks in `project`, or one of its subprojects.
+    ///
+    /// e.g. `Work` matches `Work` and `Work.ClientA`, but not `Workshop`.
+    fn with_project(self, project: &str) -> impl Iterator<Item = &'a Task> {
+        self.filter(move |task| {
+            task.project()
+                .strip_prefix(project)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        })
+    }
+    /// Tasks with `tag`.
+    fn tagged(self, tag: &str) -> impl Iterator<Item = &'a Task> {
+        self.filter(move |task| task.tags().iter().any(|t| t == tag))
+    }
+    /// Tasks due before `dt`. Tasks without a due date never match.
+    fn due_before(self, dt: DateTime<Utc>) -> impl Iterator<Item = &'a Task> {
+        self.filter(move |task| task.due().is_some_and(|due| due < &dt))
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a Task>> TaskIteratorExt<'a> for I {}
+
+/// Filters over a slice of tasks. See `TaskIteratorExt` to chain them.
+///
+/// e.g. `tasks.pending().tagged("work")`
+pub trait TaskSliceExt {
+    fn pending(&self) -> impl Iterator<Item = &Task>;
+    fn with_project(&self, project: &str) -> impl Iterator<Item = &Task>;
+    fn tagged(&self, tag: &str) -> impl Iterator<Item = &Task>;
+    fn due_before(&self, dt: DateTime<Utc>) -> impl Iterator<Item = &Task>;
+}
+
+impl TaskSliceExt for [Task] {
+    fn pending(&self) -> impl Iterator<Item = &Task> {
+        self.iter().pending()
+    }
+    fn with_project(&self, project: &str) -> impl Iterator<Item = &Task> {
+        self.iter().with_project(project)
+    }
+    fn tagged(&self, tag: &str) -> impl Iterator<Item = &Task> {
+        self.iter().tagged(tag)
+    }
+    fn due_before(&self, dt: DateTime<Utc>) -> impl Iterator<Item = &Task> {
+        self.iter().due_before(dt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TaskBuilder;
+    use chrono::TimeZone;
+
+    fn dt(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2022, 1, day, 0, 0, 0).unwrap()
+    }
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            TaskBuilder::new()
+                .uuid("d67fce70-c0b6-43c5-affc-a21e64567d40")
+                .description("Due on the 10th")
+                .status(Status::Pending)
+                .entry(dt(1))
+                .modified(dt(1))
+                .due(dt(10))
+                .build(),
+            TaskBuilder::new()
+                .uuid("8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b")
+                .description("Completed on the 17th")
+                .status(Status::Completed)
+                .entry(dt(10))
+                .modified(dt(17))
+                .end(dt(17))
+                .due(dt(17))
+                .build(),
+            TaskBuilder::new()
+                .uuid("0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f")
+                .description("Deleted on the 12th, no due")
+                .status(Status::Deleted)
+                .entry(dt(5))
+                .modified(dt(12))
+                .end(dt(12))
+                .build(),
+        ]
+    }
+
+    fn descriptions(tasks: Vec<&Task>) -> Vec<&str> {
+        tasks.iter().map(|task| task.description()).collect()
+    }
+
+    #[test]
+    fn boundaries() {
+        let tasks = tasks();
+        let task = &tasks[0];
+        // Inclusive start
+        assert!(task.due_between(&dt(10), &dt(11)));
+        // Exclusive end
+        assert!(!task.due_between(&dt(9), &dt(10)));
+        assert!(task.entered_between(&dt(1), &dt(2)));
+        assert!(!task.entered_between(&dt(2), &dt(3)));
+        // Not completed
+        assert!(!task.completed_between(&dt(1), &dt(31)));
+        // No due
+        assert!(!tasks[2].due_between(&dt(1), &dt(31)));
+        // Has an end, but is deleted
+        assert!(!tasks[2].completed_between(&dt(1), &dt(31)));
+    }
+    #[test]
+    fn filters() {
+        let tasks = tasks();
+        assert_eq!(
+            descriptions(due_between(&tasks, &dt(10), &dt(17))),
+            vec!["Due on the 10th"]
+        );
+        assert_eq!(
+            descriptions(due_between(&tasks, &dt(10), &dt(18))),
+            vec!["Due on the 10th", "Completed on the 17th"]
+        );
+        assert_eq!(
+            descriptions(entered_between(&tasks, &dt(5), &dt(10))),
+            vec!["Deleted on the 12th, no due"]
+        );
+        assert_eq!(
+            descriptions(completed_between(&tasks, &dt(17), &dt(18))),
+            vec!["Completed on the 17th"]
+        );
+        assert!(completed_between(&tasks, &dt(1), &dt(17)).is_empty());
+    }
+    #[test]
+    fn touch() {
+        let mut tasks = tasks();
+        touch_all(&mut tasks, dt(20));
+        assert!(tasks.iter().all(|task| task.modified() == &dt(20)));
+
+        let count = touch_where(&mut tasks, |task| task.status() == &Status::Pending, dt(25));
+        assert_eq!(count, 1);
+        assert_eq!(tasks[0].modified(), &dt(25));
+        // Completed and deleted tasks are untouched
+        assert_eq!(tasks[1].modified(), &dt(20));
+        assert_eq!(tasks[2].modified(), &dt(20));
+
+        let before = *tasks[0].modified();
+        tasks[0].touch();
+        assert!(tasks[0].modified() > &before);
+    }
+    #[test]
+    fn prune_dangling_depends() {
+        let mut tasks = tasks();
+        let missing = Uuid::parse_str("11111111-2222-4333-8444-555555555555").unwrap();
+        let present = *tasks[1].uuid();
+        *tasks[0].depends_mut() = vec![missing, present];
+
+        assert_eq!(super::prune_dangling_depends(&mut tasks), 1);
+        assert_eq!(tasks[0].depends(), &[present]);
+        assert_eq!(super::prune_dangling_depends(&mut tasks), 0);
+
+        assert!(tasks[0].remove_dependency(&present));
+        assert!(tasks[0].depends().is_empty());
+        assert!(!tasks[0].remove_dependency(&present));
+    }
+    #[test]
+    fn tasks_blocking() {
+        let mut tasks = tasks();
+        let target = *tasks[1].uuid();
+        *tasks[0].depends_mut() = vec![target];
+        *tasks[2].depends_mut() = vec![*tasks[0].uuid(), target];
+
+        assert!(tasks[0].depends_on(&target));
+        assert!(!tasks[0].depends_on(tasks[2].uuid()));
+        assert!(!tasks[1].depends_on(&target));
+
+        assert_eq!(
+            descriptions(super::tasks_blocking(&target, &tasks)),
+            vec!["Due on the 10th", "Deleted on the 12th, no due"]
+        );
+        assert_eq!(
+            descriptions(super::tasks_blocking(tasks[0].uuid(), &tasks)),
+            vec!["Deleted on the 12th, no due"]
+        );
+        assert!(super::tasks_blocking(tasks[2].uuid(), &tasks).is_empty());
+    }
+    #[test]
+    fn group_by_project() {
+        let mut tasks = tasks();
+        *tasks[0].project_mut() = "Work.ClientA".to_string();
+        *tasks[1].project_mut() = "Work.ClientB.Phase1".to_string();
+
+        let groups = super::group_by_project(&tasks);
+        assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["", "Work"]);
+        assert_eq!(
+            descriptions(groups["Work"].clone()),
+            vec!["Due on the 10th", "Completed on the 17th"]
+        );
+        assert_eq!(
+            descriptions(groups[""].clone()),
+            vec!["Deleted on the 12th, no due"]
+        );
+    }
+    #[test]
+    fn group_by() {
+        let mut tasks = tasks();
+        tasks.push(
+            TaskBuilder::new()
+                .uuid("5e1b2c3d-4f5a-4b6c-8d7e-9f0a1b2c3d4e")
+                .description("Pending, no due")
+                .status(Status::Pending)
+                .entry(dt(2))
+                .modified(dt(2))
+                .build(),
+        );
+
+        let groups = super::group_by(&tasks, |task| task.status().as_str());
+        assert_eq!(
+            groups.keys().collect::<Vec<_>>(),
+            vec![&"completed", &"deleted", &"pending"]
+        );
+        assert_eq!(
+            descriptions(groups["pending"].clone()),
+            vec!["Due on the 10th", "Pending, no due"]
+        );
+        assert_eq!(
+            descriptions(groups["deleted"].clone()),
+            vec!["Deleted on the 12th, no due"]
+        );
+
+        // By first tag
+        *tasks[0].tags_mut() = vec!["work".to_string(), "urgent".to_string()];
+        *tasks[1].tags_mut() = vec!["home".to_string()];
+        *tasks[2].tags_mut() = vec!["work".to_string()];
+        let groups = super::group_by(&tasks, |task| task.tags().first().cloned());
+        assert_eq!(
+            groups.keys().collect::<Vec<_>>(),
+            vec![&None, &Some("home".to_string()), &Some("work".to_string())]
+        );
+        assert_eq!(
+            descriptions(groups[&Some("work".to_string())].clone()),
+            vec!["Due on the 10th", "Deleted on the 12th, no due"]
+        );
+        assert_eq!(descriptions(groups[&None].clone()), vec!["Pending, no due"]);
+    }
+    #[test]
+    fn group_by_multi() {
+        let mut tasks = tasks();
+        *tasks[0].tags_mut() = vec!["work".to_string(), "urgent".to_string()];
+        *tasks[1].tags_mut() = vec!["work".to_string(), "work".to_string()];
+
+        let groups = super::group_by_multi(&tasks, |task| task.tags().to_vec());
+        assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["urgent", "work"]);
+        assert_eq!(
+            descriptions(groups["work"].clone()),
+            vec!["Due on the 10th", "Completed on the 17th"]
+        );
+        assert_eq!(
+            descriptions(groups["urgent"].clone()),
+            vec!["Due on the 10th"]
+        );
+    }
+    #[test]
+    fn rename_project_in_all() {
+        let mut tasks = tasks();
+        *tasks[0].project_mut() = "Work".to_string();
+        *tasks[1].project_mut() = "Work.ClientA".to_string();
+        *tasks[2].project_mut() = "Workshop".to_string();
+
+        assert_eq!(super::rename_project_in_all(&mut tasks, "Work", "Job"), 2);
+        assert_eq!(tasks[0].project(), "Job");
+        assert_eq!(tasks[1].project(), "Job.ClientA");
+        assert_eq!(tasks[2].project(), "Workshop");
+        assert!(tasks[0].modified() > &dt(1));
+        assert_eq!(tasks[2].modified(), &dt(12));
+
+        // Nested
+        assert_eq!(
+            super::rename_project_in_all(&mut tasks, "Job.ClientA", "Clients.A"),
+            1
+        );
+        assert_eq!(tasks[1].project(), "Clients.A");
+        assert_eq!(super::rename_project_in_all(&mut tasks, "Job", "Job"), 0);
+
+        // No project isn't a project named ""
+        tasks[2].project_mut().clear();
+        assert_eq!(super::rename_project_in_all(&mut tasks, "", "Job"), 0);
+        assert_eq!(tasks[2].project(), "");
+    }
+    #[test]
+    fn chain_filters() {
+        let mut tasks = tasks();
+        tasks[0].add_tag("work").unwrap();
+        *tasks[0].project_mut() = "Work.ClientA".to_string();
+        tasks[1].add_tag("work").unwrap();
+        *tasks[1].project_mut() = "Work".to_string();
+
+        let pending: Vec<&Task> = tasks.pending().tagged("work").collect();
+        assert_eq!(descriptions(pending), vec!["Due on the 10th"]);
+        let work: Vec<&Task> = tasks.with_project("Work").tagged("work").collect();
+        assert_eq!(
+            descriptions(work),
+            vec!["Due on the 10th", "Completed on the 17th"]
+        );
+        assert_eq!(tasks.with_project("Wor").count(), 0);
+        let due: Vec<&Task> = tasks.tagged("work").due_before(dt(17)).collect();
+        assert_eq!(descriptions(due), vec!["Due on the 10th"]);
+        assert_eq!(tasks.due_before(dt(31)).pending().count(), 1);
+    }
+}
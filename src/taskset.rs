@@ -0,0 +1,409 @@
+//! A collection of tasks with indexed lookups.
+use std::collections::HashMap;
+use std::slice;
+
+use uuid::Uuid;
+
+use crate::{Error, Task};
+
+/// A reversible change made to a `TaskSet`.
+///
+/// Changes are recorded in the set's journal and reverted, last first, by `TaskSet::undo`. This
+/// mirrors `task undo`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// A task was inserted at `position`, replacing `replaced` if it had the same UUID.
+    Insert {
+        position: usize,
+        replaced: Option<Task>,
+    },
+    /// A task was removed from `position`.
+    Remove { position: usize, task: Task },
+    /// The task at `position` was modified. `prior` is its state before the modification.
+    Modify { position: usize, prior: Task },
+}
+
+/// A collection of tasks, indexed by UUID, ID, tag, and project.
+///
+/// Tasks are unique by UUID. Inserting a task with a UUID that already exists replaces it.
+///
+/// Every `insert`, `remove`, and `modify` is recorded as a `Change` and can be reverted with
+/// `undo`.
+#[derive(Debug, Default, Clone)]
+pub struct TaskSet {
+    tasks: Vec<Task>,
+    /// Journal of changes, most recent last.
+    changes: Vec<Change>,
+    /// UUID -> position in `tasks`
+    uuids: HashMap<Uuid, usize>,
+    /// ID -> position in `tasks`
+    ids: HashMap<usize, usize>,
+    /// Tag -> positions in `tasks`
+    tags: HashMap<String, Vec<usize>>,
+    /// Project -> positions in `tasks`
+    projects: HashMap<String, Vec<usize>>,
+}
+
+/// Constructors
+///
+/// Tasks a set is constructed from are not recorded in the journal.
+impl TaskSet {
+    pub fn new() -> Self {
+        TaskSet {
+            ..Default::default()
+        }
+    }
+    /// Parse a JSON array of tasks, such as the output of `task export`.
+    pub fn from_json_array(s: &str) -> Result<Self, Error> {
+        let tasks: Vec<Task> = serde_json::from_str(s)?;
+        Ok(tasks.into_iter().collect())
+    }
+}
+
+/// Lookups
+impl TaskSet {
+    pub fn by_uuid(&self, uuid: &Uuid) -> Option<&Task> {
+        self.uuids.get(uuid).map(|&i| &self.tasks[i])
+    }
+    pub fn by_id(&self, id: usize) -> Option<&Task> {
+        self.ids.get(&id).map(|&i| &self.tasks[i])
+    }
+    /// All tasks with the given tag.
+    pub fn with_tag(&self, tag: &str) -> Vec<&Task> {
+        self.tags
+            .get(tag)
+            .map(|positions| positions.iter().map(|&i| &self.tasks[i]).collect())
+            .unwrap_or_default()
+    }
+    /// All tasks in the given project.
+    pub fn in_project(&self, project: &str) -> Vec<&Task> {
+        self.projects
+            .get(project)
+            .map(|positions| positions.iter().map(|&i| &self.tasks[i]).collect())
+            .unwrap_or_default()
+    }
+    pub fn iter(&self) -> slice::Iter<'_, Task> {
+        self.tasks.iter()
+    }
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+    /// The journal of changes, most recent last.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+}
+
+/// Mutation
+impl TaskSet {
+    /// Insert a task, returning the task it replaced if one with the same UUID existed.
+    pub fn insert(&mut self, task: Task) -> Option<Task> {
+        if let Some(&position) = self.uuids.get(task.uuid()) {
+            let old = std::mem::replace(&mut self.tasks[position], task);
+            self.reindex();
+            self.changes.push(Change::Insert {
+                position,
+                replaced: Some(old.clone()),
+            });
+            return Some(old);
+        }
+        self.tasks.push(task);
+        let position = self.tasks.len() - 1;
+        self.index(position);
+        self.changes.push(Change::Insert {
+            position,
+            replaced: None,
+        });
+        None
+    }
+    /// Remove the task with the given UUID, returning it if it existed.
+    pub fn remove(&mut self, uuid: &Uuid) -> Option<Task> {
+        let position = self.uuids.get(uuid).copied()?;
+        let task = self.tasks.remove(position);
+        self.reindex();
+        self.changes.push(Change::Remove {
+            position,
+            task: task.clone(),
+        });
+        Some(task)
+    }
+    /// Modify the task with the given UUID in place.
+    ///
+    /// Returns `false` if no such task exists. The UUID can't be changed, as the set is keyed by
+    /// it; if `f` changes it, the task is left as it was and `false` is returned. Use `remove` and
+    /// `insert` instead.
+    pub fn modify<F: FnOnce(&mut Task)>(&mut self, uuid: &Uuid, f: F) -> bool {
+        let Some(&position) = self.uuids.get(uuid) else {
+            return false;
+        };
+        let prior = self.tasks[position].clone();
+        f(&mut self.tasks[position]);
+        if self.tasks[position].uuid() != uuid {
+            self.tasks[position] = prior;
+            return false;
+        }
+        self.reindex();
+        self.changes.push(Change::Modify { position, prior });
+        true
+    }
+    /// Revert the most recent change, returning it.
+    ///
+    /// Returns `None` if there is nothing to undo.
+    pub fn undo(&mut self) -> Option<Change> {
+        let change = self.changes.pop()?;
+        match &change {
+            Change::Insert {
+                position,
+                replaced: Some(task),
+            } => self.tasks[*position] = task.clone(),
+            Change::Insert {
+                position,
+                replaced: None,
+            } => {
+                self.tasks.remove(*position);
+            }
+            Change::Remove { position, task } => self.tasks.insert(*position, task.clone()),
+            Change::Modify { position, prior } => self.tasks[*position] = prior.clone(),
+        }
+        self.reindex();
+        Some(change)
+    }
+}
+
+/// Indexing
+impl TaskSet {
+    /// Add the task at `position` to the indexes.
+    fn index(&mut self, position: usize) {
+        let task = &self.tasks[position];
+        self.uuids.insert(*task.uuid(), position);
+        if let Some(id) = task.id() {
+            self.ids.insert(*id, position);
+        }
+        for tag in task.tags() {
+            let positions = self.tags.entry(tag.clone()).or_default();
+            // Tags set directly may repeat
+            if positions.last() != Some(&position) {
+                positions.push(position);
+            }
+        }
+        if !task.project().is_empty() {
+            self.projects
+                .entry(task.project().to_string())
+                .or_default()
+                .push(position);
+        }
+    }
+    /// Rebuild all indexes from scratch.
+    fn reindex(&mut self) {
+        self.uuids.clear();
+        self.ids.clear();
+        self.tags.clear();
+        self.projects.clear();
+        for position in 0..self.tasks.len() {
+            self.index(position);
+        }
+    }
+}
+
+impl FromIterator<Task> for TaskSet {
+    fn from_iter<I: IntoIterator<Item = Task>>(iter: I) -> Self {
+        let mut set = TaskSet::new();
+        for task in iter {
+            set.insert(task);
+        }
+        set.changes.clear();
+        set
+    }
+}
+
+impl IntoIterator for TaskSet {
+    type Item = Task;
+    type IntoIter = std::vec::IntoIter<Task>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tasks.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TaskSet {
+    type Item = &'a Task;
+    type IntoIter = slice::Iter<'a, Task>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tasks.iter()
+    }
+}
+
+impl From<Vec<Task>> for TaskSet {
+    fn from(tasks: Vec<Task>) -> Self {
+        tasks.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASKS: &str = r#"
+    [
+        {
+            "id": 1,
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "First",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "project": "Work",
+            "tags": ["a", "b"]
+        },
+        {
+            "id": 2,
+            "uuid": "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b",
+            "description": "Second",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "project": "Home",
+            "tags": ["b"]
+        },
+        {
+            "id": 3,
+            "uuid": "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f",
+            "description": "Third",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "project": "Work",
+            "tags": ["a"]
+        }
+    ]
+    "#;
+
+    #[test]
+    fn lookups() {
+        let set = TaskSet::from_json_array(TASKS).unwrap();
+        assert_eq!(set.len(), 3);
+
+        let uuid = Uuid::parse_str("8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b").unwrap();
+        assert_eq!(set.by_uuid(&uuid).unwrap().description(), "Second");
+        assert_eq!(set.by_id(3).unwrap().description(), "Third");
+        assert_eq!(set.with_tag("a").len(), 2);
+        assert_eq!(set.with_tag("b").len(), 2);
+        assert_eq!(set.with_tag("c").len(), 0);
+        assert_eq!(set.in_project("Work").len(), 2);
+        assert_eq!(set.in_project("Home").len(), 1);
+    }
+    #[test]
+    fn lookups_after_remove() {
+        let mut set = TaskSet::from_json_array(TASKS).unwrap();
+
+        let uuid = Uuid::parse_str("d67fce70-c0b6-43c5-affc-a21e64567d40").unwrap();
+        let removed = set.remove(&uuid).unwrap();
+        assert_eq!(removed.description(), "First");
+        assert_eq!(set.len(), 2);
+        assert!(set.remove(&uuid).is_none());
+
+        assert!(set.by_uuid(&uuid).is_none());
+        assert!(set.by_id(1).is_none());
+        // Positions shifted, so the indexes must still point at the right tasks
+        assert_eq!(set.by_id(2).unwrap().description(), "Second");
+        assert_eq!(set.by_id(3).unwrap().description(), "Third");
+        let tagged: Vec<&str> = set.with_tag("a").iter().map(|t| t.description()).collect();
+        assert_eq!(tagged, vec!["Third"]);
+        let work: Vec<&str> = set
+            .in_project("Work")
+            .iter()
+            .map(|t| t.description())
+            .collect();
+        assert_eq!(work, vec!["Third"]);
+    }
+    #[test]
+    fn insert_replaces() {
+        let mut set = TaskSet::from_json_array(TASKS).unwrap();
+
+        let uuid = Uuid::parse_str("d67fce70-c0b6-43c5-affc-a21e64567d40").unwrap();
+        let mut task = set.by_uuid(&uuid).unwrap().clone();
+        *task.project_mut() = "Home".to_string();
+        let old = set.insert(task).unwrap();
+        assert_eq!(old.project(), "Work");
+
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.in_project("Work").len(), 1);
+        assert_eq!(set.in_project("Home").len(), 2);
+    }
+    #[test]
+    fn undo_modify() {
+        let mut set = TaskSet::from_json_array(TASKS).unwrap();
+
+        let uuid = Uuid::parse_str("8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b").unwrap();
+        let before = set.by_uuid(&uuid).unwrap().clone();
+        assert!(set.modify(&uuid, |task| {
+            *task.description_mut() = "Changed".to_string();
+            *task.project_mut() = "Work".to_string();
+            task.tags_mut().clear();
+        }));
+        assert_eq!(set.by_uuid(&uuid).unwrap().description(), "Changed");
+        assert_eq!(set.in_project("Work").len(), 3);
+
+        let change = set.undo().unwrap();
+        assert_eq!(
+            change,
+            Change::Modify {
+                position: 1,
+                prior: before.clone()
+            }
+        );
+        assert_eq!(set.by_uuid(&uuid).unwrap(), &before);
+        assert_eq!(set.in_project("Work").len(), 2);
+        assert_eq!(set.with_tag("b").len(), 2);
+    }
+    #[test]
+    fn modify_keeps_uuid() {
+        let mut set = TaskSet::from_json_array(TASKS).unwrap();
+
+        let uuid = Uuid::parse_str("8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b").unwrap();
+        let other = Uuid::parse_str("d67fce70-c0b6-43c5-affc-a21e64567d40").unwrap();
+        let before = set.by_uuid(&uuid).unwrap().clone();
+        assert!(!set.modify(&uuid, |task| {
+            *task.description_mut() = "Changed".to_string();
+            *task.uuid_mut() = other;
+        }));
+        assert_eq!(set.by_uuid(&uuid).unwrap(), &before);
+        assert_eq!(set.by_uuid(&other).unwrap().description(), "First");
+        assert!(set.changes().is_empty());
+    }
+    #[test]
+    fn repeated_tags() {
+        let mut set = TaskSet::from_json_array(TASKS).unwrap();
+
+        let uuid = Uuid::parse_str("8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b").unwrap();
+        assert!(set.modify(&uuid, |task| task.tags_mut().push("b".to_string())));
+        assert_eq!(set.with_tag("b").len(), 2);
+    }
+    #[test]
+    fn undo_insert_and_remove() {
+        let mut set = TaskSet::from_json_array(TASKS).unwrap();
+        let original: Vec<Task> = set.iter().cloned().collect();
+
+        let uuid = Uuid::parse_str("d67fce70-c0b6-43c5-affc-a21e64567d40").unwrap();
+        let removed = set.remove(&uuid).unwrap();
+        set.undo().unwrap();
+        assert_eq!(set.iter().cloned().collect::<Vec<Task>>(), original);
+        assert_eq!(set.by_id(1).unwrap(), &removed);
+
+        let mut task = removed.clone();
+        *task.uuid_mut() = Uuid::parse_str("5f0c2b7e-9a1d-4c3b-8e2f-7a6b5c4d3e2f").unwrap();
+        set.insert(task);
+        assert_eq!(set.len(), 4);
+        set.undo().unwrap();
+        assert_eq!(set.iter().cloned().collect::<Vec<Task>>(), original);
+
+        // Nothing left to undo, the initial tasks are not journaled
+        assert!(set.changes().is_empty());
+        assert!(set.undo().is_none());
+        assert_eq!(set.iter().cloned().collect::<Vec<Task>>(), original);
+    }
+}
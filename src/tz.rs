@@ -0,0 +1,80 @@
+//! Dates in a local time zone.
+//!
+//! Tasks are always stored in UTC. These only convert for display.
+//!
+//! ```rust
+//! use tasklib::prelude::*;
+//! use chrono_tz::America::New_York;
+//!
+//! let task: Task = Task::from(r#"{"uuid":"d67fce70-c0b6-43c5-affc-a21e64567d40","description":"Task to do.","status":"pending","entry":"20220131T083000Z","modified":"20220131T083000Z"}"#);
+//! assert_eq!(task.entry_local(New_York).to_string(), "2022-01-31 03:30:00 EST");
+//! ```
+use chrono::DateTime;
+use chrono_tz::Tz;
+
+use crate::Task;
+
+/// Dates (Local)
+impl Task {
+    pub fn start_local(&self, tz: Tz) -> Option<DateTime<Tz>> {
+        self.start.map(|dt| dt.with_timezone(&tz))
+    }
+    pub fn end_local(&self, tz: Tz) -> Option<DateTime<Tz>> {
+        self.end.map(|dt| dt.with_timezone(&tz))
+    }
+    pub fn entry_local(&self, tz: Tz) -> DateTime<Tz> {
+        self.entry.with_timezone(&tz)
+    }
+    pub fn modified_local(&self, tz: Tz) -> DateTime<Tz> {
+        self.modified.with_timezone(&tz)
+    }
+    pub fn scheduled_local(&self, tz: Tz) -> Option<DateTime<Tz>> {
+        self.scheduled.map(|dt| dt.with_timezone(&tz))
+    }
+    pub fn until_local(&self, tz: Tz) -> Option<DateTime<Tz>> {
+        self.until.map(|dt| dt.with_timezone(&tz))
+    }
+    pub fn wait_local(&self, tz: Tz) -> Option<DateTime<Tz>> {
+        self.wait.map(|dt| dt.with_timezone(&tz))
+    }
+    pub fn due_local(&self, tz: Tz) -> Option<DateTime<Tz>> {
+        self.due.map(|dt| dt.with_timezone(&tz))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Offset, TimeZone, Utc};
+    use chrono_tz::America::New_York;
+
+    #[test]
+    fn due_local() {
+        let task_str = r#"
+        {
+            "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+            "description": "Task to do.",
+            "status": "pending",
+            "entry": "20220131T083000Z",
+            "modified": "20220131T083000Z",
+            "due": "20220701T160000Z"
+        }
+        "#;
+        let task = task_str.parse::<Task>().unwrap();
+
+        // Daylight saving time
+        let due = task.due_local(New_York).unwrap();
+        assert_eq!(due.offset().fix().local_minus_utc(), -4 * 60 * 60);
+        assert_eq!(due.to_string(), "2022-07-01 12:00:00 EDT");
+        assert_eq!(due, Utc.with_ymd_and_hms(2022, 7, 1, 16, 0, 0).unwrap());
+        // Standard time
+        let entry = task.entry_local(New_York);
+        assert_eq!(entry.offset().fix().local_minus_utc(), -5 * 60 * 60);
+
+        assert!(task.wait_local(New_York).is_none());
+        // Serialization is unchanged
+        assert!(task
+            .to_json_string()
+            .contains(r#""due":"20220701T160000Z""#));
+    }
+}
@@ -0,0 +1,374 @@
+//! Write tasks as a JSON array, incrementally or as a batch for `task import`.
+use std::io::{self, Write};
+
+use crate::Task;
+
+/// Writes tasks as a JSON array, one at a time, without collecting them first.
+///
+/// The array is closed by `finish`, or when the writer is dropped. Errors while extending are
+/// kept and returned by `finish`. Once a write fails, nothing more is written, not even the closing
+/// `]`, since a task may have been written partway.
+///
+/// ```rust
+/// use tasklib::prelude::*;
+///
+/// let mut buffer: Vec<u8> = Vec::new();
+/// let mut writer = TaskArrayWriter::new(&mut buffer);
+/// writer.extend(Vec::<Task>::new());
+/// writer.finish().expect("write tasks");
+/// assert_eq!(buffer, b"[]");
+/// ```
+pub struct TaskArrayWriter<W: Write> {
+    writer: W,
+    /// Whether the array has been opened, with `[` written.
+    opened: bool,
+    /// Whether any task has been written, and a comma is needed before the next.
+    written: bool,
+    /// Whether the array has been closed.
+    finished: bool,
+    /// Whether a write has failed, after which nothing more is written.
+    failed: bool,
+    /// The first error encountered while extending.
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TaskArrayWriter<W> {
+    pub fn new(writer: W) -> Self {
+        TaskArrayWriter {
+            writer,
+            opened: false,
+            written: false,
+            finished: false,
+            failed: false,
+            error: None,
+        }
+    }
+    /// Write a single task to the array.
+    pub fn write(&mut self, task: &Task) -> io::Result<()> {
+        self.guard(|this| {
+            this.open()?;
+            if this.written {
+                this.writer.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut this.writer, task)?;
+            this.written = true;
+            Ok(())
+        })
+    }
+    /// Close the array and flush.
+    ///
+    /// Returns the first error encountered while extending, if any.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.close(),
+        }
+    }
+    fn close(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.guard(|this| {
+            this.open()?;
+            this.writer.write_all(b"]")?;
+            this.writer.flush()
+        })
+    }
+    /// Write with `f`, unless a write has already failed, and remember if this one does.
+    fn guard(&mut self, f: impl FnOnce(&mut Self) -> io::Result<()>) -> io::Result<()> {
+        if self.failed {
+            return Err(io::Error::other("an earlier write failed"));
+        }
+        let result = f(self);
+        self.failed = result.is_err();
+        result
+    }
+    /// Write `[`, if it hasn't been yet.
+    fn open(&mut self) -> io::Result<()> {
+        if !self.opened {
+            self.writer.write_all(b"[")?;
+            self.opened = true;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Default> TaskArrayWriter<W> {
+    /// Close the array and return the writer, e.g. a buffer collected into.
+    ///
+    /// Returns the first error encountered while extending, if any.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.close()?;
+        Ok(std::mem::take(&mut self.writer))
+    }
+}
+
+impl<W: Write> Extend<Task> for TaskArrayWriter<W> {
+    fn extend<I: IntoIterator<Item = Task>>(&mut self, iter: I) {
+        for task in iter {
+            self.extend(Some(&task));
+        }
+    }
+}
+
+impl<'a, W: Write> Extend<&'a Task> for TaskArrayWriter<W> {
+    fn extend<I: IntoIterator<Item = &'a Task>>(&mut self, iter: I) {
+        for task in iter {
+            if self.error.is_some() {
+                return;
+            }
+            if let Err(e) = self.write(task) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+/// Collect into a new writer, e.g. a `Vec<u8>`. See `TaskArrayWriter::into_inner`.
+impl<W: Write + Default> FromIterator<Task> for TaskArrayWriter<W> {
+    fn from_iter<I: IntoIterator<Item = Task>>(iter: I) -> Self {
+        let mut writer = TaskArrayWriter::new(W::default());
+        writer.extend(iter);
+        writer
+    }
+}
+
+/// See `FromIterator<Task>`.
+impl<'a, W: Write + Default> FromIterator<&'a Task> for TaskArrayWriter<W> {
+    fn from_iter<I: IntoIterator<Item = &'a Task>>(iter: I) -> Self {
+        let mut writer = TaskArrayWriter::new(W::default());
+        writer.extend(iter);
+        writer
+    }
+}
+
+impl<W: Write> Drop for TaskArrayWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+/// Tasks to pass to a single `task import`, rather than one call each.
+///
+/// Tasks are sanitized as they're added, see `Task::sanitize_for_import`.
+///
+/// ```rust
+/// use tasklib::prelude::*;
+///
+/// let mut batch = ImportBatch::new();
+/// batch.push(Task::new("Water the plants"));
+/// assert_eq!(batch.len(), 1);
+/// assert!(batch.to_json().starts_with("[{"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportBatch {
+    tasks: Vec<Task>,
+}
+
+impl ImportBatch {
+    pub fn new() -> Self {
+        ImportBatch::default()
+    }
+    /// Add a task, sanitized for import.
+    pub fn push(&mut self, mut task: Task) {
+        task.sanitize_for_import();
+        self.tasks.push(task);
+    }
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+    /// The batch as a JSON array, for `task import`.
+    pub fn to_json(&self) -> String {
+        // Can't fail, see `Task::to_json`.
+        serde_json::to_string(&self.tasks).expect("tasks serialize to JSON")
+    }
+}
+
+impl Extend<Task> for ImportBatch {
+    fn extend<I: IntoIterator<Item = Task>>(&mut self, iter: I) {
+        for task in iter {
+            self.push(task);
+        }
+    }
+}
+
+impl FromIterator<Task> for ImportBatch {
+    fn from_iter<I: IntoIterator<Item = Task>>(iter: I) -> Self {
+        let mut batch = ImportBatch::new();
+        batch.extend(iter);
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(description: &str, uuid: &str) -> Task {
+        format!(
+            r#"{{"uuid":"{uuid}","description":"{description}","status":"pending","entry":"20220131T083000Z","modified":"20220131T083000Z"}}"#
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn write_incrementally() {
+        let tasks = vec![
+            task("First", "d67fce70-c0b6-43c5-affc-a21e64567d40"),
+            task("Second", "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b"),
+            task("Third", "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f"),
+        ];
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut writer = TaskArrayWriter::new(&mut buffer);
+        writer.extend(tasks[..1].iter());
+        writer.extend(tasks[1..].iter().cloned());
+        writer.finish().unwrap();
+
+        let parsed: Vec<Task> = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(parsed, tasks);
+    }
+    #[test]
+    fn close_on_drop() {
+        let mut buffer: Vec<u8> = Vec::new();
+        {
+            let mut writer = TaskArrayWriter::new(&mut buffer);
+            writer
+                .write(&task("First", "d67fce70-c0b6-43c5-affc-a21e64567d40"))
+                .unwrap();
+        }
+        let parsed: Vec<Task> = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(parsed.len(), 1);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        TaskArrayWriter::new(&mut buffer);
+        assert_eq!(buffer, b"[]");
+    }
+    #[test]
+    fn collect() {
+        let tasks = vec![
+            task("First", "d67fce70-c0b6-43c5-affc-a21e64567d40"),
+            task("Second", "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b"),
+        ];
+        let writer: TaskArrayWriter<Vec<u8>> = tasks.iter().collect();
+        let buffer = writer.into_inner().unwrap();
+        let parsed: Vec<Task> = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(parsed, tasks);
+
+        let writer: TaskArrayWriter<Vec<u8>> = Vec::<Task>::new().into_iter().collect();
+        assert_eq!(writer.into_inner().unwrap(), b"[]");
+    }
+    #[test]
+    fn open_once_after_error() {
+        /// Fails the second write only.
+        #[derive(Default)]
+        struct FailSecond {
+            buffer: Vec<u8>,
+            writes: usize,
+        }
+        impl Write for FailSecond {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.writes += 1;
+                if self.writes == 2 {
+                    return Err(io::Error::other("full"));
+                }
+                self.buffer.write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = TaskArrayWriter::new(FailSecond::default());
+        writer.extend(Some(task("First", "d67fce70-c0b6-43c5-affc-a21e64567d40")));
+        assert!(writer.error.is_some());
+        assert!(writer.close().is_err());
+        assert_eq!(writer.writer.buffer, b"[");
+    }
+    #[test]
+    fn stop_after_error() {
+        /// Holds `limit` bytes, then fails.
+        struct Limited<'a> {
+            buffer: &'a mut Vec<u8>,
+            limit: usize,
+        }
+        impl Write for Limited<'_> {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let n = buf.len().min(self.limit - self.buffer.len());
+                if n == 0 {
+                    return Err(io::Error::other("full"));
+                }
+                self.buffer.write(&buf[..n])
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let first = task("First", "d67fce70-c0b6-43c5-affc-a21e64567d40");
+        let second = task("Second", "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b");
+        let mut buffer = Vec::new();
+        let limit = first.to_json_string().len() + 20;
+        {
+            let mut writer = TaskArrayWriter::new(Limited {
+                buffer: &mut buffer,
+                limit,
+            });
+            writer.write(&first).unwrap();
+            // Fails partway through the task
+            assert!(writer.write(&second).is_err());
+            assert!(writer.write(&first).is_err());
+        }
+        // Nothing after the partial task, not even the closing bracket on drop
+        assert_eq!(buffer.len(), limit);
+        assert!(!buffer.ends_with(b"]"));
+
+        let mut buffer = Vec::new();
+        let mut writer = TaskArrayWriter::new(Limited {
+            buffer: &mut buffer,
+            limit,
+        });
+        writer.extend([&first, &second]);
+        assert!(writer.finish().is_err());
+        assert_eq!(buffer.len(), limit);
+    }
+    #[test]
+    fn import_batch() {
+        let mut batch = ImportBatch::new();
+        assert!(batch.is_empty());
+        assert_eq!(batch.to_json(), "[]");
+
+        let mut first = task("First", "d67fce70-c0b6-43c5-affc-a21e64567d40");
+        *first.id_mut() = Some(3);
+        *first.urgency_mut() = Some(4.2);
+        batch.push(first);
+        batch.extend(vec![
+            task("Second", "8b4e8a4f-2c3c-4b53-9e1a-6f9d6c3f1a2b"),
+            task("Third", "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f"),
+        ]);
+        assert_eq!(batch.len(), 3);
+
+        let json: serde_json::Value = serde_json::from_str(&batch.to_json()).unwrap();
+        let array = json.as_array().unwrap();
+        assert_eq!(array.len(), 3);
+        assert_eq!(array[0]["description"], "First");
+        assert_eq!(array[2]["uuid"], "0c8d6a2e-5a9b-4f3e-8d7c-1b2a3c4d5e6f");
+        // Sanitized
+        assert!(array[0].get("id").is_none());
+        assert!(array[0].get("urgency").is_none());
+
+        let parsed: Vec<Task> = serde_json::from_str(&batch.to_json()).unwrap();
+        assert_eq!(parsed, batch.tasks());
+    }
+}
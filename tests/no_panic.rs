@@ -0,0 +1,76 @@
+//! Conversions that must not panic, whatever the task holds.
+//!
+//! A panic aborts the hook process, so fallible input should be an error and serialization should
+//! always succeed.
+use tasklib::prelude::*;
+
+const TASK: &str = r#"
+{
+    "uuid": "d67fce70-c0b6-43c5-affc-a21e64567d40",
+    "description": "Task to do.",
+    "status": "pending",
+    "entry": "20220131T083000Z",
+    "modified": "20220131T083000Z"
+}
+"#;
+
+fn task() -> Task {
+    TASK.parse().unwrap()
+}
+
+#[test]
+fn non_finite_numbers() {
+    for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        let mut task = task();
+        *task.urgency_mut() = Some(value);
+        *task.imask_mut() = Some(value);
+        task.udas_mut()
+            .insert("estimate".to_string(), UdaValue::Numeric(value));
+
+        // JSON has no representation for these, so they're written as null
+        let json = task.to_json_string();
+        assert!(json.contains(r#""imask":null"#), "{json}");
+        assert!(task.to_json().is_object());
+        let _ = String::from(task);
+    }
+}
+
+#[test]
+fn huge_strings() {
+    let mut task = task();
+    *task.description_mut() = "\"\\\u{0}\u{1f}\u{2028}✓".repeat(100_000);
+    *task.project_mut() = "a.".repeat(100_000);
+    *task.tags_mut() = (0..10_000).map(|i| format!("tag{i}")).collect();
+    task.udas_mut()
+        .insert("x".repeat(100_000), UdaValue::String("y".repeat(100_000)));
+
+    let json = task.to_json_string();
+    assert_eq!(json.parse::<Task>().unwrap(), task);
+}
+
+#[test]
+fn invalid_input_is_an_error() {
+    // Malformed dates
+    let json = TASK.replace("20220131T083000Z", "2022-01-31");
+    assert!(json.parse::<Task>().is_err());
+    let json = TASK.replace(r#""status""#, r#""due": "tomorrow", "status""#);
+    assert!(json.parse::<Task>().is_err());
+    // Durations too large to represent
+    for duration in [
+        "99999999999 seconds",
+        "4294967295 years",
+        "P4294967295Y",
+        "P1Y4294967295D",
+        "P1W4294967295D",
+        "999999999 weeks",
+        "99999999 quarters",
+        "99999999999 weekdays",
+    ] {
+        assert!(duration.parse::<Duration>().is_err(), "{duration}");
+    }
+    let json = TASK.replace(r#""status""#, r#""recur": "P1Y4294967295D", "status""#);
+    assert!(json.parse::<Task>().is_err());
+    assert!(Duration::try_from(UdaValue::String("P1Y4294967295D".to_string())).is_err());
+    assert!(TaskBuilder::new().try_uuid("not a uuid").is_err());
+    assert!(TaskBuilder::new().try_parent("not a uuid").is_err());
+}